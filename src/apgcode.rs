@@ -0,0 +1,185 @@
+use crate::census::{classify, Classification};
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+
+/// Column values of extended Wechsler format, bit 0 is the top row of a strip.
+const COLUMNS: &str = "0123456789abcdefghijklmnopqrstuv";
+/// Characters after `y`, standing for 4 and more empty columns.
+const RUN_LENGTHS: &str = "0123456789abcdefghijklmnopqrstuvwxyz";
+const STRIP_HEIGHT: i32 = 5;
+
+/// Canonical apgcode like `xs4_33` or `xq4_153` of still life, oscillator or spaceship,
+/// `None` for objects which don't repeat. Shortest code among every phase and orientation
+/// wins, ties are broken alphabetically.
+pub fn encode(object: &Pattern, rule: &Rule) -> Option<String> {
+    let (prefix, period) = match classify(object, rule).0 {
+        Classification::StillLife => (format!("xs{}", object.population()), 1),
+        Classification::Oscillator { period } => (format!("xp{}", period), period),
+        Classification::Spaceship { period, .. } => (format!("xq{}", period), period),
+        Classification::Unstable => return None,
+    };
+    let mut phase = object.clone();
+    let mut best: Option<String> = None;
+    for _ in 0..period {
+        for transform in 0..8 {
+            let code = wechsler(&phase.transformed(transform));
+            let better = best
+                .as_ref()
+                .is_none_or(|best| (code.len(), &code) < (best.len(), best));
+            if better {
+                best = Some(code);
+            }
+        }
+        phase = phase.step(rule);
+    }
+    best.map(|code| format!("{}_{}", prefix, code))
+}
+
+/// Extended Wechsler format of the pattern moved to 0,0: strips of 5 rows separated by `z`,
+/// each column of a strip as one character, runs of empty columns shortened by `w`, `x`, `y`.
+pub fn wechsler(pattern: &Pattern) -> String {
+    let (pattern, _) = pattern.normalized();
+    let bounds = match pattern.bounds() {
+        Some(bounds) => bounds,
+        None => return String::new(),
+    };
+    let columns: Vec<char> = COLUMNS.chars().collect();
+    let mut strips = Vec::new();
+    for top in (0..=bounds.max_y).step_by(STRIP_HEIGHT as usize) {
+        let mut strip = String::new();
+        let mut empty = 0;
+        for x in 0..=bounds.max_x {
+            let value = (0..STRIP_HEIGHT)
+                .filter(|row| pattern.contains(x, top + row))
+                .fold(0, |value, row| value | 1 << row);
+            if value == 0 {
+                empty += 1;
+                continue;
+            }
+            strip.push_str(&empty_columns(empty));
+            empty = 0;
+            strip.push(columns[value]);
+        }
+        strips.push(strip);
+    }
+    strips.join("z")
+}
+
+/// Shortest way to write `count` empty columns.
+fn empty_columns(mut count: usize) -> String {
+    let run_lengths: Vec<char> = RUN_LENGTHS.chars().collect();
+    let longest = 4 + run_lengths.len() - 1;
+    let mut text = String::new();
+    while count > 0 {
+        match count {
+            1 => text.push('0'),
+            2 => text.push('w'),
+            3 => text.push('x'),
+            _ => {
+                let run = count.min(longest);
+                text.push('y');
+                text.push(run_lengths[run - 4]);
+                count -= run;
+                continue;
+            }
+        }
+        break;
+    }
+    text
+}
+
+/// Cells of apgcode with `xs`, `xp` or `xq` prefix, top left corner at 0,0.
+pub fn decode(code: &str) -> Result<Pattern, String> {
+    let code = code.trim();
+    let (prefix, body) = code
+        .split_once('_')
+        .ok_or_else(|| format!("Apgcode {:?} has no prefix like xs4_", code))?;
+    let kind_is_known = ["xs", "xp", "xq"].iter().any(|kind| {
+        prefix
+            .strip_prefix(kind)
+            .is_some_and(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+    });
+    if !kind_is_known {
+        return Err(format!(
+            "Apgcode {:?} must start with xs, xp or xq followed by number",
+            code
+        ));
+    }
+    let mut cells = Vec::new();
+    let (mut x, mut top) = (0, 0);
+    let mut chars = body.chars().enumerate();
+    while let Some((index, c)) = chars.next() {
+        let column = prefix.len() + 2 + index;
+        match c {
+            'w' => x += 2,
+            'x' => x += 3,
+            'y' => match chars.next() {
+                Some((_, run)) if RUN_LENGTHS.contains(run) => {
+                    x += 4 + RUN_LENGTHS.find(run).unwrap_or(0) as i32
+                }
+                _ => {
+                    return Err(format!(
+                        "Column {}: 'y' must be followed by 0-9 or a-z",
+                        column
+                    ))
+                }
+            },
+            'z' => {
+                x = 0;
+                top += STRIP_HEIGHT;
+            }
+            c => match COLUMNS.find(c) {
+                Some(value) => {
+                    for row in 0..STRIP_HEIGHT {
+                        if value & (1 << row) != 0 {
+                            cells.push((x, top + row));
+                        }
+                    }
+                    x += 1;
+                }
+                None => return Err(format!("Column {}: unexpected character {:?}", column, c)),
+            },
+        }
+    }
+    Ok(Pattern::new(cells).normalized().0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::apgcode::*;
+
+    #[test]
+    fn it_encodes_common_objects() {
+        let rule = Rule::conway();
+        let code = |rows: &str| encode(&Pattern::from_rows(rows), &rule);
+        assert_eq!(code("OO/OO").as_deref(), Some("xs4_33"));
+        assert_eq!(code(".O./..O/OOO").as_deref(), Some("xq4_153"));
+        assert_eq!(code("OOO").as_deref(), Some("xp2_7"));
+        assert_eq!(code(".OO./O..O/.OO.").as_deref(), Some("xs6_696"));
+        assert_eq!(code(".OO./O..O/.O.O/..O.").as_deref(), Some("xs7_2596"));
+        assert_eq!(code("OO../O.O./..O./..OO").as_deref(), Some("xs7_178c"));
+        assert_eq!(code(".O..O/O..../O...O/OOOO.").as_deref(), Some("xq4_6frc"));
+        assert_eq!(code(".O./OOO"), None);
+    }
+
+    #[test]
+    fn it_decodes_apgcode() {
+        assert_eq!(decode("xq4_153"), Ok(Pattern::from_rows("OOO/..O/.O.")));
+        // Two blocks 7 columns apart
+        assert_eq!(
+            decode("xs8_33y333"),
+            Ok(Pattern::from_rows("OO.......OO/OO.......OO"))
+        );
+        assert_eq!(decode("xs8_3z3"), Ok(Pattern::from_rows("O/O/./././O/O")));
+        assert!(decode("33").is_err());
+        assert_eq!(
+            decode("xs4_3!"),
+            Err("Column 6: unexpected character '!'".to_string())
+        );
+        let pulsar = "xp3_co9nas0san9oczgoldlo0oldlogz1047210127401";
+        let decoded = decode(pulsar).unwrap();
+        // Phase with most cells has the shortest code
+        assert_eq!(decoded.population(), 72);
+        assert_eq!(encode(&decoded, &Rule::conway()).as_deref(), Some(pulsar));
+    }
+}
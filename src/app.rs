@@ -1,9 +1,9 @@
-#[allow(unused_imports)]
 use std::time::Duration;
 
 use sdl2::event::Event;
-use sdl2::event::Event::{MouseButtonDown, Quit};
+use sdl2::event::Event::{MouseButtonDown, MouseMotion, Quit};
 use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
 use sdl2::render::Canvas;
 use sdl2::render::TextureCreator;
 use sdl2::video::{Window, WindowContext};
@@ -11,8 +11,10 @@ use sdl2::EventPump;
 use sdl2::Sdl;
 
 use crate::app_state::AppState;
+use crate::cli::Options;
 use crate::managers::FontManager;
 use crate::renderer::Renderer;
+use crate::timing::FrameClock;
 use sdl2::ttf::Sdl2TtfContext;
 
 pub type WindowCanvas = Canvas<Window>;
@@ -26,6 +28,10 @@ pub const CELL_SIZE: u32 = 20;
 pub const GRID_SIZE: u32 = 400;
 pub const TICKS_PER_SECOND: u32 = 60;
 pub const SECOND: u32 = 1_000_000_000u32;
+pub const FONT_PATH: &str = "./assets/fonts/Beyond Wonderland.ttf";
+pub const LABEL_FONT_SIZE: u16 = 20;
+/// Longest time to block waiting for input while nothing is simulated.
+pub const IDLE_WAIT_MS: u32 = 250;
 
 #[derive(Clone, Debug)]
 pub enum UpdateResult {
@@ -36,10 +42,11 @@ pub enum UpdateResult {
 pub struct App {
     sdl_context: Sdl,
     canvas: WindowCanvas,
+    vsync: bool,
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(vsync: bool) -> Self {
         let sdl_context = sdl2::init().unwrap();
 
         let video_subsystem = sdl_context.video().unwrap();
@@ -51,34 +58,65 @@ impl App {
             .build()
             .unwrap();
 
-        let canvas = window.into_canvas().accelerated().build().unwrap();
+        let canvas_builder = window.into_canvas().accelerated();
+        let canvas = if vsync {
+            canvas_builder.present_vsync().build().unwrap()
+        } else {
+            canvas_builder.build().unwrap()
+        };
 
         Self {
             sdl_context,
             canvas,
+            vsync,
         }
     }
 
-    pub fn run(&mut self) {
-        let sleep_time: Duration = Duration::new(0, SECOND as u32 / TICKS_PER_SECOND as u32);
+    pub fn run(&mut self, options: &Options) {
+        let timestep: Duration = Duration::new(0, SECOND / TICKS_PER_SECOND);
         let font_context: Sdl2TtfContext = sdl2::ttf::init().unwrap();
         let font_manager: FontManager = FontManager::new(&font_context);
         let mut event_pump: EventPump = self.sdl_context.event_pump().unwrap();
+        let text_input = self.sdl_context.video().unwrap().text_input();
+        text_input.stop();
 
         let texture_creator: WindowTextureCreator = self.canvas.texture_creator();
         let mut renderer: Renderer = Renderer::new(&font_context, &texture_creator);
 
-        let mut app_state: AppState = AppState::new(&mut renderer);
+        let mut app_state: AppState = AppState::new(&mut renderer, options);
+
+        let mut clock = FrameClock::new(timestep);
 
         'running: loop {
-            match self.handle_events(&mut event_pump, &mut app_state) {
-                UpdateResult::Stop => break 'running,
-                _ => {}
+            let idle = app_state.is_idle();
+            if let UpdateResult::Stop = self.handle_events(&mut event_pump, &mut app_state, idle) {
+                break 'running;
+            }
+            if idle {
+                clock.skip_idle();
+            }
+
+            // Only collect typed text while a prompt is open, shortcuts would leak into it otherwise
+            if app_state.is_prompt_open() != text_input.is_active() {
+                if app_state.is_prompt_open() {
+                    text_input.start();
+                } else {
+                    text_input.stop();
+                }
             }
 
-            app_state.update();
+            for _ in 0..clock.begin_frame() {
+                app_state.update();
+            }
+            app_state.set_frame_stats(clock.stats());
             app_state.render(&mut self.canvas, &mut renderer);
-            ::std::thread::sleep(sleep_time);
+            app_state.take_screenshot(&mut self.canvas, &mut renderer);
+            clock.end_frame(app_state.generation());
+
+            // With vsync present already waits for the display
+            if !self.vsync && !app_state.is_idle() {
+                ::std::thread::sleep(clock.until_next_update());
+            }
         }
     }
 
@@ -86,20 +124,47 @@ impl App {
         &mut self,
         event_pump: &mut EventPump,
         app_state: &mut AppState,
+        idle: bool,
     ) -> UpdateResult {
-        for event in event_pump.poll_iter() {
-            match event {
-                Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => return UpdateResult::Stop,
-                MouseButtonDown { x, y, .. } => {
-                    return app_state.handle_click(x, y);
+        if idle {
+            if let Some(event) = event_pump.wait_event_timeout(IDLE_WAIT_MS) {
+                if let UpdateResult::Stop = self.handle_event(event, app_state) {
+                    return UpdateResult::Stop;
                 }
-                _ => UpdateResult::NoOp,
-            };
+            }
+        }
+        for event in event_pump.poll_iter() {
+            if let UpdateResult::Stop = self.handle_event(event, app_state) {
+                return UpdateResult::Stop;
+            }
         }
         UpdateResult::NoOp
     }
+
+    fn handle_event(&mut self, event: Event, app_state: &mut AppState) -> UpdateResult {
+        match event {
+            Quit { .. } => UpdateResult::Stop,
+            Event::KeyDown {
+                keycode: Some(Keycode::Escape),
+                ..
+            } if !app_state.is_prompt_open() => UpdateResult::Stop,
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => app_state.handle_key(keycode),
+            Event::TextInput { text, .. } => app_state.handle_text(&text),
+            MouseButtonDown {
+                mouse_btn: MouseButton::Right,
+                x,
+                y,
+                ..
+            } => app_state.start_selection(x, y),
+            MouseButtonDown { x, y, .. } => app_state.handle_click(x, y),
+            MouseMotion {
+                mousestate, x, y, ..
+            } if mousestate.right() => app_state.drag_selection(x, y),
+            MouseMotion { x, y, .. } => app_state.move_cursor(x, y),
+            _ => UpdateResult::NoOp,
+        }
+    }
 }
@@ -1,19 +1,62 @@
+use sdl2::keyboard::Keycode;
 use sdl2::pixels::Color;
 use sdl2::rect::{ Rect, Point };
 
-use crate::map::Map;
+use crate::apgcode;
+
+use crate::bitmap::ImageImport;
+
+use crate::cell_state::CellState;
+
+use crate::census::CensusPanel;
+
+use crate::cli::Options;
+
+use crate::explore::{RuleList, RulesPanel};
+
+use crate::gallery::{Gallery, GalleryGrid, GRID_TILES};
+
+use crate::genetic::{self, Evolution, GeneticSearch};
+
+use crate::graph::PopulationGraph;
+
+use crate::managers::{BitmapLoader, ResourceLoader};
+
+use crate::map::{Map, Topology};
 
 use crate::button::Button;
 
+use crate::prompt::{Prompt, PromptKind};
+
 use crate::renderer::{Render, Renderer};
 
-use crate::cell_state::CellState;
+use crate::pattern::Pattern;
+
+use crate::pattern_file::PatternFile;
+
+use crate::rule::Rule;
+
+use crate::screenshot::{save_png, Poster, PIXEL_FORMAT};
+
+use crate::session::Session;
+
+use crate::simulation::{SimCommand, SimulationHandle};
+
+use crate::svg::{self, Svg};
+
+use crate::soup::Soup;
+
+use crate::stability::OnStable;
+
+use crate::statistics::BoundingBox;
+
+use crate::timing::FrameStats;
 
 use crate::app::CELL_SIZE;
 use crate::app::GRID_SIZE;
 use crate::app::LEFT_MARGIN;
-use crate::app::TICKS_PER_SECOND;
 use crate::app::TOP_MARGIN;
+use crate::app::{WINDOW_HEIGHT, WINDOW_WIDTH};
 use crate::app::{UpdateResult, WindowCanvas};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -22,19 +65,45 @@ pub enum AppStatus {
     Paused,
 }
 
-#[derive(Clone)]
 pub struct AppState<'a> {
-    map: Map,
+    simulation: SimulationHandle,
     pause_button: Button<'a>,
     run_button: Button<'a>,
-    status: AppStatus,
-    logic_counter: i32,
+    step_button: Button<'a>,
+    slower_button: Button<'a>,
+    faster_button: Button<'a>,
+    randomize_button: Button<'a>,
+    soup: Soup,
+    gallery: Option<Gallery>,
+    gallery_index: usize,
+    /// Page of gallery thumbnails shown over the board.
+    grid_page: Option<usize>,
+    rules: Option<RuleList>,
+    rule_index: usize,
+    show_rules: bool,
+    /// Cell where selection started and cell it was dragged to.
+    selection: Option<((i32, i32), (i32, i32))>,
+    /// Cell under mouse pointer, where pasted objects go.
+    cursor: Option<(i32, i32)>,
+    /// Where the view is saved after it is drawn next time.
+    screenshot: Option<String>,
+    poster: Poster,
+    svg: Svg,
+    image_import: ImageImport,
+    genetic_search: GeneticSearch,
+    evolution: Option<Evolution>,
+    /// Latest generation of running genetic search, its best candidate is on the board.
+    evolution_progress: Option<genetic::Progress>,
+    threads: usize,
+    prompt: Option<Prompt>,
+    show_census: bool,
+    frame_stats: FrameStats,
 }
 
 impl<'a> AppState<'a> {
-    pub fn new(renderer: &mut Renderer<'a, 'a>) -> Self {
-        Self {
-            map: Map::new(),
+    pub fn new(renderer: &mut Renderer<'a, 'a>, options: &Options) -> Self {
+        let mut app_state = Self {
+            simulation: options.simulation(OnStable::Continue).spawn(),
             pause_button: Button::new(
                 renderer,
                 "Pause",
@@ -49,93 +118,772 @@ impl<'a> AppState<'a> {
                 Color::RGBA(0, 255, 0, 255),
                 Color::RGBA(0, 0, 0, 255),
             ),
-            status: AppStatus::Paused,
-            logic_counter: AppState::default_logic_counter(),
-        }
+            step_button: Button::new(
+                renderer,
+                "Step",
+                Rect::new(200, 510, 190, 70),
+                Color::RGBA(100, 200, 255, 255),
+                Color::RGBA(0, 0, 0, 255),
+            ),
+            slower_button: Button::new(
+                renderer,
+                "Slower",
+                Rect::new(610, 510, 85, 70),
+                Color::RGBA(255, 150, 100, 255),
+                Color::RGBA(0, 0, 0, 255),
+            ),
+            faster_button: Button::new(
+                renderer,
+                "Faster",
+                Rect::new(705, 510, 85, 70),
+                Color::RGBA(255, 150, 100, 255),
+                Color::RGBA(0, 0, 0, 255),
+            ),
+            randomize_button: Button::new(
+                renderer,
+                "Randomize",
+                Rect::new(10, 510, 180, 70),
+                Color::RGBA(200, 150, 255, 255),
+                Color::RGBA(0, 0, 0, 255),
+            ),
+            soup: options.soup(&Map::new()),
+            gallery: options.gallery.as_ref().and_then(|path| match Gallery::load(path) {
+                Ok(gallery) => Some(gallery),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    None
+                }
+            }),
+            gallery_index: 0,
+            grid_page: None,
+            rules: options.rules.as_ref().and_then(|path| match RuleList::load(path) {
+                Ok(rules) => Some(rules),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    None
+                }
+            }),
+            rule_index: 0,
+            show_rules: options.session.as_ref().is_some_and(|session| session.show_rules),
+            selection: None,
+            cursor: None,
+            screenshot: None,
+            poster: options.poster(),
+            svg: options.svg(),
+            image_import: options.image_import(),
+            genetic_search: options.genetic_search(),
+            evolution: None,
+            evolution_progress: None,
+            threads: options.threads,
+            prompt: None,
+            show_census: options.session.as_ref().is_some_and(|session| session.show_census),
+            frame_stats: FrameStats::default(),
+        };
+        app_state.browse_gallery(0);
+        app_state
     }
 
     pub fn render(&self, canvas: &mut WindowCanvas, renderer: &mut Renderer<'a, 'a>) {
+        self.draw(canvas, renderer);
+        renderer.present(canvas);
+    }
+
+    /// Draws the whole view without presenting it, so it can go to offscreen target too.
+    fn draw(&self, canvas: &mut WindowCanvas, renderer: &mut Renderer<'a, 'a>) {
+        let snapshot = self.simulation.snapshot();
         renderer.clear(canvas);
-        self.map.render(canvas, renderer);
-        match self.status {
+        snapshot.map.render(canvas, renderer);
+        match snapshot.status {
             AppStatus::Running => {
                 self.pause_button.render(canvas, renderer);
             }
             AppStatus::Paused => {
                 self.run_button.render(canvas, renderer);
+                self.step_button.render(canvas, renderer);
             }
         }
-        renderer.present(canvas);
+        self.slower_button.render(canvas, renderer);
+        self.faster_button.render(canvas, renderer);
+        self.randomize_button.render(canvas, renderer);
+        if let Some(grid) = self.gallery_grid() {
+            grid.render(canvas, renderer);
+        } else if let Some(area) = self.selection_area() {
+            renderer.render_frame(
+                canvas,
+                &Rect::new(
+                    LEFT_MARGIN as i32 + area.min_x * CELL_SIZE as i32,
+                    TOP_MARGIN as i32 + area.min_y * CELL_SIZE as i32,
+                    (area.max_x - area.min_x + 1) as u32 * CELL_SIZE,
+                    (area.max_y - area.min_y + 1) as u32 * CELL_SIZE,
+                ),
+                &Color::RGBA(0, 120, 255, 255),
+            );
+        }
+        let panel = Rect::new(610, TOP_MARGIN as i32, 180, GRID_SIZE);
+        match (self.rules_panel(), &snapshot.census) {
+            (Some(rules), _) => rules.render(canvas, renderer),
+            (None, Some(census)) if self.show_census => CensusPanel {
+                rect: panel,
+                census,
+            }
+            .render(canvas, renderer),
+            _ => PopulationGraph {
+                rect: panel,
+                series: &snapshot.recent_stats,
+            }
+            .render(canvas, renderer),
+        }
+        self.render_info(canvas, renderer);
+    }
+
+    /// Draws the view again onto offscreen texture and saves it as PNG, once a path was given.
+    pub fn take_screenshot(&mut self, canvas: &mut WindowCanvas, renderer: &mut Renderer<'a, 'a>) {
+        let path = match self.screenshot.take() {
+            Some(path) => path,
+            None => return,
+        };
+        let result = renderer
+            .texture_creator
+            .create_texture_target(PIXEL_FORMAT, WINDOW_WIDTH, WINDOW_HEIGHT)
+            .map_err(|e| e.to_string())
+            .and_then(|mut texture| {
+                let mut pixels = Err("Nothing was drawn".to_string());
+                canvas
+                    .with_texture_canvas(&mut texture, |target| {
+                        self.draw(target, renderer);
+                        pixels = target.read_pixels(None, PIXEL_FORMAT);
+                    })
+                    .map_err(|e| e.to_string())?;
+                pixels
+            })
+            .and_then(|mut pixels| save_png(&mut pixels, WINDOW_WIDTH, WINDOW_HEIGHT, &path));
+        match result {
+            Ok(()) => println!("Screenshot saved to {}", path),
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    fn render_info(&self, canvas: &mut WindowCanvas, renderer: &mut Renderer<'a, 'a>) {
+        let snapshot = self.simulation.snapshot();
+        let color = Color::RGBA(0, 0, 0, 255);
+        renderer.render_text(
+            canvas,
+            &format!("Generation {}", snapshot.generation),
+            Point::new(10, TOP_MARGIN as i32),
+            &color,
+        );
+        renderer.render_text(
+            canvas,
+            &format!("Speed {}", snapshot.speed),
+            Point::new(10, TOP_MARGIN as i32 + 30),
+            &color,
+        );
+        renderer.render_text(
+            canvas,
+            &format!("FPS {:.0}", self.frame_stats.fps),
+            Point::new(10, TOP_MARGIN as i32 + 60),
+            &color,
+        );
+        renderer.render_text(
+            canvas,
+            &format!("Measured {:.1} gen/s", self.frame_stats.generations_per_second),
+            Point::new(10, TOP_MARGIN as i32 + 90),
+            &color,
+        );
+        renderer.render_text(
+            canvas,
+            &format!("Rule {}", snapshot.rule),
+            Point::new(10, TOP_MARGIN as i32 + 120),
+            &color,
+        );
+        renderer.render_text(
+            canvas,
+            &format!("Topology {}", snapshot.map.topology()),
+            Point::new(10, TOP_MARGIN as i32 + 150),
+            &color,
+        );
+        renderer.render_text(
+            canvas,
+            &format!("Status {}", snapshot.stability),
+            Point::new(10, TOP_MARGIN as i32 + 180),
+            &color,
+        );
+        renderer.render_text(
+            canvas,
+            &format!("On stable {}", snapshot.on_stable),
+            Point::new(10, TOP_MARGIN as i32 + 210),
+            &color,
+        );
+        let soup = snapshot.soup.unwrap_or(self.soup);
+        renderer.render_text(
+            canvas,
+            &format!("Seed {} {}", soup.seed, soup.symmetry),
+            Point::new(10, TOP_MARGIN as i32 + 240),
+            &color,
+        );
+        if let Some(jump) = &snapshot.jump {
+            renderer.render_text(
+                canvas,
+                &format!(
+                    "Jumping to {} ({:.0}%)",
+                    jump.to,
+                    jump.progress(snapshot.generation) * 100.0
+                ),
+                Point::new(10, TOP_MARGIN as i32 + 270),
+                &color,
+            );
+        }
+        if let Some(progress) = &self.evolution_progress {
+            renderer.render_text(
+                canvas,
+                &format!(
+                    "Evolution {}: {} {}",
+                    progress.generation,
+                    self.genetic_search.fitness(),
+                    progress.best.score
+                ),
+                Point::new(10, TOP_MARGIN as i32 + 300),
+                &color,
+            );
+        }
+        if let Some(gallery) = &self.gallery {
+            if let Some(entry) = gallery.entries.get(self.gallery_index) {
+                renderer.render_text(
+                    canvas,
+                    &format!(
+                        "Gallery {}/{}: {}",
+                        self.gallery_index + 1,
+                        gallery.len(),
+                        entry.label
+                    ),
+                    Point::new(LEFT_MARGIN as i32, 10),
+                    &color,
+                );
+            }
+        }
+        if let Some(prompt) = &self.prompt {
+            renderer.render_text(
+                canvas,
+                &format!("{}: {}_", prompt.label(), prompt.input),
+                Point::new(LEFT_MARGIN as i32, 40),
+                &color,
+            );
+        } else if let Some(message) = &snapshot.message {
+            renderer.render_text(canvas, message, Point::new(LEFT_MARGIN as i32, 40), &color);
+        }
+    }
+
+    pub fn set_frame_stats(&mut self, frame_stats: FrameStats) {
+        self.frame_stats = frame_stats;
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.simulation.snapshot().generation
+    }
+
+    /// Nothing changes until user does something.
+    pub fn is_idle(&self) -> bool {
+        let snapshot = self.simulation.snapshot();
+        snapshot.status == AppStatus::Paused
+            && snapshot.jump.is_none()
+            && self.simulation.is_synchronized()
     }
 
     pub fn update(&mut self) {
-        match self.status {
-            AppStatus::Running => {
-                self.logic_counter -= 1;
-                if self.logic_counter < 0 {
-                    self.logic_counter = AppState::default_logic_counter();
-                    self.next_generation();
+        self.simulation.poll();
+        self.poll_evolution();
+    }
+
+    /// Starts genetic search on background thread, or stops the running one.
+    fn toggle_evolution(&mut self) {
+        if self.evolution.take().is_some() {
+            println!("Genetic search stopped");
+            return;
+        }
+        let rule = self.simulation.snapshot().rule;
+        let search = self.genetic_search.clone().with_rule(rule);
+        println!(
+            "Genetic search for {}, seed {}",
+            search.fitness(),
+            search.seed()
+        );
+        self.evolution_progress = None;
+        self.evolution = Some(search.spawn(self.threads));
+    }
+
+    /// Shows every new best candidate evolving on the board.
+    fn poll_evolution(&mut self) {
+        let progress = match self.evolution.as_mut().and_then(|evolution| evolution.poll()) {
+            Some(progress) => progress,
+            None => return,
+        };
+        println!("{}", progress);
+        let improved = self
+            .evolution_progress
+            .as_ref()
+            .is_none_or(|previous| previous.best != progress.best);
+        if improved {
+            self.simulation
+                .send(SimCommand::LoadPattern(progress.best.pattern.clone()));
+            self.simulation.send(SimCommand::Run);
+        }
+        self.evolution_progress = Some(progress);
+    }
+
+    /// Fills board with soup of current settings and given seed.
+    fn load_soup(&mut self, seed: u64) {
+        self.soup = self.soup.with_seed(seed);
+        println!("Soup {}", self.soup);
+        self.simulation.send(SimCommand::LoadSoup(self.soup));
+    }
+
+    /// Loads gallery entry `offset` places from current one onto the board.
+    fn browse_gallery(&mut self, offset: isize) {
+        let gallery = match &self.gallery {
+            Some(gallery) if !gallery.is_empty() => gallery,
+            _ => return,
+        };
+        let count = gallery.len() as isize;
+        self.gallery_index = (self.gallery_index as isize + offset).rem_euclid(count) as usize;
+        let entry = &gallery.entries[self.gallery_index];
+        if let Some(rule) = gallery.rule {
+            if rule != self.simulation.snapshot().rule {
+                self.simulation.send(SimCommand::SetRule(rule));
+            }
+        }
+        self.simulation
+            .send(SimCommand::LoadPattern(entry.pattern.clone()));
+    }
+
+    fn gallery_grid(&self) -> Option<GalleryGrid<'_>> {
+        match (&self.gallery, self.grid_page) {
+            (Some(gallery), Some(page)) => Some(GalleryGrid {
+                rect: Rect::new(LEFT_MARGIN as i32, TOP_MARGIN as i32, GRID_SIZE, GRID_SIZE),
+                gallery,
+                page,
+            }),
+            _ => None,
+        }
+    }
+
+    fn turn_grid_page(&mut self, offset: isize) {
+        if let (Some(gallery), Some(page)) = (&self.gallery, self.grid_page) {
+            let pages = GalleryGrid::pages(gallery) as isize;
+            self.grid_page = Some((page as isize + offset).rem_euclid(pages) as usize);
+        }
+    }
+
+    fn rules_panel(&self) -> Option<RulesPanel<'_>> {
+        match &self.rules {
+            Some(list) if self.show_rules && !list.is_empty() => Some(RulesPanel {
+                rect: Rect::new(610, TOP_MARGIN as i32, 180, GRID_SIZE),
+                list,
+                selected: self.rule_index,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Switches to rule of the list and tries it on a fresh soup.
+    fn select_rule(&mut self, index: usize) {
+        let report = match &self.rules {
+            Some(list) if index < list.len() => list.reports[index],
+            _ => return,
+        };
+        self.rule_index = index;
+        println!("Rule {}: {}", index + 1, report);
+        self.simulation.send(SimCommand::SetRule(report.rule));
+        self.load_soup(Soup::random_seed());
+    }
+
+    pub fn is_prompt_open(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    pub fn handle_text(&mut self, text: &str) -> UpdateResult {
+        if let Some(prompt) = &mut self.prompt {
+            prompt.push(text);
+        }
+        UpdateResult::NoOp
+    }
+
+    pub fn handle_key(&mut self, keycode: Keycode) -> UpdateResult {
+        if self.prompt.is_some() {
+            return self.handle_prompt_key(keycode);
+        }
+        match keycode {
+            Keycode::Space => {
+                let command = match self.simulation.snapshot().status {
+                    AppStatus::Running => SimCommand::Pause,
+                    AppStatus::Paused => SimCommand::Run,
+                };
+                self.simulation.send(command);
+            }
+            Keycode::N | Keycode::Right => self.simulation.send(SimCommand::Step),
+            Keycode::Plus | Keycode::Equals | Keycode::KpPlus => {
+                self.simulation.send(SimCommand::Faster)
+            }
+            Keycode::Minus | Keycode::KpMinus => self.simulation.send(SimCommand::Slower),
+            Keycode::G => self.prompt = Some(Prompt::new(PromptKind::JumpToGeneration)),
+            Keycode::R => self.prompt = Some(Prompt::new(PromptKind::Rule)),
+            Keycode::E => self.prompt = Some(Prompt::new(PromptKind::ExportStatistics)),
+            Keycode::C => {
+                self.show_census = !self.show_census;
+                if self.show_census {
+                    self.simulation.send(SimCommand::TakeCensus);
+                }
+            }
+            Keycode::O => self.prompt = Some(Prompt::new(PromptKind::ExportCensus)),
+            Keycode::I => self.prompt = Some(Prompt::new(PromptKind::ImportPattern)),
+            Keycode::X => self.prompt = Some(Prompt::new(PromptKind::ExportPattern)),
+            Keycode::D => self.prompt = Some(Prompt::new(PromptKind::Apgcode)),
+            Keycode::W => self.prompt = Some(Prompt::new(PromptKind::SaveSession)),
+            Keycode::U => self.prompt = Some(Prompt::new(PromptKind::LoadSession)),
+            Keycode::F => self.prompt = Some(Prompt::new(PromptKind::Screenshot)),
+            Keycode::B => self.prompt = Some(Prompt::new(PromptKind::Poster)),
+            Keycode::Z => self.prompt = Some(Prompt::new(PromptKind::Svg)),
+            Keycode::LeftBracket if self.grid_page.is_some() => self.turn_grid_page(-1),
+            Keycode::RightBracket if self.grid_page.is_some() => self.turn_grid_page(1),
+            Keycode::LeftBracket => self.browse_gallery(-1),
+            Keycode::RightBracket => self.browse_gallery(1),
+            Keycode::M if self.gallery.is_some() => {
+                self.grid_page = match self.grid_page {
+                    Some(_) => None,
+                    None => Some(self.gallery_index / (GRID_TILES * GRID_TILES)),
                 }
             }
-            AppStatus::Paused => {}
+            Keycode::P => self.find_parent(),
+            Keycode::L => self.simulation.send(SimCommand::MeasureLifespan),
+            Keycode::V => self.toggle_evolution(),
+            Keycode::K => self.show_rules = !self.show_rules,
+            Keycode::Up if self.rules_panel().is_some() => {
+                self.select_rule(self.rule_index.saturating_sub(1))
+            }
+            Keycode::Down if self.rules_panel().is_some() => self.select_rule(self.rule_index + 1),
+            Keycode::S => self.prompt = Some(Prompt::new(PromptKind::SoupSeed)),
+            Keycode::Y => {
+                self.soup.symmetry = self.soup.symmetry.next();
+                self.load_soup(self.soup.seed);
+            }
+            Keycode::T => {
+                let topology = match self.simulation.snapshot().map.topology() {
+                    Topology::Bounded => Topology::Torus,
+                    Topology::Torus => Topology::Bounded,
+                };
+                self.simulation.send(SimCommand::SetTopology(topology));
+            }
+            Keycode::A => {
+                let on_stable = self.simulation.snapshot().on_stable.next();
+                self.simulation.send(SimCommand::SetOnStable(on_stable));
+            }
+            _ => {}
         }
+        UpdateResult::NoOp
     }
 
-    fn next_generation(&mut self) {
-        for y in 0..20 {
-            for x in 0..20 {
-                let mut neighbours = self.map.get_neighbours(x, y);
-                let mut s = 0;
-                for neighbour in &mut neighbours {
-                    if *neighbour == CellState::Alive {
-                        s += 1;
-                    }
+    fn handle_prompt_key(&mut self, keycode: Keycode) -> UpdateResult {
+        match keycode {
+            Keycode::Escape => self.prompt = None,
+            Keycode::Backspace => {
+                if let Some(prompt) = &mut self.prompt {
+                    prompt.pop();
                 }
-                match self.map.get_at(x, y) {
-                    CellState::Alive => {
-                        let s = s - 1;
-                        if s != 3 && s != 2 {
-                            self.map.set_dead(x, y);
-                        }
-                    }
-                    CellState::Dead => {
-                        if s == 3 {
-                            self.map.set_alive(x, y);
-                        }
+            }
+            Keycode::Return | Keycode::KpEnter => {
+                if let Some(prompt) = self.prompt.take() {
+                    self.submit_prompt(prompt);
+                }
+            }
+            _ => {}
+        }
+        UpdateResult::NoOp
+    }
+
+    fn submit_prompt(&mut self, prompt: Prompt) {
+        match prompt.kind {
+            PromptKind::JumpToGeneration => match prompt.input.parse::<u64>() {
+                Ok(target) => self.simulation.send(SimCommand::JumpTo(target)),
+                Err(e) => println!("Invalid generation {:?}: {}", prompt.input, e),
+            },
+            PromptKind::Rule => match prompt.input.parse::<Rule>() {
+                Ok(rule) => self.simulation.send(SimCommand::SetRule(rule)),
+                Err(e) => println!("Invalid rule {:?}: {}", prompt.input, e),
+            },
+            PromptKind::ExportStatistics => {
+                if !prompt.input.is_empty() {
+                    self.simulation
+                        .send(SimCommand::ExportStatistics(prompt.input));
+                }
+            }
+            PromptKind::ExportCensus => {
+                if !prompt.input.is_empty() {
+                    self.simulation.send(SimCommand::ExportCensus(prompt.input));
+                }
+            }
+            PromptKind::SoupSeed => match prompt.input.parse::<u64>() {
+                Ok(seed) => self.load_soup(seed),
+                Err(e) => println!("Invalid seed {:?}: {}", prompt.input, e),
+            },
+            PromptKind::ImportPattern => self.import_pattern(&prompt.input),
+            PromptKind::ExportPattern => {
+                if !prompt.input.is_empty() {
+                    self.simulation.send(SimCommand::ExportPattern(prompt.input));
+                }
+            }
+            PromptKind::Apgcode => match apgcode::decode(&prompt.input) {
+                Ok(object) => self.paste(object),
+                Err(e) => println!("Invalid apgcode {:?}: {}", prompt.input, e),
+            },
+            PromptKind::SaveSession => {
+                if !prompt.input.is_empty() {
+                    self.save_session(&prompt.input);
+                }
+            }
+            PromptKind::Screenshot => {
+                if !prompt.input.is_empty() {
+                    self.screenshot = Some(prompt.input);
+                }
+            }
+            PromptKind::Poster => {
+                if !prompt.input.is_empty() {
+                    let map = &self.simulation.snapshot().map;
+                    match self.poster.save(map, &prompt.input) {
+                        Ok(()) => println!("Poster saved to {}", prompt.input),
+                        Err(e) => println!("{}", e),
                     }
                 }
             }
+            PromptKind::Svg => {
+                if !prompt.input.is_empty() {
+                    self.save_svg(&prompt.input);
+                }
+            }
+            PromptKind::LoadSession => match Session::load(&prompt.input) {
+                Ok(session) => {
+                    self.show_census = session.show_census;
+                    self.show_rules = session.show_rules;
+                    self.poster = session.svg.poster.clone();
+                    self.svg = session.svg.clone();
+                    self.simulation.send(SimCommand::LoadSession(Box::new(session)));
+                    println!("Session loaded from {}", prompt.input);
+                }
+                Err(e) => println!("{}", e),
+            },
         }
     }
 
-    fn default_logic_counter() -> i32 {
-        (TICKS_PER_SECOND / 2) as i32
+    /// Saves board and simulation as seen in latest snapshot along with visible panels.
+    fn save_session(&self, path: &str) {
+        let session = Session {
+            show_census: self.show_census,
+            show_rules: self.show_rules,
+            svg: Svg::new(self.poster.clone()).with_shape(self.svg.shape),
+            ..Session::from_snapshot(self.simulation.snapshot())
+        };
+        match session.save(path) {
+            Ok(()) => println!("Session saved to {}", path),
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    /// Saves selection, or whole board without one, as seen in latest snapshot.
+    fn save_svg(&self, path: &str) {
+        let snapshot = self.simulation.snapshot();
+        let area = self
+            .selection_area()
+            .unwrap_or_else(|| svg::board_area(&snapshot.map));
+        match self
+            .svg
+            .save(&snapshot.map, &area, &snapshot.rule, snapshot.generation, path)
+        {
+            Ok(()) => println!("SVG saved to {}", path),
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    /// Adds object centered on the cell under mouse pointer, or on the middle of board.
+    fn paste(&mut self, object: Pattern) {
+        let map = &self.simulation.snapshot().map;
+        let (x, y) = self
+            .cursor
+            .unwrap_or((map.width() / 2, map.height() / 2));
+        let (width, height) = object
+            .bounds()
+            .map_or((0, 0), |bounds| (bounds.max_x + 1, bounds.max_y + 1));
+        self.simulation
+            .send(SimCommand::PlacePattern(object, x - width / 2, y - height / 2));
+    }
+
+    /// Loads pattern file onto the board and switches to rule and topology from its header.
+    fn import_pattern(&mut self, path: &str) {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".png") || lower.ends_with(".bmp") {
+            self.import_image(path);
+            return;
+        }
+        let file = match PatternFile::load(path) {
+            Ok(file) => file,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        if let Some(rule) = file.rule {
+            self.simulation.send(SimCommand::SetRule(rule));
+        }
+        if let Some(topology) = file.topology {
+            self.simulation.send(SimCommand::SetTopology(topology));
+        }
+        // Board is rebuilt on simulation thread, a copy tells whether the pattern fits
+        let mut map = Map::new();
+        let dropped = file.place_on(&mut map);
+        if dropped > 0 {
+            println!(
+                "{} cells of pattern don't fit on {}x{} board",
+                dropped,
+                map.width(),
+                map.height()
+            );
+        }
+        println!("Loaded {}", file.name.as_deref().unwrap_or(path));
+        self.simulation.send(SimCommand::LoadPattern(file.pattern));
+    }
+
+    /// Converts image scaled to fit the board into new starting pattern.
+    fn import_image(&mut self, path: &str) {
+        let bitmap = match BitmapLoader.load(path) {
+            Ok(bitmap) => bitmap,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        let map = &self.simulation.snapshot().map;
+        let pattern = self.image_import.to_pattern(
+            &bitmap,
+            map.width() as usize,
+            map.height() as usize,
+        );
+        println!(
+            "Loaded {}x{} image {} as {} cells",
+            bitmap.width,
+            bitmap.height,
+            path,
+            pattern.population()
+        );
+        self.simulation.send(SimCommand::LoadPattern(pattern));
     }
 
     pub fn handle_click(&mut self, x: i32, y: i32) -> UpdateResult {
+        let entry = self
+            .gallery_grid()
+            .and_then(|grid| grid.entry_at(Point::new(x, y)));
+        if let Some(index) = entry {
+            self.grid_page = None;
+            self.gallery_index = index;
+            self.browse_gallery(0);
+            return UpdateResult::NoOp;
+        }
+        let rule = self
+            .rules_panel()
+            .and_then(|panel| panel.report_at(Point::new(x, y)));
+        if let Some(index) = rule {
+            self.select_rule(index);
+            return UpdateResult::NoOp;
+        }
         match (x, y) {
             _cell if self.is_cell(x, y) => {
                 let nx: usize = (x - LEFT_MARGIN as i32) as usize / CELL_SIZE as usize;
                 let ny: usize = (y - TOP_MARGIN as i32) as usize / CELL_SIZE as usize;
-                self.map.set_alive(nx as i32, ny as i32);
+                self.simulation
+                    .send(SimCommand::SetCell(nx as i32, ny as i32, CellState::Alive));
                 UpdateResult::NoOp
             }
             _run if self.is_run_button(x, y) => {
-                self.status = AppStatus::Running;
+                self.simulation.send(SimCommand::Run);
                 println!("Button clicked (run)");
                 UpdateResult::NoOp
 
             }
             _pause if self.is_pause_button(x, y) => {
-                self.status = AppStatus::Paused;
+                self.simulation.send(SimCommand::Pause);
                 println!("Button clicked (pause)");
                 UpdateResult::NoOp
 
             }
+            _step if self.is_step_button(x, y) => {
+                self.simulation.send(SimCommand::Step);
+                println!("Button clicked (step)");
+                UpdateResult::NoOp
+            }
+            _slower if self.slower_button.contains(Point::new(x, y)) => {
+                self.simulation.send(SimCommand::Slower);
+                println!("Button clicked (slower)");
+                UpdateResult::NoOp
+            }
+            _faster if self.faster_button.contains(Point::new(x, y)) => {
+                self.simulation.send(SimCommand::Faster);
+                println!("Button clicked (faster)");
+                UpdateResult::NoOp
+            }
+            _randomize if self.randomize_button.contains(Point::new(x, y)) => {
+                self.load_soup(Soup::random_seed());
+                println!("Button clicked (randomize)");
+                UpdateResult::NoOp
+            }
             _ => UpdateResult::NoOp,
         }
     }
 
+    /// Right click on board starts selection, anywhere else clears it.
+    pub fn start_selection(&mut self, x: i32, y: i32) -> UpdateResult {
+        self.selection = self.cell_at(x, y).map(|cell| (cell, cell));
+        UpdateResult::NoOp
+    }
+
+    pub fn move_cursor(&mut self, x: i32, y: i32) -> UpdateResult {
+        self.cursor = self.cell_at(x, y);
+        UpdateResult::NoOp
+    }
+
+    pub fn drag_selection(&mut self, x: i32, y: i32) -> UpdateResult {
+        if let (Some((start, _)), Some(cell)) = (self.selection, self.cell_at(x, y)) {
+            self.selection = Some((start, cell));
+        }
+        UpdateResult::NoOp
+    }
+
+    fn selection_area(&self) -> Option<BoundingBox> {
+        self.selection.map(|((x1, y1), (x2, y2))| BoundingBox {
+            min_x: x1.min(x2),
+            min_y: y1.min(y2),
+            max_x: x1.max(x2),
+            max_y: y1.max(y2),
+        })
+    }
+
+    /// Selected cells or whole board when nothing is selected.
+    fn find_parent(&mut self) {
+        let map = &self.simulation.snapshot().map;
+        let area = self.selection_area().unwrap_or(BoundingBox {
+            min_x: 0,
+            min_y: 0,
+            max_x: map.width() - 1,
+            max_y: map.height() - 1,
+        });
+        self.simulation.send(SimCommand::FindParent(area));
+    }
+
+    fn cell_at(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        if !self.is_cell(x, y) {
+            return None;
+        }
+        Some((
+            (x - LEFT_MARGIN as i32) / CELL_SIZE as i32,
+            (y - TOP_MARGIN as i32) / CELL_SIZE as i32,
+        ))
+    }
+
     fn is_cell(&self, x: i32, y: i32) -> bool {
         x >= LEFT_MARGIN as i32
             && x < (GRID_SIZE + LEFT_MARGIN) as i32
@@ -144,16 +892,23 @@ impl<'a> AppState<'a> {
     }
 
     fn is_run_button(&self, x: i32, y: i32) -> bool {
-        match self.status {
+        match self.simulation.snapshot().status {
             AppStatus::Paused => self.run_button.contains(Point::new(x, y)),
             AppStatus::Running => false,
         }
     }
 
     fn is_pause_button(&self, x: i32, y: i32) -> bool {
-        match self.status {
+        match self.simulation.snapshot().status {
             AppStatus::Running => self.pause_button.contains(Point::new(x, y)),
             AppStatus::Paused => false,
         }
     }
+
+    fn is_step_button(&self, x: i32, y: i32) -> bool {
+        match self.simulation.snapshot().status {
+            AppStatus::Paused => self.step_button.contains(Point::new(x, y)),
+            AppStatus::Running => false,
+        }
+    }
 }
@@ -0,0 +1,252 @@
+use std::fmt;
+use std::str::FromStr;
+
+use sdl2::surface::Surface;
+
+use crate::pattern::Pattern;
+use crate::screenshot::PIXEL_FORMAT;
+
+/// Darkness above which cells are alive.
+pub const DEFAULT_THRESHOLD: f64 = 0.5;
+/// 4x4 Bayer matrix, thresholds spread so every 16 neighbouring cells get all of them.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dither {
+    /// Each cell alone against the threshold, best for logos with flat colours.
+    Threshold,
+    /// Error of every cell is passed on to its unvisited neighbours.
+    FloydSteinberg,
+    /// Threshold varies with position in repeating Bayer matrix.
+    Ordered,
+}
+
+pub const DITHERS: [Dither; 3] = [Dither::Threshold, Dither::FloydSteinberg, Dither::Ordered];
+
+impl fmt::Display for Dither {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Dither::Threshold => write!(f, "threshold"),
+            Dither::FloydSteinberg => write!(f, "floyd-steinberg"),
+            Dither::Ordered => write!(f, "ordered"),
+        }
+    }
+}
+
+impl FromStr for Dither {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DITHERS
+            .iter()
+            .find(|dither| dither.to_string() == s.to_lowercase())
+            .cloned()
+            .ok_or_else(|| {
+                format!(
+                    "Unknown dithering {:?}, expected threshold, floyd-steinberg or ordered",
+                    s
+                )
+            })
+    }
+}
+
+/// Image as bytes in R, G, B, A order, row by row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bitmap {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Bitmap {
+    pub fn from_rgba(width: usize, height: usize, pixels: Vec<u8>) -> Self {
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Pixels of surface in any format, images are read by `BitmapLoader`.
+    pub fn from_surface(surface: &Surface) -> Result<Self, String> {
+        // Conversion borrows pixel format of a surface which has it
+        let format = Surface::new(1, 1, PIXEL_FORMAT)?;
+        let surface = surface.convert(&format.pixel_format())?;
+        let (width, height) = (surface.width() as usize, surface.height() as usize);
+        let pitch = surface.pitch() as usize;
+        let pixels = surface.with_lock(|bytes| {
+            (0..height)
+                .flat_map(|y| bytes[y * pitch..y * pitch + width * 4].iter().cloned())
+                .collect()
+        });
+        if width == 0 || height == 0 {
+            return Err("image has no pixels".to_string());
+        }
+        Ok(Self::from_rgba(width, height, pixels))
+    }
+
+    /// 0 for white or transparent pixel to 1 for black, transparency is laid over white.
+    pub fn darkness(&self, x: usize, y: usize) -> f64 {
+        let pixel = &self.pixels[(y * self.width + x) * 4..][..4];
+        let luminance =
+            (0.299 * pixel[0] as f64 + 0.587 * pixel[1] as f64 + 0.114 * pixel[2] as f64) / 255.0;
+        (1.0 - luminance) * pixel[3] as f64 / 255.0
+    }
+
+    /// Size keeping aspect ratio which fills `width` or `height` and fits in the other.
+    pub fn fit(&self, width: usize, height: usize) -> (usize, usize) {
+        let scale = (width as f64 / self.width as f64).min(height as f64 / self.height as f64);
+        (
+            ((self.width as f64 * scale).round() as usize).clamp(1, width),
+            ((self.height as f64 * scale).round() as usize).clamp(1, height),
+        )
+    }
+
+    /// Average darkness of pixels covered by each of `width` by `height` cells.
+    pub fn scaled(&self, width: usize, height: usize) -> Vec<Vec<f64>> {
+        let span = |cell: usize, cells: usize, pixels: usize| {
+            let start = cell * pixels / cells;
+            let end = ((cell + 1) * pixels).div_ceil(cells);
+            start..end.max(start + 1)
+        };
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| {
+                        let (xs, ys) = (span(x, width, self.width), span(y, height, self.height));
+                        let count = (xs.len() * ys.len()) as f64;
+                        ys.flat_map(|py| xs.clone().map(move |px| (px, py)))
+                            .map(|(px, py)| self.darkness(px, py))
+                            .sum::<f64>()
+                            / count
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// How image becomes starting pattern: dark pixels are alive cells unless inverted.
+/// Rules have two states, so colours are only told apart by how dark they are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageImport {
+    pub dither: Dither,
+    pub threshold: f64,
+    /// Light pixels are alive, for light drawing on dark background.
+    pub invert: bool,
+}
+
+impl ImageImport {
+    pub fn new(dither: Dither) -> Self {
+        Self {
+            dither,
+            threshold: DEFAULT_THRESHOLD,
+            invert: false,
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn with_invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    /// Image scaled to fit `width` by `height` cells, pattern starts at 0,0.
+    pub fn to_pattern(&self, bitmap: &Bitmap, width: usize, height: usize) -> Pattern {
+        let (width, height) = bitmap.fit(width, height);
+        let mut values = bitmap.scaled(width, height);
+        if self.invert {
+            for value in values.iter_mut().flatten() {
+                *value = 1.0 - *value;
+            }
+        }
+        let mut cells = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let value = values[y][x];
+                let alive = match self.dither {
+                    Dither::Threshold => value > self.threshold,
+                    Dither::FloydSteinberg => {
+                        let alive = value > self.threshold;
+                        let error = value - alive as u8 as f64;
+                        let mut spread = |dx: isize, dy: usize, weight: f64| {
+                            let nx = x as isize + dx;
+                            if nx >= 0 && (nx as usize) < width && y + dy < height {
+                                values[y + dy][nx as usize] += error * weight / 16.0;
+                            }
+                        };
+                        spread(1, 0, 7.0);
+                        spread(-1, 1, 3.0);
+                        spread(0, 1, 5.0);
+                        spread(1, 1, 1.0);
+                        alive
+                    }
+                    Dither::Ordered => {
+                        let level = (BAYER[y % 4][x % 4] as f64 + 0.5) / 16.0;
+                        value + 0.5 - self.threshold > level
+                    }
+                };
+                if alive {
+                    cells.push((x as i32, y as i32));
+                }
+            }
+        }
+        Pattern::new(cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitmap::*;
+
+    fn gray(width: usize, height: usize, level: u8) -> Bitmap {
+        let pixels = (0..width * height)
+            .flat_map(|_| vec![level, level, level, 255])
+            .collect();
+        Bitmap::from_rgba(width, height, pixels)
+    }
+
+    #[test]
+    fn it_scales_image_to_fit_board() {
+        // Black top left quarter of white image
+        let mut bitmap = gray(4, 4, 255);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            bitmap.pixels[(y * 4 + x) * 4..][..3].copy_from_slice(&[0, 0, 0]);
+        }
+        let import = ImageImport::new(Dither::Threshold);
+        assert_eq!(import.to_pattern(&bitmap, 2, 2), Pattern::new(vec![(0, 0)]));
+        assert_eq!(import.to_pattern(&bitmap, 20, 20).population(), 10 * 10);
+        assert_eq!(
+            import
+                .clone()
+                .with_invert(true)
+                .to_pattern(&bitmap, 2, 2)
+                .population(),
+            3
+        );
+        assert_eq!(gray(8, 2, 0).fit(20, 20), (20, 5));
+        assert_eq!(gray(3, 300, 0).fit(20, 20), (1, 20));
+        // Transparent pixels are dead whatever their colour
+        let clear = Bitmap::from_rgba(1, 1, vec![0, 0, 0, 0]);
+        assert!(import.to_pattern(&clear, 20, 20).is_empty());
+    }
+
+    #[test]
+    fn it_dithers_gray() {
+        let bitmap = gray(8, 8, 128);
+        let count = |dither| {
+            ImageImport::new(dither)
+                .to_pattern(&bitmap, 8, 8)
+                .population()
+        };
+        // Just lighter than half gray, so every cell falls on the same side of threshold
+        assert_eq!(count(Dither::Threshold), 0);
+        assert_eq!(count(Dither::Ordered), 32);
+        assert_eq!(count(Dither::FloydSteinberg), 32);
+        assert_eq!("Floyd-Steinberg".parse(), Ok(Dither::FloydSteinberg));
+    }
+}
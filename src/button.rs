@@ -6,7 +6,7 @@ use sdl2::ttf::Font;
 
 use std::rc::Rc;
 
-use crate::app::WindowCanvas;
+use crate::app::{WindowCanvas, FONT_PATH};
 use crate::managers::FontDetails;
 use crate::renderer::{Render, Renderer};
 use crate::ui::render_text;
@@ -31,7 +31,7 @@ impl<'a> Button<'a> {
         text_color: Color,
     ) -> Self {
         let r = background_rect.clone();
-        let font_details = FontDetails::new("./assets/fonts/Beyond Wonderland.ttf", 30);
+        let font_details = FontDetails::new(FONT_PATH, 30);
         let tex: TextTexture<'a> = render_text(&font_details, text, &text_color, renderer);
         let text_src = Rect::new(r.x(), r.y(), tex.width, tex.height);
         let text_dest = Rect::new(r.x(), r.y(), tex.width, tex.height);
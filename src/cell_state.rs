@@ -2,6 +2,14 @@ use super::app::WindowCanvas;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 
+/// Frame around cells of the board in the window.
+pub const GRID_COLOR: Color = Color {
+    r: 200,
+    g: 200,
+    b: 200,
+    a: 255,
+};
+
 #[derive(Clone, Debug, Copy, PartialEq)]
 pub enum CellState {
     Alive,
@@ -9,12 +17,16 @@ pub enum CellState {
 }
 
 impl CellState {
-    pub fn draw(&self, rect: &Rect, canvas: &mut WindowCanvas) {
-        let color = match self {
+    /// Colour in the window, posters of light theme use it too.
+    pub fn color(&self) -> Color {
+        match self {
             CellState::Dead => Color::RGBA(220, 220, 220, 255),
             CellState::Alive => Color::RGBA(20, 20, 20, 255),
-        };
-        canvas.set_draw_color(color);
+        }
+    }
+
+    pub fn draw(&self, rect: &Rect, canvas: &mut WindowCanvas) {
+        canvas.set_draw_color(self.color());
         canvas.fill_rect(*rect).unwrap();
     }
 }
@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+
+use crate::apgcode;
+use crate::app::WindowCanvas;
+use crate::cell_state::CellState;
+use crate::map::{Map, Topology};
+use crate::pattern::Pattern;
+use crate::renderer::{Render, Renderer};
+use crate::rule::Rule;
+
+/// Cells this far apart or closer (in both axes) belong to the same object.
+pub const DEFAULT_INTERACTION_DISTANCE: i32 = 2;
+/// Longest period recognized when object is evolved in isolation.
+pub const MAX_PERIOD: u64 = 64;
+
+/// Common objects of B3/S23, any phase and orientation is recognized.
+pub const KNOWN_OBJECTS: &[(&str, &str)] = &[
+    ("block", "OO/OO"),
+    ("beehive", ".OO./O..O/.OO."),
+    ("loaf", ".OO./O..O/.O.O/..O."),
+    ("boat", "OO./O.O/.O."),
+    ("ship", "OO./O.O/.OO"),
+    ("tub", ".O./O.O/.O."),
+    ("pond", ".OO./O..O/O..O/.OO."),
+    ("barge", ".O../O.O./.O.O/..O."),
+    ("long boat", "OO../O.O./.O.O/..O."),
+    ("mango", ".OO../O..O./.O..O/..OO."),
+    ("aircraft carrier", "OO../O..O/..OO"),
+    ("snake", "OO.O/O.OO"),
+    ("eater", "OO../O.O./..O./..OO"),
+    ("blinker", "OOO"),
+    ("toad", ".OOO/OOO."),
+    ("beacon", "OO../OO../..OO/..OO"),
+    ("clock", "..O./O.O./.O.O/.O.."),
+    ("pulsar", "..OOO...OOO../............./O....O.O....O/O....O.O....O/O....O.O....O/..OOO...OOO../............./..OOO...OOO../O....O.O....O/O....O.O....O/O....O.O....O/............./..OOO...OOO.."),
+    ("pentadecathlon", "..O....O../OO.OOOO.OO/..O....O.."),
+    ("glider", ".O./..O/OOO"),
+    ("LWSS", ".O..O/O..../O...O/OOOO."),
+    ("MWSS", "...O../.O...O/O...../O....O/OOOOO."),
+    ("HWSS", "...OO../.O....O/O....../O.....O/OOOOOO."),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Classification {
+    StillLife,
+    Oscillator { period: u64 },
+    /// Displacement per period, `dx` is the larger one of both axes.
+    Spaceship { period: u64, dx: i32, dy: i32 },
+    /// Dies or doesn't repeat within `MAX_PERIOD` when left alone.
+    Unstable,
+}
+
+impl fmt::Display for Classification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Classification::StillLife => write!(f, "still life"),
+            Classification::Oscillator { period } => write!(f, "p{} oscillator", period),
+            Classification::Spaceship { period, dx, dy } => {
+                // Speed in lowest terms, LWSS moving 2 cells in 4 generations is c/2
+                let divisor = gcd(gcd(*dx as u64, *dy as u64), *period);
+                let (period, dx, dy) =
+                    (period / divisor, *dx as u64 / divisor, *dy as u64 / divisor);
+                let speed = if dx == 1 {
+                    format!("c/{}", period)
+                } else {
+                    format!("{}c/{}", dx, period)
+                };
+                match dy {
+                    0 => write!(f, "{} orthogonal spaceship", speed),
+                    dy if dy == dx => write!(f, "{} diagonal spaceship", speed),
+                    dy => write!(f, "({}, {})c/{} oblique spaceship", dx, dy, period),
+                }
+            }
+            Classification::Unstable => write!(f, "unstable"),
+        }
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CensusEntry {
+    pub name: Option<&'static str>,
+    pub classification: Classification,
+    /// Smallest canonical form among all phases.
+    pub key: Pattern,
+    pub count: usize,
+}
+
+impl CensusEntry {
+    pub fn label(&self) -> String {
+        match self.name {
+            Some(name) => name.to_string(),
+            None => format!("{} {} cells", self.classification, self.key.population()),
+        }
+    }
+
+    /// Compact identifier of the object, `None` for unstable ones.
+    pub fn apgcode(&self, rule: &Rule) -> Option<String> {
+        apgcode::encode(&self.key, rule)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Census {
+    pub generation: u64,
+    pub rule: Rule,
+    pub entries: Vec<CensusEntry>,
+}
+
+impl Census {
+    pub fn take(map: &Map, rule: &Rule, generation: u64, distance: i32) -> Self {
+        Self::of_objects(components(map, distance), rule, generation)
+    }
+
+    /// Census of objects already separated from each other.
+    pub fn of_objects(objects: Vec<Pattern>, rule: &Rule, generation: u64) -> Self {
+        let known = known_objects(rule);
+        let mut counts: HashMap<(Classification, Pattern), usize> = HashMap::new();
+        for object in objects {
+            *counts.entry(classify(&object, rule)).or_insert(0) += 1;
+        }
+        let mut entries: Vec<CensusEntry> = counts
+            .into_iter()
+            .map(|((classification, key), count)| CensusEntry {
+                name: known
+                    .iter()
+                    .find(|(known_key, _)| *known_key == key)
+                    .map(|(_, name)| *name),
+                classification,
+                key,
+                count,
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then(a.classification.cmp(&b.classification))
+                .then(a.key.cmp(&b.key))
+        });
+        Self {
+            generation,
+            rule: *rule,
+            entries,
+        }
+    }
+
+    pub fn object_count(&self) -> usize {
+        self.entries.iter().map(|entry| entry.count).sum()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "Census of generation {} ({}), {} objects\n",
+            self.generation,
+            self.rule,
+            self.object_count()
+        );
+        for entry in &self.entries {
+            text.push_str(&format!(
+                "{} {} - {}, {} cells",
+                entry.count,
+                entry.name.unwrap_or("unnamed"),
+                entry.classification,
+                entry.key.population()
+            ));
+            if entry.name.is_none() {
+                text.push_str(&format!(": {}", entry.key.to_rows()));
+            }
+            if let Some(code) = entry.apgcode(&self.rule) {
+                text.push_str(&format!(" ({})", code));
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        fs::write(path.as_ref(), self.to_text())
+            .map_err(|e| format!("Can't write {}: {}", path.as_ref().display(), e))
+    }
+}
+
+/// Groups of alive cells where each cell is within `distance` of another one.
+/// Objects crossing torus edge are returned in one piece.
+pub fn components(map: &Map, distance: i32) -> Vec<Pattern> {
+    let (width, height) = (map.width(), map.height());
+    let torus = map.topology() == Topology::Torus;
+    let index = |x: i32, y: i32| (y * width + x) as usize;
+    let mut visited = vec![false; (width * height) as usize];
+    let mut objects = Vec::new();
+    for (sx, sy) in map.alive_cells() {
+        if visited[index(sx, sy)] {
+            continue;
+        }
+        visited[index(sx, sy)] = true;
+        let mut stack = vec![(sx, sy)];
+        let mut cells = Vec::new();
+        while let Some((x, y)) = stack.pop() {
+            cells.push((x, y));
+            for dy in -distance..=distance {
+                for dx in -distance..=distance {
+                    let (ux, uy) = (x + dx, y + dy);
+                    let (mx, my) = if torus {
+                        (ux.rem_euclid(width), uy.rem_euclid(height))
+                    } else {
+                        (ux, uy)
+                    };
+                    if !(0..width).contains(&mx) || !(0..height).contains(&my) {
+                        continue;
+                    }
+                    if map.get_at(mx, my) == CellState::Alive && !visited[index(mx, my)] {
+                        visited[index(mx, my)] = true;
+                        stack.push((ux, uy));
+                    }
+                }
+            }
+        }
+        objects.push(Pattern::new(cells));
+    }
+    objects
+}
+
+/// Evolves object alone and returns its kind with the key identifying it in any phase.
+pub fn classify(object: &Pattern, rule: &Rule) -> (Classification, Pattern) {
+    let (start, start_offset) = object.normalized();
+    let mut key = start.canonical();
+    let mut current = object.clone();
+    for generation in 1..=MAX_PERIOD {
+        current = current.step(rule);
+        if current.is_empty() {
+            break;
+        }
+        let (normalized, offset) = current.normalized();
+        key = key.min(normalized.canonical());
+        if normalized == start {
+            let (dx, dy) = (
+                (offset.0 - start_offset.0).abs(),
+                (offset.1 - start_offset.1).abs(),
+            );
+            let classification = match (generation, dx.max(dy), dx.min(dy)) {
+                (1, 0, 0) => Classification::StillLife,
+                (period, 0, 0) => Classification::Oscillator { period },
+                (period, dx, dy) => Classification::Spaceship { period, dx, dy },
+            };
+            return (classification, key);
+        }
+    }
+    (Classification::Unstable, start.canonical())
+}
+
+/// Keys of `KNOWN_OBJECTS`, names are only meaningful for B3/S23. Classified once and kept
+/// for the rest of the run.
+pub fn known_objects(rule: &Rule) -> &'static [(Pattern, &'static str)] {
+    static KNOWN: OnceLock<Vec<(Pattern, &'static str)>> = OnceLock::new();
+    if *rule != Rule::conway() {
+        return &[];
+    }
+    KNOWN.get_or_init(|| {
+        KNOWN_OBJECTS
+            .iter()
+            .map(|(name, rows)| (classify(&Pattern::from_rows(rows), rule).1, *name))
+            .collect()
+    })
+}
+
+/// List of census entries shown in place of population graph.
+pub struct CensusPanel<'c> {
+    pub rect: Rect,
+    pub census: &'c Census,
+}
+
+impl<'c> Render for CensusPanel<'c> {
+    fn render(&self, canvas: &mut WindowCanvas, renderer: &mut Renderer) {
+        let color = Color::RGBA(0, 0, 0, 255);
+        renderer.render_rect(canvas, &self.rect, &Color::RGBA(240, 240, 240, 255));
+        renderer.render_frame(canvas, &self.rect, &Color::RGBA(200, 200, 200, 255));
+        let x = self.rect.x() + 5;
+        renderer.render_text(
+            canvas,
+            &format!("Census gen {}", self.census.generation),
+            Point::new(x, self.rect.y()),
+            &color,
+        );
+        renderer.render_text(
+            canvas,
+            &format!("{} objects", self.census.object_count()),
+            Point::new(x, self.rect.y() + 25),
+            &color,
+        );
+        let lines = (self.rect.height() as i32 - 60) / 25;
+        for (i, entry) in self.census.entries.iter().take(lines as usize).enumerate() {
+            renderer.render_text(
+                canvas,
+                &format!("{} {}", entry.count, entry.label()),
+                Point::new(x, self.rect.y() + 60 + i as i32 * 25),
+                &color,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::census::*;
+
+    #[test]
+    fn it_classifies_objects() {
+        let rule = Rule::conway();
+        let classification = |rows: &str| classify(&Pattern::from_rows(rows), &rule).0;
+        assert_eq!(classification("OO/OO"), Classification::StillLife);
+        assert_eq!(
+            classification("OOO"),
+            Classification::Oscillator { period: 2 }
+        );
+        assert_eq!(
+            classification(".O./..O/OOO"),
+            Classification::Spaceship {
+                period: 4,
+                dx: 1,
+                dy: 1
+            }
+        );
+        assert_eq!(
+            classification(".O..O/O..../O...O/OOOO."),
+            Classification::Spaceship {
+                period: 4,
+                dx: 2,
+                dy: 0
+            }
+        );
+        assert_eq!(classification("OO"), Classification::Unstable);
+        assert_eq!(
+            classification(".O..O/O..../O...O/OOOO.").to_string(),
+            "c/2 orthogonal spaceship"
+        );
+        assert_eq!(
+            classification(".O./..O/OOO").to_string(),
+            "c/4 diagonal spaceship"
+        );
+        let knight = Classification::Spaceship {
+            period: 12,
+            dx: 4,
+            dy: 2,
+        };
+        assert_eq!(knight.to_string(), "(2, 1)c/6 oblique spaceship");
+    }
+
+    fn place(map: &mut Map, rows: &str, x: i32, y: i32) {
+        for (cx, cy) in Pattern::from_rows(rows).cells() {
+            map.set_alive(cx + x, cy + y);
+        }
+    }
+
+    #[test]
+    fn it_counts_named_objects() {
+        let mut map = Map::new();
+        place(&mut map, "OO/OO", 1, 1);
+        place(&mut map, "OO/OO", 10, 15);
+        place(&mut map, "O/O/O", 15, 2);
+        place(&mut map, "OOO/O../.O.", 5, 8);
+        let census = Census::take(&map, &Rule::conway(), 0, DEFAULT_INTERACTION_DISTANCE);
+        let counts: Vec<(String, usize)> = census
+            .entries
+            .iter()
+            .map(|entry| (entry.label(), entry.count))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                ("block".to_string(), 2),
+                ("blinker".to_string(), 1),
+                ("glider".to_string(), 1)
+            ]
+        );
+        assert_eq!(census.object_count(), 4);
+    }
+
+    #[test]
+    fn it_joins_objects_across_torus_edge() {
+        let mut map = Map::new();
+        map.set_topology(Topology::Torus);
+        map.set_alive(19, 5);
+        map.set_alive(0, 5);
+        map.set_alive(19, 6);
+        map.set_alive(0, 6);
+        let objects = components(&map, 1);
+        assert_eq!(objects.len(), 1);
+        assert_eq!(
+            classify(&objects[0], &Rule::conway()).0,
+            Classification::StillLife
+        );
+    }
+}
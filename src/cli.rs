@@ -0,0 +1,669 @@
+use std::path::Path;
+
+use crate::bitmap::{Bitmap, Dither, ImageImport, DEFAULT_THRESHOLD};
+use crate::census::DEFAULT_INTERACTION_DISTANCE;
+use crate::collision::DEFAULT_LAB_RANGE;
+use crate::enumerate::{DEFAULT_MAX_PERIOD, MAX_ENUMERATE_SIZE};
+use crate::explore::{RuleFilter, DEFAULT_EXPLORE_SOUPS};
+use crate::genetic::{
+    Fitness, GeneticSearch, DEFAULT_ELITE, DEFAULT_FITNESS_GENERATIONS, DEFAULT_GA_POPULATION,
+    DEFAULT_GA_SIZE, DEFAULT_MUTATION, MAX_GA_SIZE,
+};
+use crate::managers::{BitmapLoader, ResourceLoader};
+use crate::map::{Map, Topology};
+use crate::methuselah::{DEFAULT_LIFESPAN_FILE, DEFAULT_MAX_GENERATIONS};
+use crate::pattern::Pattern;
+use crate::pattern_file::{PatternFile, PatternFormat};
+use crate::predecessor::{DEFAULT_PARENT_GENERATIONS, DEFAULT_PARENT_MARGIN};
+use crate::recorder::{Recorder, DEFAULT_FRAME_DELAY, DEFAULT_RECORD_TO};
+use crate::rule::Rule;
+use crate::screenshot::{Poster, Theme, DEFAULT_POSTER_SCALE};
+use crate::search::{SearchSettings, DEFAULT_METHUSELAH, DEFAULT_SEARCH_FILE};
+use crate::session::Session;
+use crate::simulation::Simulation;
+use crate::spaceship::{
+    ShipSpeed, ShipSymmetry, DEFAULT_MAX_LENGTH, DEFAULT_MAX_QUEUE, DEFAULT_SHIP_WIDTH,
+    MAX_SHIP_WIDTH,
+};
+use crate::soup::{Soup, Symmetry, DEFAULT_DENSITY};
+use crate::stability::OnStable;
+use crate::svg::{CellShape, Svg};
+
+pub const USAGE: &str = "Usage: game_of_life [OPTIONS]
+
+Options:
+    --vsync                 present frames synchronized with display refresh
+    --headless              run without window and print results
+    --generations N         generations computed in headless mode (default 1000)
+    --rule RULE             rule in B/S notation (default B3/S23)
+    --topology TOPOLOGY     bounded or torus (default bounded)
+    --density D             fill board randomly with probability D
+    --seed N                fill board with soup generated from seed N (density defaults to 0.5)
+    --symmetry SYM          soup symmetry: C1, C2, C4, D2, D4 or D8 (default C1)
+    --soup-size N           fill only N by N square in the middle of board (default whole board)
+    --on-stable ACTION      continue, pause or reseed once board settles
+    --stats FILE            write statistics of every generation in headless mode, CSV or JSON by extension
+    --census                print census of objects at the end of headless run
+    --census-distance N     cells N or fewer cells apart belong to the same object (default 2)
+    --search N              evaluate N soups with consecutive seeds in parallel and aggregate their census
+    --threads N             worker threads of soup search (default number of CPUs)
+    --search-file FILE      file search results are saved to and resumed from (default search.txt)
+    --methuselah N          report soups living at least N generations in soup search (default 200)
+    --enumerate N           list every still life and oscillator fitting in N by N box (N up to 6) and save them as gallery
+    --max-period P          longest oscillator period enumerated, 1 searches only still lifes (default 15)
+    --gallery FILE          gallery browsed in the window, or written by --enumerate (default gallery.txt)
+    --ship SPEED            search spaceships of speed c/2, c/3, c/4 or c/4d (diagonal)
+    --ship-width N          columns spaceship may use (default 6, at most 16)
+    --ship-symmetry SYM     asymmetric, mirror or glide (default asymmetric)
+    --max-length N          longest spaceship searched in rows (default 40)
+    --max-queue N           most partial spaceships kept in memory (default 100000)
+    --ships N               stop after N spaceships are found (default 1)
+    --parent ROWS           find pattern evolving into ROWS (like .O./..O/OOO) and print it
+    --parent-generations K  generations between parent and target (default 1)
+    --parent-margin M       cells parent may reach beyond target box (default 1)
+    --lifespan PATTERN      evolve rows or pattern file until it settles and report lifespan, growth and census
+    --lifespan-dir DIR      measure every pattern file in DIR and write table to --lifespan-file
+    --lifespan-file FILE    CSV table written by --lifespan-dir (default lifespan.csv)
+    --max-generations N     give up measuring lifespan after N generations (default 50000)
+    --explore N             classify N rules matching --rule-filter by evolving soups and save them to --rules
+    --rule-filter F         rules containing every transition of B/S rule, with optional :B/S of forbidden ones (default all 2^17 rules without B0)
+    --explore-soups N       soups tried with every explored rule (default 4)
+    --rules FILE            rule list browsed in the window, or written by --explore (default rules.txt)
+    --collide A B           collide object B (rows like .O./..O/OOO) with object A at every offset and phase, group outcomes and save them as gallery
+    --lab-range R           offsets of collided objects range from -R to R (default 6)
+    --evolve N              run N generations of genetic search for seed patterns and save the best ones as gallery
+    --fitness F             lifespan, final-population, max-population or symmetry (default lifespan)
+    --ga-population N       candidates in every generation of genetic search (default 40)
+    --ga-size N             seed patterns fit in N by N box (default 8, at most 20)
+    --elite N               best candidates kept unchanged in next generation, less than --ga-population (default 4)
+    --mutation RATE         probability of flipping each cell of a child (default 0.02)
+    --fitness-generations N give up evolving candidate after N generations (default 5000)
+    --pattern FILE          start with RLE, plaintext, Life 1.05/1.06, macrocell or MCell pattern placed in the middle of board, rule and topology from the file replace --rule and --topology given before
+    --image FILE            start with PNG or BMP image scaled to fit the board, dark pixels become alive cells and the rest dead, colours only count by darkness
+    --dither D              threshold, floyd-steinberg or ordered conversion of images (default threshold)
+    --threshold F           darkness from 0 to 1 above which image pixels are alive (default 0.5)
+    --invert                light image pixels become alive cells instead of dark ones
+    --save-pattern FILE     write board at the end of headless run, format is taken from extension (.rle, .cells, .lif, .mc, .mcl)
+    --pattern-format F      rle, cells, life105, life106, mc or mcl written by --save-pattern regardless of extension
+    --apgcode CODE          print cells of apgcode like xq4_153, or apgcode of rows like .O./..O/OOO
+    --session FILE          continue session saved with W key, its rule, topology and poster settings replace options given before
+    --save-session FILE     write session at the end of headless run
+    --poster FILE           write whole board as PNG at the end of headless run
+    --poster-scale N        pixels per cell of posters, also saved with B key (default 4)
+    --poster-grid           draw grid lines between cells of posters, from 3 pixels per cell
+    --theme T               light, dark or print colours of posters (default light)
+    --transparent           leave background and dead cells of posters transparent
+    --svg FILE              write whole board as SVG at the end of headless run
+    --svg-shape S           square or circle cells of SVG, also saved with Z key (default square)
+    --record FILE           record run as .gif animation, or numbered .png or .ppm frames
+    --record-from N         first generation recorded (default 0)
+    --record-to N           last generation recorded (default 100)
+    --record-every N        record every Nth generation (default 1)
+    --frame-delay CS        hundredths of second each GIF frame is shown (default 10)
+    --loop N                times GIF animation repeats, 0 forever (default 0)
+    --help                  print this message";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub vsync: bool,
+    pub headless: bool,
+    pub generations: u64,
+    pub rule: Rule,
+    pub topology: Topology,
+    pub density: Option<f64>,
+    pub seed: Option<u64>,
+    pub symmetry: Symmetry,
+    pub soup_size: Option<i32>,
+    pub on_stable: Option<OnStable>,
+    pub stats: Option<String>,
+    pub census: bool,
+    pub census_distance: i32,
+    pub search: Option<u64>,
+    pub threads: usize,
+    pub search_file: String,
+    pub methuselah: u64,
+    pub enumerate: Option<usize>,
+    pub max_period: u64,
+    pub gallery: Option<String>,
+    pub ship: Option<ShipSpeed>,
+    pub ship_width: usize,
+    pub ship_symmetry: ShipSymmetry,
+    pub max_length: usize,
+    pub max_queue: usize,
+    pub ships: usize,
+    pub parent: Option<Pattern>,
+    pub parent_generations: u64,
+    pub parent_margin: i32,
+    pub lifespan: Option<Pattern>,
+    pub lifespan_dir: Option<String>,
+    pub lifespan_file: String,
+    pub max_generations: u64,
+    pub explore: Option<usize>,
+    pub rule_filter: RuleFilter,
+    pub explore_soups: usize,
+    pub rules: Option<String>,
+    pub collide: Option<(Pattern, Pattern)>,
+    pub lab_range: i32,
+    pub evolve: Option<u64>,
+    pub fitness: Fitness,
+    pub ga_population: usize,
+    pub ga_size: i32,
+    pub elite: usize,
+    pub mutation: f64,
+    pub fitness_generations: u64,
+    pub pattern: Option<PatternFile>,
+    pub image: Option<Bitmap>,
+    pub dither: Dither,
+    pub threshold: f64,
+    pub invert: bool,
+    pub save_pattern: Option<String>,
+    pub pattern_format: Option<PatternFormat>,
+    pub apgcode: Option<String>,
+    pub session: Option<Session>,
+    pub save_session: Option<String>,
+    pub poster: Option<String>,
+    pub poster_scale: u32,
+    pub poster_grid: bool,
+    pub theme: Theme,
+    pub transparent: bool,
+    pub svg: Option<String>,
+    pub svg_shape: CellShape,
+    pub record: Option<String>,
+    pub record_from: u64,
+    pub record_to: u64,
+    pub record_every: u64,
+    pub frame_delay: u16,
+    pub loop_count: u16,
+    pub help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            vsync: false,
+            headless: false,
+            generations: 1000,
+            rule: Rule::conway(),
+            topology: Topology::Bounded,
+            density: None,
+            seed: None,
+            symmetry: Symmetry::C1,
+            soup_size: None,
+            on_stable: None,
+            stats: None,
+            census: false,
+            census_distance: DEFAULT_INTERACTION_DISTANCE,
+            search: None,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            search_file: DEFAULT_SEARCH_FILE.to_string(),
+            methuselah: DEFAULT_METHUSELAH,
+            enumerate: None,
+            max_period: DEFAULT_MAX_PERIOD,
+            gallery: None,
+            ship: None,
+            ship_width: DEFAULT_SHIP_WIDTH,
+            ship_symmetry: ShipSymmetry::Asymmetric,
+            max_length: DEFAULT_MAX_LENGTH,
+            max_queue: DEFAULT_MAX_QUEUE,
+            ships: 1,
+            parent: None,
+            parent_generations: DEFAULT_PARENT_GENERATIONS,
+            parent_margin: DEFAULT_PARENT_MARGIN,
+            lifespan: None,
+            lifespan_dir: None,
+            lifespan_file: DEFAULT_LIFESPAN_FILE.to_string(),
+            max_generations: DEFAULT_MAX_GENERATIONS,
+            explore: None,
+            rule_filter: RuleFilter::default(),
+            explore_soups: DEFAULT_EXPLORE_SOUPS,
+            rules: None,
+            collide: None,
+            lab_range: DEFAULT_LAB_RANGE,
+            evolve: None,
+            fitness: Fitness::Lifespan,
+            ga_population: DEFAULT_GA_POPULATION,
+            ga_size: DEFAULT_GA_SIZE,
+            elite: DEFAULT_ELITE,
+            mutation: DEFAULT_MUTATION,
+            fitness_generations: DEFAULT_FITNESS_GENERATIONS,
+            pattern: None,
+            image: None,
+            dither: Dither::Threshold,
+            threshold: DEFAULT_THRESHOLD,
+            invert: false,
+            save_pattern: None,
+            pattern_format: None,
+            apgcode: None,
+            session: None,
+            save_session: None,
+            poster: None,
+            poster_scale: DEFAULT_POSTER_SCALE,
+            poster_grid: false,
+            theme: Theme::Light,
+            transparent: false,
+            svg: None,
+            svg_shape: CellShape::Square,
+            record: None,
+            record_from: 0,
+            record_to: DEFAULT_RECORD_TO,
+            record_every: 1,
+            frame_delay: DEFAULT_FRAME_DELAY,
+            loop_count: 0,
+            help: false,
+        }
+    }
+}
+
+impl Options {
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--vsync" => options.vsync = true,
+                "--headless" => options.headless = true,
+                "--help" | "-h" => options.help = true,
+                "--generations" => {
+                    options.generations = parse_value(&arg, args.next())?;
+                }
+                "--rule" => options.rule = parse_value(&arg, args.next())?,
+                "--topology" => options.topology = parse_value(&arg, args.next())?,
+                "--density" => {
+                    let density: f64 = parse_value(&arg, args.next())?;
+                    if !(0.0..=1.0).contains(&density) {
+                        return Err(format!("Density {} is not between 0 and 1", density));
+                    }
+                    options.density = Some(density);
+                }
+                "--seed" => options.seed = Some(parse_value(&arg, args.next())?),
+                "--symmetry" => options.symmetry = parse_value(&arg, args.next())?,
+                "--soup-size" => {
+                    let size: i32 = parse_value(&arg, args.next())?;
+                    if size < 1 {
+                        return Err("Soup size must be at least 1".to_string());
+                    }
+                    options.soup_size = Some(size);
+                }
+                "--on-stable" => options.on_stable = Some(parse_value(&arg, args.next())?),
+                "--stats" => options.stats = Some(parse_value(&arg, args.next())?),
+                "--census" => options.census = true,
+                "--census-distance" => {
+                    options.census_distance = parse_value(&arg, args.next())?;
+                    if options.census_distance < 1 {
+                        return Err("Census distance must be at least 1".to_string());
+                    }
+                }
+                "--search" => options.search = Some(parse_value(&arg, args.next())?),
+                "--threads" => {
+                    options.threads = parse_value(&arg, args.next())?;
+                    if options.threads < 1 {
+                        return Err("Search needs at least 1 thread".to_string());
+                    }
+                }
+                "--search-file" => options.search_file = parse_value(&arg, args.next())?,
+                "--methuselah" => options.methuselah = parse_value(&arg, args.next())?,
+                "--enumerate" => {
+                    let size: usize = parse_value(&arg, args.next())?;
+                    if !(1..=MAX_ENUMERATE_SIZE).contains(&size) {
+                        return Err(format!(
+                            "Enumerated box must be between 1 and {}",
+                            MAX_ENUMERATE_SIZE
+                        ));
+                    }
+                    options.enumerate = Some(size);
+                }
+                "--max-period" => {
+                    options.max_period = parse_value(&arg, args.next())?;
+                    if options.max_period < 1 {
+                        return Err("Max period must be at least 1".to_string());
+                    }
+                }
+                "--gallery" => options.gallery = Some(parse_value(&arg, args.next())?),
+                "--ship" => options.ship = Some(parse_value(&arg, args.next())?),
+                "--ship-width" => {
+                    options.ship_width = parse_value(&arg, args.next())?;
+                    if !(1..=MAX_SHIP_WIDTH).contains(&options.ship_width) {
+                        return Err(format!(
+                            "Spaceship width must be between 1 and {}",
+                            MAX_SHIP_WIDTH
+                        ));
+                    }
+                }
+                "--ship-symmetry" => options.ship_symmetry = parse_value(&arg, args.next())?,
+                "--max-length" => options.max_length = parse_value(&arg, args.next())?,
+                "--max-queue" => options.max_queue = parse_value(&arg, args.next())?,
+                "--ships" => options.ships = parse_value(&arg, args.next())?,
+                "--parent" => {
+                    let rows: String = parse_value(&arg, args.next())?;
+                    options.parent = Some(Pattern::from_rows(&rows));
+                }
+                "--parent-generations" => {
+                    options.parent_generations = parse_value(&arg, args.next())?;
+                    if options.parent_generations < 1 {
+                        return Err("Parent must be at least 1 generation earlier".to_string());
+                    }
+                }
+                "--parent-margin" => {
+                    options.parent_margin = parse_value(&arg, args.next())?;
+                    if options.parent_margin < 0 {
+                        return Err("Parent margin can't be negative".to_string());
+                    }
+                }
+                "--lifespan" => {
+                    let value: String = parse_value(&arg, args.next())?;
+                    options.lifespan = Some(if Path::new(&value).is_file() {
+                        PatternFile::load(&value)?.pattern
+                    } else {
+                        Pattern::from_rows(&value)
+                    });
+                }
+                "--lifespan-dir" => options.lifespan_dir = Some(parse_value(&arg, args.next())?),
+                "--lifespan-file" => options.lifespan_file = parse_value(&arg, args.next())?,
+                "--max-generations" => options.max_generations = parse_value(&arg, args.next())?,
+                "--explore" => options.explore = Some(parse_value(&arg, args.next())?),
+                "--rule-filter" => options.rule_filter = parse_value(&arg, args.next())?,
+                "--explore-soups" => {
+                    options.explore_soups = parse_value(&arg, args.next())?;
+                    if options.explore_soups < 1 {
+                        return Err("Explore needs at least 1 soup".to_string());
+                    }
+                }
+                "--rules" => options.rules = Some(parse_value(&arg, args.next())?),
+                "--collide" => {
+                    let first: String = parse_value(&arg, args.next())?;
+                    let second: String = parse_value(&arg, args.next())?;
+                    options.collide =
+                        Some((Pattern::from_rows(&first), Pattern::from_rows(&second)));
+                }
+                "--lab-range" => {
+                    options.lab_range = parse_value(&arg, args.next())?;
+                    if options.lab_range < 0 {
+                        return Err("Lab range can't be negative".to_string());
+                    }
+                }
+                "--evolve" => options.evolve = Some(parse_value(&arg, args.next())?),
+                "--fitness" => options.fitness = parse_value(&arg, args.next())?,
+                "--ga-population" => {
+                    options.ga_population = parse_value(&arg, args.next())?;
+                    if options.ga_population < 2 {
+                        return Err("Genetic search needs at least 2 candidates".to_string());
+                    }
+                }
+                "--ga-size" => {
+                    options.ga_size = parse_value(&arg, args.next())?;
+                    if !(1..=MAX_GA_SIZE).contains(&options.ga_size) {
+                        return Err(format!(
+                            "Seed pattern box must be between 1 and {}",
+                            MAX_GA_SIZE
+                        ));
+                    }
+                }
+                "--elite" => options.elite = parse_value(&arg, args.next())?,
+                "--mutation" => {
+                    options.mutation = parse_value(&arg, args.next())?;
+                    if !(0.0..=1.0).contains(&options.mutation) {
+                        return Err(format!(
+                            "Mutation rate {} is not between 0 and 1",
+                            options.mutation
+                        ));
+                    }
+                }
+                "--fitness-generations" => {
+                    options.fitness_generations = parse_value(&arg, args.next())?;
+                }
+                "--pattern" => {
+                    let path: String = parse_value(&arg, args.next())?;
+                    let file = PatternFile::load(&path)?;
+                    if let Some(rule) = file.rule {
+                        options.rule = rule;
+                    }
+                    if let Some(topology) = file.topology {
+                        options.topology = topology;
+                    }
+                    options.pattern = Some(file);
+                }
+                "--image" => {
+                    let path: String = parse_value(&arg, args.next())?;
+                    options.image = Some(BitmapLoader.load(&path)?);
+                }
+                "--dither" => options.dither = parse_value(&arg, args.next())?,
+                "--threshold" => {
+                    options.threshold = parse_value(&arg, args.next())?;
+                    if !(0.0..=1.0).contains(&options.threshold) {
+                        return Err("Threshold must be between 0 and 1".to_string());
+                    }
+                }
+                "--invert" => options.invert = true,
+                "--save-pattern" => options.save_pattern = Some(parse_value(&arg, args.next())?),
+                "--pattern-format" => {
+                    options.pattern_format = Some(parse_value(&arg, args.next())?);
+                }
+                "--apgcode" => options.apgcode = Some(parse_value(&arg, args.next())?),
+                "--session" => {
+                    let path: String = parse_value(&arg, args.next())?;
+                    let session = Session::load(&path)?;
+                    options.rule = session.rule;
+                    options.topology = session.topology;
+                    let poster = &session.svg.poster;
+                    options.poster_scale = poster.scale;
+                    options.poster_grid = poster.grid;
+                    options.theme = poster.theme;
+                    options.transparent = poster.transparent;
+                    options.svg_shape = session.svg.shape;
+                    options.session = Some(session);
+                }
+                "--save-session" => options.save_session = Some(parse_value(&arg, args.next())?),
+                "--poster" => options.poster = Some(parse_value(&arg, args.next())?),
+                "--poster-scale" => {
+                    options.poster_scale = parse_value(&arg, args.next())?;
+                    if options.poster_scale < 1 {
+                        return Err("Poster needs at least 1 pixel per cell".to_string());
+                    }
+                }
+                "--poster-grid" => options.poster_grid = true,
+                "--theme" => options.theme = parse_value(&arg, args.next())?,
+                "--transparent" => options.transparent = true,
+                "--svg" => options.svg = Some(parse_value(&arg, args.next())?),
+                "--svg-shape" => options.svg_shape = parse_value(&arg, args.next())?,
+                "--record" => options.record = Some(parse_value(&arg, args.next())?),
+                "--record-from" => options.record_from = parse_value(&arg, args.next())?,
+                "--record-to" => options.record_to = parse_value(&arg, args.next())?,
+                "--record-every" => {
+                    options.record_every = parse_value(&arg, args.next())?;
+                    if options.record_every < 1 {
+                        return Err("--record-every must be at least 1".to_string());
+                    }
+                }
+                "--frame-delay" => options.frame_delay = parse_value(&arg, args.next())?,
+                "--loop" => options.loop_count = parse_value(&arg, args.next())?,
+                _ => return Err(format!("Unknown option {:?}", arg)),
+            }
+        }
+        // Either may come first, so they are checked together once everything is parsed
+        if options.elite >= options.ga_population {
+            return Err(format!(
+                "--elite {} must be less than --ga-population {}",
+                options.elite, options.ga_population
+            ));
+        }
+        Ok(options)
+    }
+
+    /// Soup settings for `map`, fresh seed is drawn unless given.
+    pub fn soup(&self, map: &Map) -> Soup {
+        let (width, height) = match self.soup_size {
+            Some(size) => (size.min(map.width()), size.min(map.height())),
+            None => (map.width(), map.height()),
+        };
+        Soup::new(
+            self.seed.unwrap_or_else(Soup::random_seed),
+            self.density.unwrap_or(DEFAULT_DENSITY),
+            width,
+            height,
+            self.symmetry,
+        )
+    }
+
+    /// Soup search starts at given seed or 0, so the same command resumes it.
+    pub fn search_settings(&self) -> SearchSettings {
+        let mut map = Map::new();
+        map.set_topology(self.topology);
+        SearchSettings {
+            rule: self.rule,
+            topology: self.topology,
+            soup: self.soup(&map).with_seed(self.seed.unwrap_or(0)),
+            generations: self.generations,
+            census_distance: self.census_distance,
+            methuselah: self.methuselah,
+        }
+    }
+
+    /// Genetic search starts from given seed or 0, so the same command repeats it.
+    pub fn genetic_search(&self) -> GeneticSearch {
+        GeneticSearch::new(self.rule, self.fitness)
+            .with_size(self.ga_size)
+            .with_population(self.ga_population)
+            .with_elite(self.elite)
+            .with_mutation(self.mutation)
+            .with_generations(self.fitness_generations)
+            .with_seed(self.seed.unwrap_or(0))
+    }
+
+    pub fn poster(&self) -> Poster {
+        Poster::new(self.poster_scale)
+            .with_grid(self.poster_grid)
+            .with_theme(self.theme)
+            .with_transparent(self.transparent)
+    }
+
+    /// Conversion of `--image` and of images imported with `I` key.
+    pub fn image_import(&self) -> ImageImport {
+        ImageImport::new(self.dither)
+            .with_threshold(self.threshold)
+            .with_invert(self.invert)
+    }
+
+    /// SVG uses poster settings for its layout and colours.
+    pub fn svg(&self) -> Svg {
+        Svg::new(self.poster()).with_shape(self.svg_shape)
+    }
+
+    /// Recording uses poster settings for its frames.
+    pub fn recorder(&self) -> Recorder {
+        Recorder::new(self.record_from, self.record_to)
+            .with_every(self.record_every)
+            .with_delay(self.frame_delay)
+            .with_loop_count(self.loop_count)
+            .with_poster(self.poster())
+    }
+
+    /// Simulation with board and rule described by options.
+    pub fn simulation(&self, default_on_stable: OnStable) -> Simulation {
+        let mut map = Map::new();
+        map.set_topology(self.topology);
+        let soup = self.soup(&map);
+        if let Some(file) = &self.pattern {
+            let dropped = file.place_on(&mut map);
+            if dropped > 0 {
+                println!("{} cells of pattern don't fit on board", dropped);
+            }
+        }
+        if let Some(bitmap) = &self.image {
+            let pattern =
+                self.image_import()
+                    .to_pattern(bitmap, map.width() as usize, map.height() as usize);
+            map.clear();
+            pattern.place_centered_on(&mut map);
+            println!(
+                "Image {}x{} converted to {} cells",
+                bitmap.width,
+                bitmap.height,
+                pattern.population()
+            );
+        }
+        let simulation = Simulation::new(map)
+            .with_rule(self.rule)
+            .with_on_stable(self.on_stable.unwrap_or(default_on_stable))
+            .with_census_distance(self.census_distance);
+        if let Some(session) = &self.session {
+            // Options given after --session still win
+            let session = Session {
+                rule: self.rule,
+                topology: self.topology,
+                on_stable: self.on_stable.unwrap_or(session.on_stable),
+                ..session.clone()
+            };
+            return simulation.with_session(&session);
+        }
+        if self.pattern.is_none()
+            && self.image.is_none()
+            && (self.density.is_some() || self.seed.is_some())
+        {
+            println!("Soup {}", soup);
+            simulation.with_soup(soup)
+        } else {
+            simulation
+        }
+    }
+}
+
+fn parse_value<T>(option: &str, value: Option<String>) -> Result<T, String>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let value = value.ok_or_else(|| format!("Missing value for {}", option))?;
+    value
+        .parse()
+        .map_err(|e| format!("Invalid value {:?} for {}: {}", value, option, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn it_parses_options() {
+        let options = parse(&[
+            "--headless",
+            "--generations",
+            "50",
+            "--rule",
+            "B36/S23",
+            "--topology",
+            "torus",
+            "--on-stable",
+            "reseed",
+        ])
+        .unwrap();
+        assert!(options.headless);
+        assert_eq!(options.generations, 50);
+        assert_eq!(options.rule, "B36/S23".parse().unwrap());
+        assert_eq!(options.topology, Topology::Torus);
+        assert_eq!(options.on_stable, Some(OnStable::Reseed));
+    }
+
+    #[test]
+    fn it_rejects_invalid_options() {
+        assert!(parse(&["--generations"]).is_err());
+        assert!(parse(&["--density", "2"]).is_err());
+        assert!(parse(&["--unknown"]).is_err());
+        assert!(parse(&["--symmetry", "D3"]).is_err());
+        assert!(parse(&["--soup-size", "0"]).is_err());
+        assert!(parse(&["--elite", "40"]).is_err());
+        assert!(parse(&["--elite", "5", "--ga-population", "5"]).is_err());
+        assert!(parse(&["--ga-population", "5", "--elite", "4"]).is_ok());
+    }
+
+    #[test]
+    fn it_reproduces_soup_from_seed() {
+        let options = parse(&["--seed", "1234", "--symmetry", "D4"]).unwrap();
+        let first = options.simulation(OnStable::Continue);
+        let second = options.simulation(OnStable::Continue);
+        assert_eq!(first.map(), second.map());
+        assert!(first.map().population() > 0);
+    }
+}
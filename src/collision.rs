@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use crate::census::{classify, Census, Classification, DEFAULT_INTERACTION_DISTANCE};
+use crate::gallery::Gallery;
+use crate::methuselah;
+use crate::parallel::parallel_map;
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+
+pub const DEFAULT_LAB_RANGE: i32 = 6;
+/// Collisions not settled by then are reported as unsettled.
+pub const LAB_GENERATIONS: u64 = 2000;
+const ANNIHILATION: &str = "clean annihilation";
+const NO_INTERACTION: &str = "no interaction";
+
+/// Starting arrangement of both objects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collision {
+    /// Top left corner of second object relative to the first one.
+    pub dx: i32,
+    pub dy: i32,
+    /// Generations second object was advanced by.
+    pub phase: u64,
+    pub start: Pattern,
+}
+
+/// Collisions leaving the same census.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub label: String,
+    pub collisions: Vec<Collision>,
+}
+
+/// Places second object around the first one at every offset within range and
+/// every relative phase, evolves them until settled and groups what is left.
+#[derive(Debug, Clone)]
+pub struct CollisionLab {
+    rule: Rule,
+    first: Pattern,
+    second: Pattern,
+    range: i32,
+    generations: u64,
+}
+
+impl CollisionLab {
+    pub fn new(rule: Rule, first: Pattern, second: Pattern) -> Self {
+        Self {
+            rule,
+            first: first.normalized().0,
+            second: second.normalized().0,
+            range: DEFAULT_LAB_RANGE,
+            generations: LAB_GENERATIONS,
+        }
+    }
+
+    /// Offsets from `-range` to `range` in both axes between top left corners.
+    pub fn with_range(mut self, range: i32) -> Self {
+        self.range = range.max(0);
+        self
+    }
+
+    /// Phases worth trying, enough to meet every phase of the first object with every phase of the second.
+    fn phases(&self) -> u64 {
+        let period = |object: &Pattern| match classify(object, &self.rule).0 {
+            Classification::Oscillator { period } | Classification::Spaceship { period, .. } => {
+                period
+            }
+            _ => 1,
+        };
+        let (a, b) = (period(&self.first), period(&self.second));
+        let mut gcd = (a, b);
+        while gcd.1 != 0 {
+            gcd = (gcd.1, gcd.0 % gcd.1);
+        }
+        a / gcd.0 * b
+    }
+
+    /// Arrangements where both objects start apart.
+    pub fn collisions(&self) -> Vec<Collision> {
+        let mut second = self.second.clone();
+        let mut collisions = Vec::new();
+        for phase in 0..self.phases() {
+            let shape = second.normalized().0;
+            for dy in -self.range..=self.range {
+                for dx in -self.range..=self.range {
+                    let moved = shape.translated(dx, dy);
+                    if self.touches(&moved) {
+                        continue;
+                    }
+                    let mut cells = self.first.cells().to_vec();
+                    cells.extend_from_slice(moved.cells());
+                    collisions.push(Collision {
+                        dx,
+                        dy,
+                        phase,
+                        start: Pattern::new(cells),
+                    });
+                }
+            }
+            second = second.step(&self.rule);
+        }
+        collisions
+    }
+
+    fn touches(&self, other: &Pattern) -> bool {
+        let distance = DEFAULT_INTERACTION_DISTANCE;
+        self.first.cells().iter().any(|(x, y)| {
+            other
+                .cells()
+                .iter()
+                .any(|(ox, oy)| (x - ox).abs() <= distance && (y - oy).abs() <= distance)
+        })
+    }
+
+    /// Outcomes sorted from the most common one.
+    pub fn run(&self, threads: usize) -> Vec<Outcome> {
+        let collisions = self.collisions();
+        let labels = parallel_map(&collisions, threads, |collision| self.outcome(collision));
+
+        let mut groups: HashMap<String, Vec<Collision>> = HashMap::new();
+        for (label, collision) in labels.into_iter().zip(collisions) {
+            groups.entry(label).or_default().push(collision);
+        }
+        let mut outcomes: Vec<Outcome> = groups
+            .into_iter()
+            .map(|(label, collisions)| Outcome { label, collisions })
+            .collect();
+        outcomes.sort_by(|a, b| {
+            b.collisions
+                .len()
+                .cmp(&a.collisions.len())
+                .then(a.label.cmp(&b.label))
+        });
+        outcomes
+    }
+
+    fn outcome(&self, collision: &Collision) -> String {
+        let lifespan = methuselah::measure(&collision.start, &self.rule, self.generations);
+        if lifespan.lifespan.is_none() {
+            return format!("unsettled after {}", self.generations);
+        }
+        let census = lifespan.census_summary();
+        let objects = collision.start.components(DEFAULT_INTERACTION_DISTANCE);
+        let before = methuselah::summary(&Census::of_objects(objects, &self.rule, 0));
+        if census.is_empty() {
+            ANNIHILATION.to_string()
+        } else if census == before {
+            NO_INTERACTION.to_string()
+        } else {
+            census
+        }
+    }
+}
+
+/// One entry per outcome with its first collision, labels tell how many collisions gave it.
+pub fn to_gallery(rule: &Rule, outcomes: &[Outcome]) -> Gallery {
+    let mut gallery = Gallery::new(Some(*rule));
+    for outcome in outcomes {
+        let first = &outcome.collisions[0];
+        gallery.push(
+            format!(
+                "{} ({}x, offset {} {} phase {})",
+                outcome.label,
+                outcome.collisions.len(),
+                first.dx,
+                first.dy,
+                first.phase
+            ),
+            first.start.clone(),
+        );
+    }
+    gallery
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collision::*;
+
+    #[test]
+    fn it_groups_glider_block_collisions() {
+        let lab = CollisionLab::new(
+            Rule::conway(),
+            Pattern::from_rows("OO/OO"),
+            Pattern::from_rows(".O./..O/OOO"),
+        )
+        .with_range(4);
+        let outcomes = lab.run(2);
+        let labels: Vec<&str> = outcomes.iter().map(|outcome| outcome.label.as_str()).collect();
+        assert!(labels.contains(&NO_INTERACTION));
+        assert!(labels.contains(&ANNIHILATION));
+        let collisions: usize = outcomes.iter().map(|outcome| outcome.collisions.len()).sum();
+        assert_eq!(collisions, lab.collisions().len());
+        // Every collision of a group really ends the same way
+        for outcome in &outcomes {
+            for collision in outcome.collisions.iter().take(3) {
+                assert_eq!(lab.outcome(collision), outcome.label);
+            }
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::census::{classify, known_objects, Classification};
+use crate::gallery::Gallery;
+use crate::parallel::parallel_map;
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+
+/// Above this size enumeration takes longer than anyone waits.
+pub const MAX_ENUMERATE_SIZE: usize = 6;
+pub const DEFAULT_MAX_PERIOD: u64 = 15;
+
+/// Still life or oscillator found by enumeration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Found {
+    pub name: Option<&'static str>,
+    pub classification: Classification,
+    /// Smallest canonical form among all phases.
+    pub key: Pattern,
+}
+
+impl Found {
+    pub fn label(&self) -> String {
+        match self.name {
+            Some(name) => name.to_string(),
+            None => format!("{} {} cells", self.classification, self.key.population()),
+        }
+    }
+}
+
+/// Every still life and oscillator whose phases all fit in `size` by `size` box.
+/// With `max_period` 1 only still lifes are searched, which prunes far more.
+pub fn enumerate(rule: &Rule, size: usize, max_period: u64, threads: usize) -> Vec<Found> {
+    let mut found: HashMap<Pattern, Classification> = HashMap::new();
+    for height in 1..=size {
+        // Box and its transpose hold the same objects
+        for width in 1..=height {
+            let enumerator = Enumerator::new(rule, width, height, max_period);
+            found.extend(enumerator.run(threads.max(1)));
+        }
+    }
+    let known = known_objects(rule);
+    let mut found: Vec<Found> = found
+        .into_iter()
+        .map(|(key, classification)| Found {
+            name: known
+                .iter()
+                .find(|(known_key, _)| *known_key == key)
+                .map(|(_, name)| *name),
+            classification,
+            key,
+        })
+        .collect();
+    found.sort_by(|a, b| {
+        a.classification
+            .cmp(&b.classification)
+            .then(a.key.population().cmp(&b.key.population()))
+            .then(a.key.cmp(&b.key))
+    });
+    found
+}
+
+pub fn to_gallery(rule: &Rule, found: &[Found]) -> Gallery {
+    let mut gallery = Gallery::new(Some(*rule));
+    for object in found {
+        gallery.push(object.label(), object.key.clone());
+    }
+    gallery
+}
+
+/// Row by row search in single box. Rows are bit masks where column `x` is bit `x + 2`,
+/// so one column on both sides of the box is kept to notice cells escaping it.
+/// Frame index `i` holds row `i - 1`, first and last row of frame lie outside the box.
+struct Enumerator {
+    transition: [bool; 512],
+    rule: Rule,
+    width: usize,
+    height: usize,
+    max_period: u64,
+    outside: u32,
+}
+
+impl Enumerator {
+    fn new(rule: &Rule, width: usize, height: usize, max_period: u64) -> Self {
+        Self {
+            transition: rule.transition_table(),
+            rule: *rule,
+            width,
+            height,
+            max_period,
+            outside: (1 << 1) | (1 << (width + 2)),
+        }
+    }
+
+    fn next_row(&self, above: u32, row: u32, below: u32) -> u32 {
+        let mut next = 0;
+        for shift in 0..self.width + 2 {
+            let index = ((above >> shift) & 7)
+                | (((row >> shift) & 7) << 3)
+                | (((below >> shift) & 7) << 6);
+            if self.transition[index as usize] {
+                next |= 1 << (shift + 1);
+            }
+        }
+        next
+    }
+
+    fn row_at(frame: &[u32], index: usize) -> u32 {
+        frame.get(index).cloned().unwrap_or(0)
+    }
+
+    fn next_row_at(&self, frame: &[u32], index: usize) -> u32 {
+        let above = if index == 0 { 0 } else { frame[index - 1] };
+        self.next_row(above, frame[index], Self::row_at(frame, index + 1))
+    }
+
+    fn step(&self, frame: &[u32]) -> Vec<u32> {
+        (0..frame.len())
+            .map(|index| self.next_row_at(frame, index))
+            .collect()
+    }
+
+    fn is_inside(&self, frame: &[u32]) -> bool {
+        frame[0] == 0
+            && frame[frame.len() - 1] == 0
+            && frame.iter().all(|row| row & self.outside == 0)
+    }
+
+    fn reversed(&self, row: u32) -> u32 {
+        (row >> 2).reverse_bits() >> (32 - self.width) << 2
+    }
+
+    /// Splits search on the first row between threads.
+    fn run(&self, threads: usize) -> HashMap<Pattern, Classification> {
+        let firsts: Vec<u32> = (0..1 << self.width).collect();
+        let found = parallel_map(&firsts, threads, |first| {
+            let mut found = HashMap::new();
+            let mut frame = vec![0; self.height + 2];
+            self.place(&mut frame, 0, first << 2, Ordering::Equal, &mut found);
+            found
+        });
+        found.into_iter().flatten().collect()
+    }
+
+    /// `mirror` compares rows placed so far with their left to right reflection,
+    /// patterns larger than their reflection are skipped as the reflection is searched too.
+    fn place(
+        &self,
+        frame: &mut Vec<u32>,
+        y: usize,
+        row: u32,
+        mirror: Ordering,
+        found: &mut HashMap<Pattern, Classification>,
+    ) {
+        let mirror = match mirror {
+            Ordering::Equal => self.reversed(row).cmp(&row).reverse(),
+            ordering => ordering,
+        };
+        if mirror == Ordering::Greater {
+            return;
+        }
+        frame[y + 1] = row;
+        // Previous row and its neighbours are known now, so is its successor
+        let next = self.next_row_at(frame, y);
+        let rejected = if self.max_period == 1 {
+            next != frame[y]
+        } else {
+            next & self.outside != 0 || (y == 0 && next != 0)
+        };
+        if !rejected {
+            if y + 1 == self.height {
+                self.complete(frame, found);
+            } else {
+                for row in 0..1 << self.width {
+                    self.place(frame, y + 1, row << 2, mirror, found);
+                }
+            }
+        }
+        frame[y + 1] = 0;
+    }
+
+    fn complete(&self, frame: &[u32], found: &mut HashMap<Pattern, Classification>) {
+        let last = self.height;
+        if self.max_period == 1 && self.next_row_at(frame, last) != frame[last] {
+            return;
+        }
+        if frame.iter().all(|row| *row == 0) {
+            return;
+        }
+        let flipped: Vec<u32> = frame.iter().rev().cloned().collect();
+        if flipped.as_slice() < frame {
+            return;
+        }
+        let mut current = frame.to_vec();
+        let mut union = frame.to_vec();
+        let mut period = None;
+        for generation in 1..=self.max_period {
+            current = self.step(&current);
+            if !self.is_inside(&current) {
+                return;
+            }
+            for (cells, row) in union.iter_mut().zip(&current) {
+                *cells |= row;
+            }
+            if current == frame {
+                period = Some(generation);
+                break;
+            }
+        }
+        let period = match period {
+            Some(period) => period,
+            None => return,
+        };
+        // Objects not touching all sides belong to smaller box
+        let columns = union.iter().fold(0, |columns, row| columns | row);
+        if union[1] == 0
+            || union[last] == 0
+            || columns & (1 << 2) == 0
+            || columns & (1 << (self.width + 1)) == 0
+        {
+            return;
+        }
+        let pattern = self.pattern(frame);
+        if self.is_pseudo(&pattern, period) {
+            return;
+        }
+        let key = classify(&pattern, &self.rule).1;
+        let classification = if period == 1 {
+            Classification::StillLife
+        } else {
+            Classification::Oscillator { period }
+        };
+        found.insert(key, classification);
+    }
+
+    /// Pseudo still lifes and constellations split into two groups of islands which
+    /// repeat on their own and evolve the same way side by side, like bi-block.
+    fn is_pseudo(&self, pattern: &Pattern, period: u64) -> bool {
+        let islands = pattern.components(1);
+        // Last island always stays in the second group, so every split is tried once
+        (1..1u32 << (islands.len() - 1)).any(|mask| {
+            let group = |inside: bool| {
+                let cells = islands
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| (mask & (1 << index) != 0) == inside)
+                    .flat_map(|(_, island)| island.cells().to_vec())
+                    .collect();
+                Pattern::new(cells)
+            };
+            let (first, second) = (group(true), group(false));
+            let (mut a, mut b, mut whole) = (first.clone(), second.clone(), pattern.clone());
+            for _ in 0..period {
+                a = a.step(&self.rule);
+                b = b.step(&self.rule);
+                whole = whole.step(&self.rule);
+                let side_by_side = Pattern::new([a.cells(), b.cells()].concat());
+                if side_by_side != whole {
+                    return false;
+                }
+            }
+            a == first && b == second
+        })
+    }
+
+    fn pattern(&self, frame: &[u32]) -> Pattern {
+        let mut cells = Vec::new();
+        for (y, row) in frame[1..=self.height].iter().enumerate() {
+            for x in 0..self.width {
+                if row & (1 << (x + 2)) != 0 {
+                    cells.push((x as i32, y as i32));
+                }
+            }
+        }
+        Pattern::new(cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enumerate::*;
+
+    #[test]
+    fn it_counts_small_still_lifes() {
+        let found = enumerate(&Rule::conway(), 4, 1, 2);
+        let names: Vec<Option<&str>> = found.iter().map(|object| object.name).collect();
+        for name in &[
+            "block",
+            "beehive",
+            "loaf",
+            "boat",
+            "ship",
+            "tub",
+            "pond",
+            "barge",
+            "long boat",
+            "aircraft carrier",
+            "snake",
+        ] {
+            assert!(names.contains(&Some(*name)), "{} not found", name);
+        }
+        assert!(found
+            .iter()
+            .all(|object| object.classification == Classification::StillLife));
+        assert_eq!(found.len(), 13);
+        // Strict ones only, bi-block and other pseudo still lifes are left out
+        let found = enumerate(&Rule::conway(), 5, 1, 2);
+        assert_eq!(found.len(), 33);
+        let bi_block = classify(&Pattern::from_rows("OO/OO/../OO/OO"), &Rule::conway()).1;
+        assert!(found.iter().all(|object| object.key != bi_block));
+    }
+
+    #[test]
+    fn it_finds_oscillators_with_all_phases_in_box() {
+        let found = enumerate(&Rule::conway(), 4, 4, 2);
+        let mut oscillators: Vec<String> = found
+            .iter()
+            .filter(|object| object.classification != Classification::StillLife)
+            .map(|object| object.label())
+            .collect();
+        oscillators.sort();
+        assert_eq!(oscillators, vec!["beacon", "blinker", "clock", "toad"]);
+    }
+}
@@ -0,0 +1,646 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use rand::Rng;
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+
+use crate::app::WindowCanvas;
+use crate::parallel::parallel_map;
+use crate::renderer::{Render, Renderer};
+use crate::rule::Rule;
+use crate::soup::{seeded_rng, Soup, Symmetry, DEFAULT_DENSITY};
+
+pub const DEFAULT_RULES_FILE: &str = "rules.txt";
+pub const DEFAULT_EXPLORE_SOUPS: usize = 4;
+pub const EXPLORE_GENERATIONS: u64 = 300;
+const FILE_HEADER: &str = "# game_of_life rule space";
+/// Soups evolve on torus of this many 64 bit rows.
+const GRID_SIZE: usize = 64;
+const SOUP_SIZE: i32 = 16;
+/// Growing this many times over initial population counts as explosion.
+const EXPLODING_GROWTH: f64 = 3.0;
+/// Share of cells changing each generation above which board is chaotic.
+const CHAOTIC_ACTIVITY: f64 = 0.01;
+
+/// Typical fate of soups under a rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RuleClass {
+    Dying,
+    Stable,
+    /// Repeats with period above 1, or is left with little besides spaceships.
+    Periodic,
+    Chaotic,
+    Exploding,
+}
+
+const RULE_CLASSES: [RuleClass; 5] = [
+    RuleClass::Dying,
+    RuleClass::Stable,
+    RuleClass::Periodic,
+    RuleClass::Chaotic,
+    RuleClass::Exploding,
+];
+
+impl fmt::Display for RuleClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            RuleClass::Dying => "dying",
+            RuleClass::Stable => "stable",
+            RuleClass::Periodic => "periodic",
+            RuleClass::Chaotic => "chaotic",
+            RuleClass::Exploding => "exploding",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for RuleClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RULE_CLASSES
+            .iter()
+            .find(|class| class.to_string() == s)
+            .cloned()
+            .ok_or_else(|| format!("Unknown rule class {:?}", s))
+    }
+}
+
+/// Averages over soups of one rule.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RuleMetrics {
+    /// Final population divided by initial one.
+    pub trend: f64,
+    /// Share of cells changing per generation in second half of run.
+    pub activity: f64,
+    /// Entropy of 2x2 blocks of final board, 1 for uniformly random board.
+    pub entropy: f64,
+    /// Most common period of soups which started repeating.
+    pub period: Option<u64>,
+    /// Cells per generation the soup spread out at, 1 is the speed of light.
+    pub speed: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RuleReport {
+    pub rule: Rule,
+    pub class: RuleClass,
+    pub metrics: RuleMetrics,
+}
+
+impl fmt::Display for RuleReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {:.3} {:.4} {:.3} {} {:.3}",
+            self.rule,
+            self.class,
+            self.metrics.trend,
+            self.metrics.activity,
+            self.metrics.entropy,
+            self.metrics
+                .period
+                .map_or("-".to_string(), |period| period.to_string()),
+            self.metrics.speed
+        )
+    }
+}
+
+impl FromStr for RuleReport {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        if fields.len() != 7 {
+            return Err(format!("expected 7 fields, found {}", fields.len()));
+        }
+        let number = |field: &str| {
+            field
+                .parse::<f64>()
+                .map_err(|e| format!("Invalid number {:?}: {}", field, e))
+        };
+        Ok(Self {
+            rule: fields[0].parse()?,
+            class: fields[1].parse()?,
+            metrics: RuleMetrics {
+                trend: number(fields[2])?,
+                activity: number(fields[3])?,
+                entropy: number(fields[4])?,
+                period: match fields[5] {
+                    "-" => None,
+                    period => Some(
+                        period
+                            .parse()
+                            .map_err(|e| format!("Invalid period {:?}: {}", period, e))?,
+                    ),
+                },
+                speed: number(fields[6])?,
+            },
+        })
+    }
+}
+
+/// Rules containing every transition of `include` and none of `exclude`,
+/// written as `B3/S2` or `B3/S2:B0/S8`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RuleFilter {
+    pub include: Rule,
+    pub exclude: Rule,
+}
+
+impl RuleFilter {
+    pub fn matches(&self, rule: &Rule) -> bool {
+        rule.birth() & self.include.birth() == self.include.birth()
+            && rule.survival() & self.include.survival() == self.include.survival()
+            && rule.birth() & self.exclude.birth() == 0
+            && rule.survival() & self.exclude.survival() == 0
+    }
+
+    /// Matching rules, births without neighbours are skipped as the simulation ignores them.
+    pub fn rules(&self) -> Vec<Rule> {
+        let mut rules = Vec::new();
+        for birth in (0..1 << 9).step_by(2) {
+            for survival in 0..1 << 9 {
+                let rule = Rule::new(birth, survival);
+                if self.matches(&rule) {
+                    rules.push(rule);
+                }
+            }
+        }
+        rules
+    }
+}
+
+impl Default for RuleFilter {
+    fn default() -> Self {
+        Self {
+            include: Rule::new(0, 0),
+            exclude: Rule::new(0, 0),
+        }
+    }
+}
+
+impl FromStr for RuleFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, ':');
+        Ok(Self {
+            include: parts.next().unwrap_or_default().parse()?,
+            exclude: match parts.next() {
+                Some(exclude) => exclude.parse()?,
+                None => Rule::new(0, 0),
+            },
+        })
+    }
+}
+
+impl fmt::Display for RuleFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.include, self.exclude)
+    }
+}
+
+/// Classified rules browsed in the editor.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RuleList {
+    pub reports: Vec<RuleReport>,
+}
+
+impl RuleList {
+    pub fn len(&self) -> usize {
+        self.reports.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reports.is_empty()
+    }
+
+    /// Header followed by `rule class trend activity entropy period speed` lines.
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{}\n# rule class trend activity entropy period speed\n",
+            FILE_HEADER
+        );
+        for report in &self.reports {
+            text.push_str(&format!("{}\n", report));
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut list = Self::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            list.reports.push(
+                line.parse()
+                    .map_err(|e| format!("Line {}: {}", number + 1, e))?,
+            );
+        }
+        Ok(list)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let text = fs::read_to_string(path.as_ref())
+            .map_err(|e| format!("Can't read {}: {}", path.as_ref().display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.as_ref().display(), e))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        fs::write(path.as_ref(), self.to_text())
+            .map_err(|e| format!("Can't write {}: {}", path.as_ref().display(), e))
+    }
+}
+
+const PANEL_LINE_HEIGHT: i32 = 25;
+const PANEL_HEADER_HEIGHT: i32 = 30;
+
+/// Scrolling list of rules shown in place of population graph, selected one is highlighted.
+pub struct RulesPanel<'l> {
+    pub rect: Rect,
+    pub list: &'l RuleList,
+    pub selected: usize,
+}
+
+impl<'l> RulesPanel<'l> {
+    fn lines(&self) -> usize {
+        ((self.rect.height() as i32 - PANEL_HEADER_HEIGHT) / PANEL_LINE_HEIGHT).max(1) as usize
+    }
+
+    /// First visible report, keeps selection in the middle where possible.
+    fn first(&self) -> usize {
+        let lines = self.lines();
+        self.selected
+            .saturating_sub(lines / 2)
+            .min(self.list.len().saturating_sub(lines))
+    }
+
+    /// Report under point of the panel.
+    pub fn report_at(&self, point: Point) -> Option<usize> {
+        if !self.rect.contains_point(point) {
+            return None;
+        }
+        let line = (point.y() - self.rect.y() - PANEL_HEADER_HEIGHT) / PANEL_LINE_HEIGHT;
+        if line < 0 {
+            return None;
+        }
+        let index = self.first() + line as usize;
+        if index < self.list.len() {
+            Some(index)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'l> Render for RulesPanel<'l> {
+    fn render(&self, canvas: &mut WindowCanvas, renderer: &mut Renderer) {
+        let color = Color::RGBA(0, 0, 0, 255);
+        renderer.render_rect(canvas, &self.rect, &Color::RGBA(240, 240, 240, 255));
+        renderer.render_frame(canvas, &self.rect, &Color::RGBA(200, 200, 200, 255));
+        let x = self.rect.x() + 5;
+        renderer.render_text(
+            canvas,
+            &format!("Rules {}/{}", self.selected + 1, self.list.len()),
+            Point::new(x, self.rect.y()),
+            &color,
+        );
+        let first = self.first();
+        for (i, report) in self.list.reports[first..].iter().take(self.lines()).enumerate() {
+            let y = self.rect.y() + PANEL_HEADER_HEIGHT + i as i32 * PANEL_LINE_HEIGHT;
+            if first + i == self.selected {
+                renderer.render_rect(
+                    canvas,
+                    &Rect::new(self.rect.x() + 1, y, self.rect.width() - 2, PANEL_LINE_HEIGHT as u32),
+                    &Color::RGBA(200, 220, 255, 255),
+                );
+            }
+            renderer.render_text(
+                canvas,
+                &format!("{} {}", report.rule, report.class),
+                Point::new(x, y),
+                &color,
+            );
+        }
+    }
+}
+
+/// Classifies up to `count` rules matching `filter`, sampled with `seed` when there are more.
+/// Every rule is tried on the same `soups` soups.
+pub fn explore(
+    filter: &RuleFilter,
+    count: usize,
+    soups: usize,
+    seed: u64,
+    threads: usize,
+) -> RuleList {
+    let mut rules = filter.rules();
+    if rules.len() > count {
+        let mut rng = seeded_rng(seed);
+        for i in 0..count {
+            let j = rng.gen_range(i, rules.len());
+            rules.swap(i, j);
+        }
+        rules.truncate(count);
+    }
+    rules.sort_by_key(|rule| (rule.birth(), rule.survival()));
+    let soups: Vec<Vec<u64>> = (0..soups as u64)
+        .map(|i| soup_grid(seed.wrapping_add(i)))
+        .collect();
+
+    RuleList {
+        reports: parallel_map(&rules, threads, |rule| classify_rule(rule, &soups)),
+    }
+}
+
+fn soup_grid(seed: u64) -> Vec<u64> {
+    let soup = Soup::new(seed, DEFAULT_DENSITY, SOUP_SIZE, SOUP_SIZE, Symmetry::C1);
+    let mut grid = vec![0u64; GRID_SIZE];
+    let offset = (GRID_SIZE as i32 - SOUP_SIZE) / 2;
+    for (x, y) in soup.generate().cells() {
+        grid[(y + offset) as usize] |= 1 << (x + offset);
+    }
+    grid
+}
+
+pub fn classify_rule(rule: &Rule, soups: &[Vec<u64>]) -> RuleReport {
+    let runs: Vec<SoupRun> = soups.iter().map(|soup| SoupRun::evolve(rule, soup)).collect();
+    let mut votes: HashMap<RuleClass, usize> = HashMap::new();
+    let mut periods: HashMap<u64, usize> = HashMap::new();
+    for run in &runs {
+        *votes.entry(run.class).or_insert(0) += 1;
+        if let Some(period) = run.period {
+            *periods.entry(period).or_insert(0) += 1;
+        }
+    }
+    // Ties go to the more active class
+    let class = votes
+        .into_iter()
+        .max_by_key(|(class, votes)| (*votes, *class))
+        .map_or(RuleClass::Dying, |(class, _)| class);
+    let mean = |metric: fn(&SoupRun) -> f64| {
+        runs.iter().map(metric).sum::<f64>() / runs.len().max(1) as f64
+    };
+    RuleReport {
+        rule: *rule,
+        class,
+        metrics: RuleMetrics {
+            trend: mean(|run| run.trend),
+            activity: mean(|run| run.activity),
+            entropy: mean(|run| run.entropy),
+            period: periods
+                .into_iter()
+                .max_by_key(|(period, count)| (*count, std::cmp::Reverse(*period)))
+                .map(|(period, _)| period),
+            speed: mean(|run| run.speed),
+        },
+    }
+}
+
+/// Measurements of single soup on torus.
+struct SoupRun {
+    class: RuleClass,
+    trend: f64,
+    activity: f64,
+    entropy: f64,
+    period: Option<u64>,
+    speed: f64,
+}
+
+impl SoupRun {
+    fn evolve(rule: &Rule, soup: &[u64]) -> Self {
+        let stepper = Stepper::new(rule);
+        let initial = population(soup);
+        let mut grid = soup.to_vec();
+        let mut seen: HashMap<Vec<u64>, u64> = HashMap::new();
+        seen.insert(grid.clone(), 0);
+        let mut period = None;
+        let mut changes = 0;
+        let mut measured = 0;
+        let initial_extent = extent(soup);
+        let mut spread = None;
+        let mut generation = 0;
+        while generation < EXPLORE_GENERATIONS {
+            let next = stepper.step(&grid);
+            generation += 1;
+            if generation > EXPLORE_GENERATIONS / 2 {
+                changes += grid
+                    .iter()
+                    .zip(&next)
+                    .map(|(a, b)| (a ^ b).count_ones() as u64)
+                    .sum::<u64>();
+                measured += 1;
+            }
+            grid = next;
+            if spread.is_none() && touches_edge(&grid) {
+                spread = Some(generation);
+            }
+            if let Some(previous) = seen.insert(grid.clone(), generation) {
+                period = Some(generation - previous);
+                break;
+            }
+        }
+        let cells = (GRID_SIZE * GRID_SIZE) as f64;
+        let final_population = population(&grid);
+        let trend = final_population as f64 / initial.max(1) as f64;
+        let activity = if measured > 0 {
+            changes as f64 / measured as f64 / cells
+        } else {
+            0.0
+        };
+        let speed = match spread {
+            Some(generation) => (GRID_SIZE as f64 - initial_extent) / 2.0 / generation as f64,
+            None => ((extent(&grid) - initial_extent) / 2.0).max(0.0) / generation as f64,
+        };
+        let class = if final_population == 0 {
+            RuleClass::Dying
+        } else if trend >= EXPLODING_GROWTH {
+            RuleClass::Exploding
+        } else if period == Some(1) {
+            RuleClass::Stable
+        } else if period.is_some() {
+            RuleClass::Periodic
+        } else if activity >= CHAOTIC_ACTIVITY {
+            RuleClass::Chaotic
+        } else {
+            RuleClass::Periodic
+        };
+        Self {
+            class,
+            trend,
+            activity,
+            entropy: entropy(&grid),
+            period: period.filter(|_| final_population > 0),
+            speed,
+        }
+    }
+}
+
+fn population(grid: &[u64]) -> u32 {
+    grid.iter().map(|row| row.count_ones()).sum()
+}
+
+fn touches_edge(grid: &[u64]) -> bool {
+    let edges = 1 | (1 << (GRID_SIZE - 1));
+    grid[0] != 0 || grid[GRID_SIZE - 1] != 0 || grid.iter().any(|row| row & edges != 0)
+}
+
+/// Larger side of bounding box of alive cells, ignoring that the board wraps.
+fn extent(grid: &[u64]) -> f64 {
+    let columns = grid.iter().fold(0, |columns, row| columns | row);
+    if columns == 0 {
+        return 0.0;
+    }
+    let width = GRID_SIZE as u32 - columns.leading_zeros() - columns.trailing_zeros();
+    let first = grid.iter().position(|row| *row != 0).unwrap();
+    let last = grid.iter().rposition(|row| *row != 0).unwrap();
+    width.max((last - first + 1) as u32) as f64
+}
+
+/// Shannon entropy of 2x2 blocks divided by its maximum of 4 bits.
+fn entropy(grid: &[u64]) -> f64 {
+    let mut counts = [0u32; 16];
+    for y in (0..GRID_SIZE).step_by(2) {
+        for x in (0..GRID_SIZE).step_by(2) {
+            let bit = |row: u64, x: usize| ((row >> x) & 1) as usize;
+            let block = bit(grid[y], x)
+                | (bit(grid[y], x + 1) << 1)
+                | (bit(grid[y + 1], x) << 2)
+                | (bit(grid[y + 1], x + 1) << 3);
+            counts[block] += 1;
+        }
+    }
+    let total = (GRID_SIZE * GRID_SIZE / 4) as f64;
+    counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / total;
+            p * (1.0 / p).log2()
+        })
+        .sum::<f64>()
+        / 4.0
+}
+
+/// Whole row at once, neighbour counts are kept as four bit planes.
+struct Stepper {
+    /// Mask of counts giving alive cell, for dead and alive centre.
+    birth: u16,
+    survival: u16,
+}
+
+impl Stepper {
+    fn new(rule: &Rule) -> Self {
+        Self {
+            // Births without neighbours are ignored like everywhere else
+            birth: rule.birth() & !1,
+            survival: rule.survival(),
+        }
+    }
+
+    fn step(&self, grid: &[u64]) -> Vec<u64> {
+        (0..GRID_SIZE)
+            .map(|y| {
+                let above = grid[(y + GRID_SIZE - 1) % GRID_SIZE];
+                let row = grid[y];
+                let below = grid[(y + 1) % GRID_SIZE];
+                self.next_row(above, row, below)
+            })
+            .collect()
+    }
+
+    fn next_row(&self, above: u64, row: u64, below: u64) -> u64 {
+        let neighbours = [
+            above.rotate_left(1),
+            above,
+            above.rotate_right(1),
+            row.rotate_left(1),
+            row.rotate_right(1),
+            below.rotate_left(1),
+            below,
+            below.rotate_right(1),
+        ];
+        let mut bits = [0u64; 4];
+        for neighbour in &neighbours {
+            let mut carry = *neighbour;
+            for bit in bits.iter_mut() {
+                let next_carry = *bit & carry;
+                *bit ^= carry;
+                carry = next_carry;
+            }
+        }
+        let mut born = 0;
+        let mut survives = 0;
+        for count in 0..=8 {
+            let mut exactly = !0;
+            for (i, bit) in bits.iter().enumerate() {
+                exactly &= if count & (1 << i) != 0 { *bit } else { !*bit };
+            }
+            if self.birth & (1 << count) != 0 {
+                born |= exactly;
+            }
+            if self.survival & (1 << count) != 0 {
+                survives |= exactly;
+            }
+        }
+        (row & survives) | (!row & born)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::explore::*;
+    use crate::pattern::Pattern;
+
+    fn to_pattern(grid: &[u64]) -> Pattern {
+        let mut cells = Vec::new();
+        for (y, row) in grid.iter().enumerate() {
+            for x in 0..GRID_SIZE {
+                if row & (1 << x) != 0 {
+                    cells.push((x as i32, y as i32));
+                }
+            }
+        }
+        Pattern::new(cells)
+    }
+
+    #[test]
+    fn it_steps_like_pattern() {
+        // Soup lies far from edges, so wrapping doesn't matter yet
+        let rule: Rule = "B36/S23".parse().unwrap();
+        let soup = soup_grid(7);
+        let next = Stepper::new(&rule).step(&soup);
+        assert_eq!(to_pattern(&next), to_pattern(&soup).step(&rule));
+    }
+
+    #[test]
+    fn it_classifies_known_rules() {
+        let soups: Vec<Vec<u64>> = (0..4).map(soup_grid).collect();
+        let class = |rule: &str| classify_rule(&rule.parse().unwrap(), &soups).class;
+        assert_eq!(class("B/S"), RuleClass::Dying);
+        assert_eq!(class("B/S012345678"), RuleClass::Stable);
+        assert_eq!(class("B36/S125"), RuleClass::Chaotic);
+        assert_eq!(class("B2/S"), RuleClass::Exploding);
+        assert_eq!(class("B3/S012345678"), RuleClass::Exploding);
+    }
+
+    #[test]
+    fn it_parses_saved_list() {
+        let list = explore(&"B3/S23:B4/S".parse().unwrap(), 3, 1, 5, 2);
+        assert_eq!(list.len(), 3);
+        assert!(list.reports.iter().all(|report| report.rule.is_born(3)
+            && !report.rule.is_born(4)
+            && report.rule.survives(2)));
+        let parsed = RuleList::parse(&list.to_text()).unwrap();
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed.reports[0].rule, list.reports[0].rule);
+        assert_eq!(parsed.reports[0].class, list.reports[0].class);
+    }
+}
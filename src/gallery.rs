@@ -0,0 +1,196 @@
+use std::fs;
+use std::path::Path;
+
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+
+use crate::app::WindowCanvas;
+use crate::pattern::Pattern;
+use crate::renderer::{Render, Renderer};
+use crate::rule::Rule;
+
+pub const DEFAULT_GALLERY_FILE: &str = "gallery.txt";
+const FILE_HEADER: &str = "# game_of_life gallery";
+/// Thumbnails in each row and column of grid page.
+pub const GRID_TILES: usize = 4;
+const TILE_LABEL_HEIGHT: i32 = 22;
+const TILE_LABEL_LENGTH: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GalleryEntry {
+    pub label: String,
+    pub pattern: Pattern,
+}
+
+/// Collection of patterns browsed one by one in the editor.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Gallery {
+    /// Rule patterns were found under.
+    pub rule: Option<Rule>,
+    pub entries: Vec<GalleryEntry>,
+}
+
+impl Gallery {
+    pub fn new(rule: Option<Rule>) -> Self {
+        Self {
+            rule,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, label: String, pattern: Pattern) {
+        self.entries.push(GalleryEntry { label, pattern });
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// One `label: rows` line per pattern, rows as in `Pattern::to_rows`.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\n", FILE_HEADER);
+        if let Some(rule) = self.rule {
+            text.push_str(&format!("rule {}\n", rule));
+        }
+        for entry in &self.entries {
+            text.push_str(&format!("{}: {}\n", entry.label, entry.pattern.to_rows()));
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut gallery = Self::new(None);
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(rule) = line.strip_prefix("rule ") {
+                gallery.rule = Some(
+                    rule.parse()
+                        .map_err(|e| format!("Line {}: {}", number + 1, e))?,
+                );
+                continue;
+            }
+            match line.rfind(": ") {
+                Some(index) => gallery.push(
+                    line[..index].to_string(),
+                    Pattern::from_rows(&line[index + 2..]),
+                ),
+                None => {
+                    return Err(format!(
+                        "Line {}: expected \"label: rows\", found {:?}",
+                        number + 1,
+                        line
+                    ))
+                }
+            }
+        }
+        Ok(gallery)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let text = fs::read_to_string(path.as_ref())
+            .map_err(|e| format!("Can't read {}: {}", path.as_ref().display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.as_ref().display(), e))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        fs::write(path.as_ref(), self.to_text())
+            .map_err(|e| format!("Can't write {}: {}", path.as_ref().display(), e))
+    }
+}
+
+/// Page of gallery drawn as thumbnails with shortened labels.
+pub struct GalleryGrid<'g> {
+    pub rect: Rect,
+    pub gallery: &'g Gallery,
+    pub page: usize,
+}
+
+impl<'g> GalleryGrid<'g> {
+    pub fn pages(gallery: &Gallery) -> usize {
+        gallery.len().div_ceil(GRID_TILES * GRID_TILES).max(1)
+    }
+
+    fn tile(&self, slot: usize) -> Rect {
+        let width = self.rect.width() / GRID_TILES as u32;
+        let height = self.rect.height() / GRID_TILES as u32;
+        Rect::new(
+            self.rect.x() + (slot % GRID_TILES) as i32 * width as i32,
+            self.rect.y() + (slot / GRID_TILES) as i32 * height as i32,
+            width,
+            height,
+        )
+    }
+
+    fn entries(&self) -> impl Iterator<Item = (usize, &GalleryEntry)> {
+        let per_page = GRID_TILES * GRID_TILES;
+        self.gallery
+            .entries
+            .iter()
+            .enumerate()
+            .skip(self.page * per_page)
+            .take(per_page)
+    }
+
+    /// Index of entry whose thumbnail is under point.
+    pub fn entry_at(&self, point: Point) -> Option<usize> {
+        self.entries()
+            .enumerate()
+            .find(|(slot, _)| self.tile(*slot).contains_point(point))
+            .map(|(_, (index, _))| index)
+    }
+}
+
+impl<'g> Render for GalleryGrid<'g> {
+    fn render(&self, canvas: &mut WindowCanvas, renderer: &mut Renderer) {
+        let frame = Color::RGBA(200, 200, 200, 255);
+        let alive = Color::RGBA(0, 0, 0, 255);
+        renderer.render_rect(canvas, &self.rect, &Color::RGBA(255, 255, 255, 255));
+        for (slot, (index, entry)) in self.entries().enumerate() {
+            let tile = self.tile(slot);
+            renderer.render_frame(canvas, &tile, &frame);
+            let (pattern, _) = entry.pattern.normalized();
+            if let Some(bounds) = pattern.bounds() {
+                let area = (tile.width() as i32 - 6, tile.height() as i32 - TILE_LABEL_HEIGHT - 6);
+                let cell = (area.0 / (bounds.max_x + 1))
+                    .min(area.1 / (bounds.max_y + 1))
+                    .max(1);
+                let left = tile.x() + 3 + (area.0 - cell * (bounds.max_x + 1)).max(0) / 2;
+                let top = tile.y() + 3 + (area.1 - cell * (bounds.max_y + 1)).max(0) / 2;
+                for (x, y) in pattern.cells() {
+                    let rect = Rect::new(left + x * cell, top + y * cell, cell as u32, cell as u32);
+                    if tile.contains_rect(rect) {
+                        renderer.render_rect(canvas, &rect, &alive);
+                    }
+                }
+            }
+            let label: String = entry.label.chars().take(TILE_LABEL_LENGTH).collect();
+            renderer.render_text(
+                canvas,
+                &format!("{} {}", index + 1, label),
+                Point::new(tile.x() + 3, tile.bottom() - TILE_LABEL_HEIGHT),
+                &alive,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gallery::*;
+
+    #[test]
+    fn it_parses_saved_gallery() {
+        let mut gallery = Gallery::new(Some(Rule::conway()));
+        gallery.push("block".to_string(), Pattern::from_rows("OO/OO"));
+        gallery.push("p2 oscillator: 3 cells".to_string(), Pattern::from_rows("OOO"));
+        assert_eq!(Gallery::parse(&gallery.to_text()), Ok(gallery));
+        assert!(Gallery::parse("OO/OO").is_err());
+    }
+}
@@ -0,0 +1,460 @@
+use std::cmp::Reverse;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+use rand::prng::XorShiftRng;
+use rand::Rng;
+
+use crate::methuselah;
+use crate::parallel::parallel_map;
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+use crate::soup::{seeded_rng, DEFAULT_DENSITY};
+
+pub const DEFAULT_GA_POPULATION: usize = 40;
+pub const DEFAULT_GA_SIZE: i32 = 8;
+/// Side of the board, seed pattern has to fit on it to be shown.
+pub const MAX_GA_SIZE: i32 = 20;
+pub const DEFAULT_ELITE: usize = 4;
+pub const DEFAULT_MUTATION: f64 = 0.02;
+pub const DEFAULT_FITNESS_GENERATIONS: u64 = 5000;
+/// Candidates competing for every parent slot.
+const TOURNAMENT: usize = 3;
+
+/// What makes a seed pattern good.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fitness {
+    /// Generations until what is left repeats.
+    Lifespan,
+    /// Population once settled, escaped spaceships included.
+    FinalPopulation,
+    /// Largest population reached.
+    MaxPopulation,
+    /// Cells of the settled pattern mirrored by its best reflection or rotation.
+    Symmetry,
+}
+
+pub const FITNESSES: [Fitness; 4] = [
+    Fitness::Lifespan,
+    Fitness::FinalPopulation,
+    Fitness::MaxPopulation,
+    Fitness::Symmetry,
+];
+
+impl fmt::Display for Fitness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Fitness::Lifespan => "lifespan",
+            Fitness::FinalPopulation => "final-population",
+            Fitness::MaxPopulation => "max-population",
+            Fitness::Symmetry => "symmetry",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Fitness {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FITNESSES
+            .iter()
+            .find(|fitness| fitness.to_string().eq_ignore_ascii_case(s))
+            .cloned()
+            .ok_or_else(|| {
+                format!(
+                    "Unknown fitness {:?}, expected lifespan, final-population, max-population or symmetry",
+                    s
+                )
+            })
+    }
+}
+
+/// Seed pattern within `size` by `size` box starting at 0,0, with its score.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub pattern: Pattern,
+    pub score: u64,
+}
+
+/// State after one generation of the algorithm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub generation: u64,
+    pub best: Candidate,
+    pub mean: f64,
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Generation {}: best {} ({} cells), mean {:.1}",
+            self.generation,
+            self.best.score,
+            self.best.pattern.population(),
+            self.mean
+        )
+    }
+}
+
+/// Evolves population of small seed patterns towards higher fitness. Best candidates
+/// survive unchanged, the rest are children of tournament winners, crossed over along
+/// a random row or column and mutated cell by cell. The same seed gives the same run.
+#[derive(Debug, Clone)]
+pub struct GeneticSearch {
+    rule: Rule,
+    fitness: Fitness,
+    size: i32,
+    population_size: usize,
+    elite: usize,
+    mutation: f64,
+    generations: u64,
+    seed: u64,
+    rng: XorShiftRng,
+    generation: u64,
+    population: Vec<Candidate>,
+}
+
+impl GeneticSearch {
+    pub fn new(rule: Rule, fitness: Fitness) -> Self {
+        Self {
+            rule,
+            fitness,
+            size: DEFAULT_GA_SIZE,
+            population_size: DEFAULT_GA_POPULATION,
+            elite: DEFAULT_ELITE,
+            mutation: DEFAULT_MUTATION,
+            generations: DEFAULT_FITNESS_GENERATIONS,
+            seed: 0,
+            rng: seeded_rng(0),
+            generation: 0,
+            population: Vec::new(),
+        }
+    }
+
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    /// Side of the box seed patterns live in.
+    pub fn with_size(mut self, size: i32) -> Self {
+        self.size = size.clamp(1, MAX_GA_SIZE);
+        self
+    }
+
+    pub fn with_population(mut self, population: usize) -> Self {
+        self.population_size = population.max(2);
+        self
+    }
+
+    /// Best candidates copied to next generation unchanged.
+    pub fn with_elite(mut self, elite: usize) -> Self {
+        self.elite = elite;
+        self
+    }
+
+    /// Probability of flipping each cell of a child.
+    pub fn with_mutation(mut self, mutation: f64) -> Self {
+        self.mutation = mutation.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Generations every candidate is evolved for at most.
+    pub fn with_generations(mut self, generations: u64) -> Self {
+        self.generations = generations;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self.rng = seeded_rng(seed);
+        self
+    }
+
+    pub fn fitness(&self) -> Fitness {
+        self.fitness
+    }
+
+    pub fn size(&self) -> i32 {
+        self.size
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn score(&self, pattern: &Pattern) -> u64 {
+        let lifespan = methuselah::measure(pattern, &self.rule, self.generations);
+        match self.fitness {
+            Fitness::Lifespan => lifespan.lifespan.unwrap_or(self.generations),
+            Fitness::FinalPopulation => lifespan
+                .census
+                .entries
+                .iter()
+                .map(|entry| entry.count * entry.key.population())
+                .sum::<usize>() as u64,
+            Fitness::MaxPopulation => lifespan.max_population as u64,
+            Fitness::Symmetry => {
+                let mut settled = pattern.clone();
+                for _ in 0..lifespan.lifespan.unwrap_or(self.generations) {
+                    settled = settled.step(&self.rule);
+                }
+                symmetric_cells(&settled) as u64
+            }
+        }
+    }
+
+    /// Random soups at first, then one generation of selection, crossover and mutation.
+    pub fn next_generation(&mut self, threads: usize) -> Progress {
+        self.next_generation_until(threads, &AtomicBool::new(false))
+            .unwrap()
+    }
+
+    /// Like `next_generation`, but gives up as soon as `stop` is set, leaving population as
+    /// it was.
+    pub fn next_generation_until(&mut self, threads: usize, stop: &AtomicBool) -> Option<Progress> {
+        let mut next: Vec<Candidate> = self
+            .population
+            .iter()
+            .take(self.elite.min(self.population_size))
+            .cloned()
+            .collect();
+        let mut children = Vec::new();
+        while next.len() + children.len() < self.population_size {
+            let child = if self.population.is_empty() {
+                self.random_pattern()
+            } else {
+                let first = self.select();
+                let second = self.select();
+                let child = self.crossover(&first, &second);
+                self.mutate(&child)
+            };
+            children.push(child);
+        }
+        next.extend(self.evaluate(children, threads, stop)?);
+        // Stable sort keeps older candidates first among equal ones
+        next.sort_by_key(|candidate| Reverse(candidate.score));
+        self.population = next;
+        self.generation += 1;
+        let total: u64 = self
+            .population
+            .iter()
+            .map(|candidate| candidate.score)
+            .sum();
+        Some(Progress {
+            generation: self.generation,
+            best: self.population[0].clone(),
+            mean: total as f64 / self.population.len() as f64,
+        })
+    }
+
+    /// Runs `generations` generations and returns final population.
+    pub fn run<F: FnMut(&Progress)>(
+        &mut self,
+        generations: u64,
+        threads: usize,
+        mut progress: F,
+    ) -> Vec<Candidate> {
+        for _ in 0..generations {
+            progress(&self.next_generation(threads));
+        }
+        self.population.clone()
+    }
+
+    /// Keeps evolving on background thread and reports every generation until stopped.
+    pub fn spawn(mut self, threads: usize) -> Evolution {
+        let (sender, receiver) = channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        thread::Builder::new()
+            .name("genetic".to_string())
+            .spawn(move || {
+                while let Some(progress) = self.next_generation_until(threads, &stopped) {
+                    if sender.send(progress).is_err() {
+                        break;
+                    }
+                }
+            })
+            .unwrap();
+        Evolution {
+            progress: receiver,
+            stop,
+        }
+    }
+
+    /// Scores of `patterns`, none if `stop` was set before all of them were scored.
+    fn evaluate(
+        &self,
+        patterns: Vec<Pattern>,
+        threads: usize,
+        stop: &AtomicBool,
+    ) -> Option<Vec<Candidate>> {
+        // Candidates left once stopped are skipped, the whole generation is dropped anyway
+        let scores = parallel_map(&patterns, threads, |pattern| {
+            if stop.load(Ordering::Relaxed) {
+                0
+            } else {
+                self.score(pattern)
+            }
+        });
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+        Some(
+            patterns
+                .into_iter()
+                .zip(scores)
+                .map(|(pattern, score)| Candidate { pattern, score })
+                .collect(),
+        )
+    }
+
+    fn random_pattern(&mut self) -> Pattern {
+        let mut cells = Vec::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if self.rng.gen_bool(DEFAULT_DENSITY) {
+                    cells.push((x, y));
+                }
+            }
+        }
+        Pattern::new(cells)
+    }
+
+    /// Best of a few random candidates.
+    fn select(&mut self) -> Pattern {
+        let count = self.population.len();
+        let winner = (0..TOURNAMENT)
+            .map(|_| self.rng.gen_range(0, count))
+            .min()
+            .unwrap_or(0);
+        self.population[winner].pattern.clone()
+    }
+
+    /// Cells before random row or column come from `first`, the rest from `second`.
+    fn crossover(&mut self, first: &Pattern, second: &Pattern) -> Pattern {
+        let cut = self.rng.gen_range(0, self.size + 1);
+        let by_rows = self.rng.gen_bool(0.5);
+        let before = |(x, y): &&(i32, i32)| if by_rows { *y < cut } else { *x < cut };
+        let mut cells: Vec<(i32, i32)> = first.cells().iter().filter(before).cloned().collect();
+        cells.extend(second.cells().iter().filter(|cell| !before(cell)));
+        Pattern::new(cells)
+    }
+
+    fn mutate(&mut self, pattern: &Pattern) -> Pattern {
+        let mut cells = Vec::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if pattern.contains(x, y) != self.rng.gen_bool(self.mutation) {
+                    cells.push((x, y));
+                }
+            }
+        }
+        Pattern::new(cells)
+    }
+}
+
+/// Most cells any rotation or reflection of the pattern shares with it.
+fn symmetric_cells(pattern: &Pattern) -> usize {
+    let pattern = pattern.normalized().0;
+    (1..8)
+        .map(|transform| {
+            let image = pattern.transformed(transform).normalized().0;
+            image
+                .cells()
+                .iter()
+                .filter(|(x, y)| pattern.contains(*x, *y))
+                .count()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Handle of search running on background thread, dropping it stops the search without
+/// waiting for candidates being scored.
+pub struct Evolution {
+    progress: Receiver<Progress>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Evolution {
+    /// Newest finished generation, if any finished since last poll.
+    pub fn poll(&mut self) -> Option<Progress> {
+        self.progress.try_iter().last()
+    }
+}
+
+impl Drop for Evolution {
+    fn drop(&mut self) {
+        // Thread notices within one score and exits on its own
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::genetic::*;
+
+    fn search() -> GeneticSearch {
+        GeneticSearch::new(Rule::conway(), Fitness::Lifespan)
+            .with_size(4)
+            .with_population(8)
+            .with_elite(2)
+            .with_generations(60)
+            .with_seed(7)
+    }
+
+    #[test]
+    fn it_reproduces_run_from_seed() {
+        let first = search().run(3, 2, |_| {});
+        let second = search().run(3, 1, |_| {});
+        assert_eq!(first, second);
+        assert_eq!(first.len(), 8);
+        assert!(first.iter().all(|candidate| candidate
+            .pattern
+            .cells()
+            .iter()
+            .all(|(x, y)| *x < 4 && *y < 4)));
+    }
+
+    #[test]
+    fn it_never_loses_best_candidate() {
+        let mut search = search();
+        let mut best = 0;
+        for _ in 0..3 {
+            let progress = search.next_generation(2);
+            assert!(progress.best.score >= best);
+            assert_eq!(progress.best.score, search.score(&progress.best.pattern));
+            best = progress.best.score;
+        }
+    }
+
+    #[test]
+    fn it_stops_between_candidates() {
+        let mut search = search();
+        search.next_generation(2);
+        let population = search.run(0, 1, |_| {});
+        assert_eq!(
+            search.next_generation_until(2, &AtomicBool::new(true)),
+            None
+        );
+        assert_eq!(search.run(0, 1, |_| {}), population);
+        // Dropping doesn't wait for long scoring to finish
+        drop(search.with_generations(50_000).spawn(1));
+    }
+
+    #[test]
+    fn it_scores_symmetry() {
+        let search = GeneticSearch::new(Rule::conway(), Fitness::Symmetry);
+        assert_eq!(search.score(&Pattern::from_rows("OO/OO")), 4);
+        assert_eq!(search.score(&Pattern::from_rows("OOO")), 3);
+        let fitness: Fitness = "max-population".parse().unwrap();
+        assert_eq!(fitness, Fitness::MaxPopulation);
+    }
+}
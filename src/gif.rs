@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// Codes never get wider than this, dictionary starts over once it is full.
+const MAX_CODE_BITS: u32 = 12;
+/// Longest data sub-block.
+const BLOCK_LENGTH: usize = 255;
+/// Frame stays in place until the next one is drawn over it.
+const DISPOSE_NONE: u8 = 1;
+/// Frame area is cleared to transparent before the next one is drawn.
+const DISPOSE_TO_BACKGROUND: u8 = 2;
+
+/// Animated GIF written frame by frame, every frame covers the whole image and uses one
+/// global palette of at most 256 colours.
+pub struct GifWriter<W: Write> {
+    writer: W,
+    width: u16,
+    height: u16,
+    /// Bits per pixel index, palette is padded to `1 << depth` colours.
+    depth: u32,
+    transparent: Option<u8>,
+}
+
+impl<W: Write> GifWriter<W> {
+    /// Writes header, palette and loop count, 0 loops forever.
+    pub fn new(
+        mut writer: W,
+        width: u16,
+        height: u16,
+        palette: &[[u8; 3]],
+        loop_count: u16,
+    ) -> io::Result<Self> {
+        if palette.is_empty() || palette.len() > 256 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "palette must have 1 to 256 colours",
+            ));
+        }
+        let depth = (1..=8).find(|bits| 1 << bits >= palette.len()).unwrap_or(8);
+        writer.write_all(b"GIF89a")?;
+        writer.write_all(&width.to_le_bytes())?;
+        writer.write_all(&height.to_le_bytes())?;
+        // Global palette present, 8 bit colour resolution and palette size
+        writer.write_all(&[0xf0 | (depth - 1) as u8, 0, 0])?;
+        for index in 0..1 << depth {
+            writer.write_all(palette.get(index).unwrap_or(&[0, 0, 0]))?;
+        }
+        writer.write_all(&[0x21, 0xff, 11])?;
+        writer.write_all(b"NETSCAPE2.0")?;
+        writer.write_all(&[3, 1])?;
+        writer.write_all(&loop_count.to_le_bytes())?;
+        writer.write_all(&[0])?;
+        Ok(Self {
+            writer,
+            width,
+            height,
+            depth,
+            transparent: None,
+        })
+    }
+
+    /// Pixels of this palette index are see-through in every following frame.
+    pub fn with_transparent(mut self, index: u8) -> Self {
+        self.transparent = Some(index);
+        self
+    }
+
+    /// Adds frame of `width * height` palette indices shown for `delay` hundredths of second.
+    pub fn add_frame(&mut self, pixels: &[u8], delay: u16) -> io::Result<()> {
+        if pixels.len() != self.width as usize * self.height as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "frame has {} pixels, expected {}x{}",
+                    pixels.len(),
+                    self.width,
+                    self.height
+                ),
+            ));
+        }
+        // Graphic control: disposal, delay and transparency. Opaque frames cover the
+        // previous one, transparent ones would let it show through unless it is cleared first
+        let disposal = match self.transparent {
+            Some(_) => DISPOSE_TO_BACKGROUND,
+            None => DISPOSE_NONE,
+        };
+        let flags = disposal << 2 | self.transparent.is_some() as u8;
+        self.writer.write_all(&[0x21, 0xf9, 4, flags])?;
+        self.writer.write_all(&delay.to_le_bytes())?;
+        self.writer.write_all(&[self.transparent.unwrap_or(0), 0])?;
+        // Image descriptor at 0,0 without local palette
+        self.writer.write_all(&[0x2c, 0, 0, 0, 0])?;
+        self.writer.write_all(&self.width.to_le_bytes())?;
+        self.writer.write_all(&self.height.to_le_bytes())?;
+        self.writer.write_all(&[0])?;
+        let min_code_size = self.depth.max(2);
+        self.writer.write_all(&[min_code_size as u8])?;
+        for block in lzw_encode(pixels, min_code_size).chunks(BLOCK_LENGTH) {
+            self.writer.write_all(&[block.len() as u8])?;
+            self.writer.write_all(block)?;
+        }
+        self.writer.write_all(&[0])
+    }
+
+    /// Writes trailer and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.write_all(&[0x3b])?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Packs codes of varying width into bytes, least significant bit first.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u32) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// GIF flavour of LZW: starts with clear code, widens codes as the dictionary grows and
+/// clears it when 12 bit codes run out.
+pub fn lzw_encode(pixels: &[u8], min_code_size: u32) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+    let mut output = BitWriter::default();
+    let mut dictionary: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut width = min_code_size + 1;
+    let mut next = end + 1;
+    output.write(clear, width);
+    let mut pixels = pixels.iter();
+    let mut current = match pixels.next() {
+        Some(pixel) => *pixel as u16,
+        None => {
+            output.write(end, width);
+            return output.finish();
+        }
+    };
+    for &pixel in pixels {
+        if let Some(code) = dictionary.get(&(current, pixel)) {
+            current = *code;
+            continue;
+        }
+        output.write(current, width);
+        if next < 1 << MAX_CODE_BITS {
+            dictionary.insert((current, pixel), next);
+            // Decoder learns the code one step later, so widen once it has
+            if next == 1 << width && width < MAX_CODE_BITS {
+                width += 1;
+            }
+            next += 1;
+        } else {
+            output.write(clear, width);
+            dictionary.clear();
+            width = min_code_size + 1;
+            next = end + 1;
+        }
+        current = pixel as u16;
+    }
+    output.write(current, width);
+    output.write(end, width);
+    output.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gif::*;
+
+    /// Reference decoder following the GIF specification.
+    fn lzw_decode(bytes: &[u8], min_code_size: u32) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let (mut position, mut width) = (0, min_code_size + 1);
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut previous: Option<Vec<u8>> = None;
+        let mut pixels = Vec::new();
+        loop {
+            let mut code = 0;
+            for bit in 0..width as usize {
+                let byte = bytes[(position + bit) / 8];
+                code |= ((byte >> ((position + bit) % 8)) as usize & 1) << bit;
+            }
+            position += width as usize;
+            if code == clear {
+                table = (0..clear).map(|index| vec![index as u8]).collect();
+                table.push(Vec::new());
+                table.push(Vec::new());
+                width = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == clear + 1 {
+                return pixels;
+            }
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => {
+                    let mut entry = previous.clone();
+                    entry.push(previous[0]);
+                    entry
+                }
+                (None, None) => panic!("code {} before any pixel", code),
+            };
+            if let Some(mut previous) = previous {
+                previous.push(entry[0]);
+                table.push(previous);
+            }
+            if table.len() == 1 << width && width < MAX_CODE_BITS {
+                width += 1;
+            }
+            pixels.extend(&entry);
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn it_round_trips_lzw() {
+        let stripes: Vec<u8> = (0..3000).map(|index| (index / 7 % 4) as u8).collect();
+        assert_eq!(lzw_decode(&lzw_encode(&stripes, 2), 2), stripes);
+        // Enough distinct sequences to fill the dictionary and clear it
+        let noise: Vec<u8> = (0..40000u32)
+            .map(|index| (index.wrapping_mul(2654435761) >> 13) as u8 % 16)
+            .collect();
+        assert_eq!(lzw_decode(&lzw_encode(&noise, 4), 4), noise);
+        assert_eq!(lzw_decode(&lzw_encode(&[], 2), 2), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn it_writes_animation() {
+        let palette = [[255, 255, 255], [0, 0, 0], [200, 200, 200]];
+        let mut gif = GifWriter::new(Vec::new(), 2, 2, &palette, 0).unwrap();
+        gif.add_frame(&[0, 1, 1, 0], 10).unwrap();
+        gif.add_frame(&[1, 0, 0, 1], 10).unwrap();
+        assert!(gif.add_frame(&[0], 10).is_err());
+        let bytes = gif.finish().unwrap();
+        assert_eq!(&bytes[..6], b"GIF89a");
+        // 4 colour palette padded from 3
+        assert_eq!(bytes[10], 0xf1);
+        // Loop extension follows 13 byte header and 12 byte palette
+        assert_eq!(&bytes[25..28], &[0x21, 0xff, 11]);
+        assert_eq!(&bytes[28..39], b"NETSCAPE2.0");
+        // Graphic control of the first frame after 19 byte loop extension
+        assert_eq!(&bytes[44..48], &[0x21, 0xf9, 4, DISPOSE_NONE << 2]);
+        assert_eq!(bytes.iter().filter(|byte| **byte == 0x2c).count(), 2);
+        assert_eq!(bytes.last(), Some(&0x3b));
+        // Dead cells must not leave ghosts behind transparent pixels
+        let mut gif = GifWriter::new(Vec::new(), 2, 2, &palette, 0)
+            .unwrap()
+            .with_transparent(0);
+        gif.add_frame(&[0, 1, 1, 0], 10).unwrap();
+        let bytes = gif.finish().unwrap();
+        assert_eq!(
+            &bytes[44..48],
+            &[0x21, 0xf9, 4, DISPOSE_TO_BACKGROUND << 2 | 1]
+        );
+        assert_eq!(&bytes[50..52], &[0, 0]);
+    }
+}
@@ -0,0 +1,105 @@
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+
+use crate::app::WindowCanvas;
+use crate::renderer::{Render, Renderer};
+use crate::statistics::{GenerationStats, RECENT_GENERATIONS};
+
+/// Height taken by labels above the chart.
+const LEGEND_HEIGHT: i32 = 95;
+
+/// Scrolling chart of population, births and deaths of recent generations.
+pub struct PopulationGraph<'s> {
+    pub rect: Rect,
+    pub series: &'s [GenerationStats],
+}
+
+impl<'s> PopulationGraph<'s> {
+    fn chart_rect(&self) -> Rect {
+        Rect::new(
+            self.rect.x(),
+            self.rect.y() + LEGEND_HEIGHT,
+            self.rect.width(),
+            self.rect.height() - LEGEND_HEIGHT as u32,
+        )
+    }
+
+    fn points<F>(&self, chart: &Rect, max: usize, value: F) -> Vec<Point>
+    where
+        F: Fn(&GenerationStats) -> usize,
+    {
+        let width = chart.width() as i32 - 1;
+        let height = chart.height() as i32 - 1;
+        self.series
+            .iter()
+            .enumerate()
+            .map(|(i, stats)| {
+                Point::new(
+                    chart.x() + i as i32 * width / (RECENT_GENERATIONS as i32 - 1),
+                    chart.bottom() - 1 - (value(stats) as i32 * height / max as i32),
+                )
+            })
+            .collect()
+    }
+
+    fn render_series(
+        &self,
+        canvas: &mut WindowCanvas,
+        renderer: &mut Renderer,
+        points: &[Point],
+        color: &Color,
+    ) {
+        for line in points.windows(2) {
+            renderer.render_line(canvas, line[0], line[1], color);
+        }
+    }
+}
+
+impl<'s> Render for PopulationGraph<'s> {
+    fn render(&self, canvas: &mut WindowCanvas, renderer: &mut Renderer) {
+        let population_color = Color::RGBA(20, 20, 20, 255);
+        let births_color = Color::RGBA(0, 160, 0, 255);
+        let deaths_color = Color::RGBA(200, 0, 0, 255);
+        let chart = self.chart_rect();
+        renderer.render_rect(canvas, &chart, &Color::RGBA(240, 240, 240, 255));
+        renderer.render_frame(canvas, &chart, &Color::RGBA(200, 200, 200, 255));
+
+        let last = match self.series.last() {
+            Some(last) => last,
+            None => return,
+        };
+        let x = self.rect.x() + 5;
+        renderer.render_text(
+            canvas,
+            &format!("Population {}", last.population),
+            Point::new(x, self.rect.y()),
+            &population_color,
+        );
+        renderer.render_text(
+            canvas,
+            &format!("Births {}", last.births),
+            Point::new(x, self.rect.y() + 25),
+            &births_color,
+        );
+        renderer.render_text(
+            canvas,
+            &format!("Deaths {}", last.deaths),
+            Point::new(x, self.rect.y() + 50),
+            &deaths_color,
+        );
+
+        let max = self
+            .series
+            .iter()
+            .map(|stats| stats.population.max(stats.births).max(stats.deaths))
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let population = self.points(&chart, max, |stats| stats.population);
+        let births = self.points(&chart, max, |stats| stats.births);
+        let deaths = self.points(&chart, max, |stats| stats.deaths);
+        self.render_series(canvas, renderer, &births, &births_color);
+        self.render_series(canvas, renderer, &deaths, &deaths_color);
+        self.render_series(canvas, renderer, &population, &population_color);
+    }
+}
@@ -0,0 +1,369 @@
+use crate::apgcode;
+use crate::cli::Options;
+use crate::collision::{self, CollisionLab};
+use crate::enumerate;
+use crate::explore::{self, DEFAULT_RULES_FILE};
+use crate::gallery::{Gallery, DEFAULT_GALLERY_FILE};
+use crate::genetic::Candidate;
+use crate::methuselah;
+use crate::pattern::Pattern;
+use crate::pattern_file::{PatternFile, PatternFormat};
+use crate::predecessor::{Predecessor, PredecessorSearch};
+use crate::spaceship::{ShipSearch, ShipSpeed};
+use crate::search;
+use crate::session::Session;
+use crate::stability::{OnStable, Stability};
+use crate::statistics::ExportFormat;
+use crate::svg;
+
+/// Runs simulation without window, prints every detected stabilization.
+pub fn run(options: &Options) -> Result<(), String> {
+    let mut simulation = options.simulation(OnStable::Pause);
+    let on_stable = options.on_stable.unwrap_or(OnStable::Pause);
+    println!(
+        "Rule {}, {} board, population {}",
+        options.rule,
+        simulation.map().topology(),
+        simulation.map().population()
+    );
+
+    let mut stability = Stability::Evolving;
+    for _ in 0..options.generations {
+        simulation.next_generation();
+        let current = simulation.stability();
+        if current != stability && current.is_settled() {
+            println!("Generation {}: {}", simulation.generation(), current);
+        }
+        stability = current;
+        if on_stable == OnStable::Pause && stability.is_settled() {
+            break;
+        }
+    }
+
+    println!(
+        "Finished at generation {}, population {}, {}",
+        simulation.generation(),
+        simulation.map().population(),
+        simulation.stability()
+    );
+
+    if options.census {
+        print!("{}", simulation.census().to_text());
+    }
+
+    if let Some(path) = &options.save_pattern {
+        let format = options
+            .pattern_format
+            .unwrap_or_else(|| PatternFormat::from_path(path));
+        PatternFile::from_map(simulation.map(), &options.rule).save(path, format)?;
+        println!("Pattern saved to {}", path);
+    }
+
+    if let Some(path) = &options.poster {
+        options.poster().save(simulation.map(), path)?;
+        println!("Poster saved to {}", path);
+    }
+
+    if let Some(path) = &options.svg {
+        let map = simulation.map();
+        options.svg().save(
+            map,
+            &svg::board_area(map),
+            &options.rule,
+            simulation.generation(),
+            path,
+        )?;
+        println!("SVG saved to {}", path);
+    }
+
+    if let Some(path) = &options.save_session {
+        let session = Session {
+            svg: options.svg(),
+            ..Session::from_snapshot(&simulation.snapshot())
+        };
+        session.save(path)?;
+        println!("Session saved to {}", path);
+    }
+
+    if let Some(path) = &options.stats {
+        simulation
+            .statistics()
+            .save(path, ExportFormat::from_path(path))?;
+        println!("Statistics saved to {}", path);
+    }
+    Ok(())
+}
+
+/// Runs soup search and prints aggregated census.
+pub fn search(options: &Options, soups: u64) -> Result<(), String> {
+    let state = search::run(
+        options.search_settings(),
+        soups,
+        options.threads,
+        &options.search_file,
+    )?;
+    print!("{}", state.report());
+    println!("Results saved to {}", options.search_file);
+    Ok(())
+}
+
+/// Enumerates small still lifes and oscillators and saves them as gallery.
+pub fn enumerate(options: &Options, size: usize) -> Result<(), String> {
+    let found = enumerate::enumerate(&options.rule, size, options.max_period, options.threads);
+    for object in &found {
+        println!("{}: {}", object.label(), object.key.to_rows());
+    }
+    println!(
+        "Found {} still lifes and oscillators up to period {} in {}x{} box, rule {}",
+        found.len(),
+        options.max_period,
+        size,
+        size,
+        options.rule
+    );
+    let path = options
+        .gallery
+        .clone()
+        .unwrap_or_else(|| DEFAULT_GALLERY_FILE.to_string());
+    enumerate::to_gallery(&options.rule, &found).save(&path)?;
+    println!("Gallery saved to {}", path);
+    Ok(())
+}
+
+/// Searches spaceships, found ones are saved as gallery when `--gallery` is given.
+pub fn ship_search(options: &Options, speed: ShipSpeed) -> Result<(), String> {
+    println!(
+        "Searching {} spaceships {} columns wide, {}, rule {}",
+        speed, options.ship_width, options.ship_symmetry, options.rule
+    );
+    let mut search = ShipSearch::new(&options.rule, speed, options.ship_width)
+        .with_symmetry(options.ship_symmetry)
+        .with_max_length(options.max_length)
+        .with_max_queue(options.max_queue);
+    let ships = search.run(options.ships, |progress| println!("{}", progress));
+    if search.dropped() > 0 {
+        println!(
+            "{} partial spaceships dropped to stay within --max-queue, the search isn't exhaustive",
+            search.dropped()
+        );
+    }
+    let mut gallery = Gallery::new(Some(options.rule));
+    for ship in &ships {
+        println!("{} cells: {}", ship.population(), ship.to_rows());
+        gallery.push(
+            format!("{} spaceship {} cells", speed, ship.population()),
+            ship.clone(),
+        );
+    }
+    println!("Found {} spaceships", ships.len());
+    if let Some(path) = &options.gallery {
+        gallery.save(path)?;
+        println!("Gallery saved to {}", path);
+    }
+    Ok(())
+}
+
+/// Looks for pattern evolving into `target` and checks it by running it forward.
+pub fn find_parent(options: &Options, target: &Pattern) -> Result<(), String> {
+    println!(
+        "Searching parent {} generations before {} within {} cells of it, rule {}",
+        options.parent_generations,
+        target.to_rows(),
+        options.parent_margin,
+        options.rule
+    );
+    let result = PredecessorSearch::new(options.rule)
+        .with_generations(options.parent_generations)
+        .with_margin(options.parent_margin)
+        .find(target);
+    match result {
+        Predecessor::Found(parent) => {
+            let mut evolved = parent.clone();
+            for _ in 0..options.parent_generations {
+                evolved = evolved.step(&options.rule);
+            }
+            println!("Parent: {}", parent.to_rows());
+            println!(
+                "Evolves into target: {}",
+                if evolved == *target { "yes" } else { "no" }
+            );
+        }
+        result => println!("Parent: {}", result),
+    }
+    Ok(())
+}
+
+/// Reports how long pattern lives and what it leaves behind.
+pub fn lifespan(options: &Options, pattern: &Pattern) -> Result<(), String> {
+    println!(
+        "Measuring {} cells for at most {} generations, rule {}",
+        pattern.population(),
+        options.max_generations,
+        options.rule
+    );
+    let lifespan = methuselah::measure(pattern, &options.rule, options.max_generations);
+    println!("{}", lifespan);
+    print!("{}", lifespan.census.to_text());
+    Ok(())
+}
+
+/// Measures every pattern of directory into CSV table.
+pub fn lifespan_dir(options: &Options, directory: &str) -> Result<(), String> {
+    let results = methuselah::measure_directory(
+        directory,
+        &options.rule,
+        options.max_generations,
+        &options.lifespan_file,
+    )?;
+    for (name, lifespan) in &results {
+        println!("{}: {}", name, lifespan);
+    }
+    println!(
+        "Measured {} patterns, table saved to {}",
+        results.len(),
+        options.lifespan_file
+    );
+    Ok(())
+}
+
+/// Classifies rules and saves them as list browsed in the window.
+pub fn explore(options: &Options, count: usize) -> Result<(), String> {
+    let seed = options.seed.unwrap_or(0);
+    println!(
+        "Exploring {} rules matching {} with {} soups from seed {}",
+        count, options.rule_filter, options.explore_soups, seed
+    );
+    let list = explore::explore(
+        &options.rule_filter,
+        count,
+        options.explore_soups,
+        seed,
+        options.threads,
+    );
+    let mut classes: Vec<(String, usize)> = Vec::new();
+    for report in &list.reports {
+        println!("{}", report);
+        let class = report.class.to_string();
+        match classes.iter_mut().find(|(name, _)| *name == class) {
+            Some((_, count)) => *count += 1,
+            None => classes.push((class, 1)),
+        }
+    }
+    for (class, count) in &classes {
+        println!("{} {}", count, class);
+    }
+    let path = options
+        .rules
+        .clone()
+        .unwrap_or_else(|| DEFAULT_RULES_FILE.to_string());
+    list.save(&path)?;
+    println!("Rules saved to {}", path);
+    Ok(())
+}
+
+/// Collides two objects in every arrangement and saves one example of each outcome as gallery.
+pub fn collide(options: &Options, first: &Pattern, second: &Pattern) -> Result<(), String> {
+    let lab = CollisionLab::new(options.rule, first.clone(), second.clone())
+        .with_range(options.lab_range);
+    println!(
+        "Colliding {} with {}: {} arrangements, rule {}",
+        first.to_rows(),
+        second.to_rows(),
+        lab.collisions().len(),
+        options.rule
+    );
+    let outcomes = lab.run(options.threads);
+    for outcome in &outcomes {
+        println!("{} x {}", outcome.collisions.len(), outcome.label);
+    }
+    let path = options
+        .gallery
+        .clone()
+        .unwrap_or_else(|| DEFAULT_GALLERY_FILE.to_string());
+    collision::to_gallery(&options.rule, &outcomes).save(&path)?;
+    println!("Gallery saved to {}", path);
+    Ok(())
+}
+
+/// Evolves seed patterns, prints every generation and saves final population as gallery.
+pub fn evolve(options: &Options, generations: u64) -> Result<(), String> {
+    let mut search = options.genetic_search();
+    println!(
+        "Evolving {} seed patterns in {}x{} box for {}, seed {}, rule {}",
+        options.ga_population,
+        search.size(),
+        search.size(),
+        search.fitness(),
+        search.seed(),
+        options.rule
+    );
+    let population = search.run(generations, options.threads, |progress| {
+        println!("{}", progress)
+    });
+    let mut gallery = Gallery::new(Some(options.rule));
+    let mut seen: Vec<&Candidate> = Vec::new();
+    for candidate in &population {
+        if seen.iter().any(|other| other.pattern == candidate.pattern) {
+            continue;
+        }
+        seen.push(candidate);
+        gallery.push(
+            format!("{} {}", search.fitness(), candidate.score),
+            candidate.pattern.clone(),
+        );
+    }
+    if let Some(best) = population.first() {
+        println!("Best {} {}: {}", search.fitness(), best.score, best.pattern.to_rows());
+    }
+    let path = options
+        .gallery
+        .clone()
+        .unwrap_or_else(|| DEFAULT_GALLERY_FILE.to_string());
+    gallery.save(&path)?;
+    println!("Gallery saved to {}", path);
+    Ok(())
+}
+
+/// Records generations of the run into animation or frame sequence.
+pub fn record(options: &Options, path: &str) -> Result<(), String> {
+    let mut simulation = options.simulation(OnStable::Continue);
+    let recorder = options.recorder();
+    if recorder.frame_count() == 0 {
+        return Err(format!(
+            "Nothing to record, --record-to {} is before --record-from {}",
+            recorder.to, recorder.from
+        ));
+    }
+    let progress = |generation| {
+        if generation % 100 == 0 {
+            println!("Generation {}", generation);
+        }
+    };
+    let frames = recorder.record(&mut simulation, path, progress)?;
+    println!(
+        "{} frames of generations {} to {} saved to {}",
+        frames,
+        recorder.from,
+        simulation.generation(),
+        path
+    );
+    Ok(())
+}
+
+/// Decodes apgcode into rows, or encodes rows of an object in current rule.
+pub fn apgcode(options: &Options, code: &str) -> Result<(), String> {
+    if code.contains('_') {
+        let object = apgcode::decode(code)?;
+        println!("{} cells: {}", object.population(), object.to_rows());
+        return Ok(());
+    }
+    let object = Pattern::from_rows(code);
+    let code = apgcode::encode(&object, &options.rule).ok_or_else(|| {
+        format!(
+            "{} doesn't repeat, only still lifes, oscillators and spaceships have apgcode",
+            object.to_rows()
+        )
+    })?;
+    println!("{}", code);
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use crate::pattern::Pattern;
+use crate::pattern_file::PatternFile;
+use crate::rule::Rule;
+
+const HEADER_105: &str = "#Life 1.05";
+const HEADER_106: &str = "#Life 1.06";
+
+/// Life 1.05 with `#D` comments, `#N` or `#R` rule and single `#P` block of `.` and `*` rows.
+pub fn to_105_text(file: &PatternFile) -> String {
+    let mut text = format!("{}\n", HEADER_105);
+    if let Some(name) = &file.name {
+        text.push_str(&format!("#D Name: {}\n", name));
+    }
+    if let Some(author) = &file.author {
+        text.push_str(&format!("#D Author: {}\n", author));
+    }
+    for comment in &file.comments {
+        text.push_str(&format!("#D {}\n", comment));
+    }
+    match file.rule {
+        Some(rule) if rule != Rule::conway() => {
+            text.push_str(&format!("#R {}\n", survival_birth(&rule)))
+        }
+        _ => text.push_str("#N\n"),
+    }
+    let (pattern, _) = file.pattern.normalized();
+    if let Some(bounds) = pattern.bounds() {
+        text.push_str("#P 0 0\n");
+        for y in 0..=bounds.max_y {
+            let row: String = (0..=bounds.max_x)
+                .map(|x| if pattern.contains(x, y) { '*' } else { '.' })
+                .collect();
+            text.push_str(row.trim_end_matches('.'));
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// Rows of each `#P x y` block start at its corner, rows before first block at 0,0.
+pub fn parse_105(text: &str) -> Result<PatternFile, String> {
+    let mut file = PatternFile::default();
+    let mut cells = Vec::new();
+    let (mut left, mut y) = (0, 0);
+    for (number, line) in text.lines().enumerate() {
+        let number = number + 1;
+        let line = line.trim_end();
+        let at = |column: usize, message: String| {
+            format!("Line {}, column {}: {}", number, column, message)
+        };
+        if let Some(directive) = line.strip_prefix('#') {
+            let value = directive.get(1..).unwrap_or("").trim();
+            match directive.chars().next() {
+                Some('D') | Some('C') => {
+                    if let Some(name) = value.strip_prefix("Name:") {
+                        file.name = Some(name.trim().to_string());
+                    } else if let Some(author) = value.strip_prefix("Author:") {
+                        file.author = Some(author.trim().to_string());
+                    } else {
+                        file.comments.push(value.to_string());
+                    }
+                }
+                Some('N') => file.rule = Some(Rule::conway()),
+                Some('R') => file.rule = Some(value.parse().map_err(|e| at(4, e))?),
+                Some('P') => {
+                    let corner: Vec<i32> = value
+                        .split_whitespace()
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| at(4, format!("invalid block corner {:?}", value)))?;
+                    match corner[..] {
+                        [x, top] => {
+                            left = x;
+                            y = top;
+                        }
+                        _ => return Err(at(4, format!("expected \"#P x y\", found {:?}", line))),
+                    }
+                }
+                // Header and unknown directives
+                _ => {}
+            }
+            continue;
+        }
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                '.' => {}
+                '*' | 'O' => cells.push((left + x as i32, y)),
+                _ => return Err(at(x + 1, format!("unexpected character {:?}", c))),
+            }
+        }
+        y += 1;
+    }
+    file.pattern = Pattern::new(cells);
+    Ok(file)
+}
+
+/// Life 1.06 coordinates of alive cells, one per line.
+pub fn to_106_text(file: &PatternFile) -> String {
+    let mut text = format!("{}\n", HEADER_106);
+    for (x, y) in file.pattern.cells() {
+        text.push_str(&format!("{} {}\n", x, y));
+    }
+    text
+}
+
+pub fn parse_106(text: &str) -> Result<PatternFile, String> {
+    let mut cells = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let coordinates: Result<Vec<i32>, _> = line.split_whitespace().map(str::parse).collect();
+        match coordinates.as_deref() {
+            Ok([x, y]) => cells.push((*x, *y)),
+            _ => {
+                return Err(format!(
+                    "Line {}, column 1: expected \"x y\", found {:?}",
+                    number + 1,
+                    line
+                ))
+            }
+        }
+    }
+    Ok(PatternFile::new(Pattern::new(cells), None))
+}
+
+/// Older survival first notation like `23/3` used by `#R`.
+pub(crate) fn survival_birth(rule: &Rule) -> String {
+    let digits = |mask: u16| -> String {
+        (0..=8)
+            .filter(|n| mask & (1 << n) != 0)
+            .map(|n| n.to_string())
+            .collect()
+    };
+    format!("{}/{}", digits(rule.survival()), digits(rule.birth()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::life::*;
+
+    #[test]
+    fn it_reads_life_105_blocks() {
+        let text = "#Life 1.05\n#D Two blinkers\n#R 23/36\n#P -1 0\n***\n#P 5 5\n*\n*\n*\n";
+        let file = parse_105(text).unwrap();
+        assert_eq!(file.rule, Some("B36/S23".parse().unwrap()));
+        assert_eq!(file.comments, vec!["Two blinkers".to_string()]);
+        assert_eq!(
+            file.pattern.cells(),
+            &[(-1, 0), (0, 0), (1, 0), (5, 5), (5, 6), (5, 7)]
+        );
+        assert!(to_105_text(&file).contains("#R 23/36\n#P 0 0\n***\n"));
+        assert_eq!(
+            parse_105("#P 0 0\n.*x"),
+            Err("Line 2, column 3: unexpected character 'x'".to_string())
+        );
+    }
+
+    #[test]
+    fn it_reads_life_106_coordinates() {
+        let file = parse_106("#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n").unwrap();
+        assert_eq!(
+            file.pattern.normalized().0,
+            Pattern::from_rows(".O./..O/OOO")
+        );
+        assert_eq!(parse_106(&to_106_text(&file)), Ok(file));
+        assert_eq!(
+            parse_106("#Life 1.06\n0 x"),
+            Err("Line 2, column 1: expected \"x y\", found \"0 x\"".to_string())
+        );
+    }
+}
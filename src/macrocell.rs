@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+
+use crate::pattern::Pattern;
+use crate::pattern_file::PatternFile;
+use crate::rule::Rule;
+
+pub const HEADER: &str = "[M2]";
+/// Level of 8x8 leaves of two state files.
+const LEAF_LEVEL: u32 = 3;
+/// More cells than this don't fit in memory as a sparse pattern.
+pub const MAX_MACROCELL_CELLS: u64 = 50_000_000;
+
+/// Node of the quadtree, children are 1-based indices of earlier lines, 0 means empty.
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    /// Alive cells of 8x8 square.
+    Leaf(Vec<(i32, i32)>),
+    /// Level 1 node of multi-state file, children are cell states.
+    States([u32; 4]),
+    Branch(u32, [usize; 4]),
+}
+
+/// Golly macrocell: 8x8 leaves written as rows of `.` and `*` ended by `$`, then
+/// `level nw ne sw se` lines for bigger squares, identical subtrees are written once.
+pub fn to_text(file: &PatternFile) -> String {
+    let mut text = format!("{} (game_of_life)\n", HEADER);
+    if let Some(rule) = file.rule {
+        text.push_str(&format!("#R {}\n", rule));
+    }
+    if let Some(name) = &file.name {
+        text.push_str(&format!("#N {}\n", name));
+    }
+    if let Some(author) = &file.author {
+        text.push_str(&format!("#O {}\n", author));
+    }
+    for comment in &file.comments {
+        text.push_str(&format!("#C {}\n", comment));
+    }
+    let (pattern, _) = file.pattern.normalized();
+    let side = pattern
+        .bounds()
+        .map_or(1, |b| (b.max_x.max(b.max_y) + 1) as u64);
+    let mut level = LEAF_LEVEL;
+    while (1u64 << level) < side {
+        level += 1;
+    }
+    let mut writer = Writer::default();
+    let root = writer.write(pattern.cells().to_vec(), 0, 0, level);
+    if root == 0 {
+        // Empty pattern still needs a root
+        writer.lines.push("$".to_string());
+    }
+    for line in writer.lines {
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
+
+#[derive(Default)]
+struct Writer {
+    lines: Vec<String>,
+    indices: HashMap<String, usize>,
+}
+
+impl Writer {
+    /// Writes square of `level` with top left corner at `x`, `y` and returns its line number.
+    fn write(&mut self, cells: Vec<(i32, i32)>, x: i32, y: i32, level: u32) -> usize {
+        if cells.is_empty() {
+            return 0;
+        }
+        let line = if level == LEAF_LEVEL {
+            let mut line = String::new();
+            let last_row = cells.iter().map(|(_, cy)| cy - y).max().unwrap_or(0);
+            for row in 0..=last_row {
+                let columns: Vec<i32> = cells
+                    .iter()
+                    .filter(|(_, cy)| cy - y == row)
+                    .map(|(cx, _)| cx - x)
+                    .collect();
+                let last = columns.iter().max().map_or(-1, |last| *last);
+                for column in 0..=last {
+                    line.push(if columns.contains(&column) { '*' } else { '.' });
+                }
+                line.push('$');
+            }
+            line
+        } else {
+            let half = 1 << (level - 1);
+            let mut quadrants: Vec<Vec<(i32, i32)>> = vec![Vec::new(); 4];
+            for (cx, cy) in cells {
+                let index = (cx >= x + half) as usize + 2 * (cy >= y + half) as usize;
+                quadrants[index].push((cx, cy));
+            }
+            let mut children = [0usize; 4];
+            for (index, quadrant) in quadrants.into_iter().enumerate() {
+                let (qx, qy) = (x + half * (index as i32 % 2), y + half * (index as i32 / 2));
+                children[index] = self.write(quadrant, qx, qy, level - 1);
+            }
+            format!(
+                "{} {} {} {} {}",
+                level, children[0], children[1], children[2], children[3]
+            )
+        };
+        if let Some(index) = self.indices.get(&line) {
+            return *index;
+        }
+        self.lines.push(line.clone());
+        self.indices.insert(line, self.lines.len());
+        self.lines.len()
+    }
+}
+
+/// Reads two state files with 8x8 leaves as well as multi-state ones built from level 1
+/// nodes, every state other than 0 is alive.
+pub fn parse(text: &str) -> Result<PatternFile, String> {
+    let mut file = PatternFile::default();
+    let mut nodes: Vec<Node> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let number = number + 1;
+        let at = |column: usize, message: String| {
+            format!("Line {}, column {}: {}", number, column, message)
+        };
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('[') {
+            continue;
+        }
+        if let Some(directive) = line.strip_prefix('#') {
+            let value = directive.get(1..).unwrap_or("").trim();
+            match directive.chars().next() {
+                Some('R') => {
+                    let notation = value.split(':').next().unwrap_or(value);
+                    file.rule = Some(notation.parse::<Rule>().map_err(|e| at(4, e))?);
+                }
+                Some('N') => file.name = Some(value.to_string()),
+                Some('O') => file.author = Some(value.to_string()),
+                Some('C') => file.comments.push(value.to_string()),
+                // Generation count and anything else
+                _ => {}
+            }
+            continue;
+        }
+        let node = if line.starts_with(|c: char| c.is_ascii_digit()) {
+            parse_node(line, &nodes).map_err(|(column, e)| at(column, e))?
+        } else {
+            parse_leaf(line).map_err(|(column, e)| at(column, e))?
+        };
+        nodes.push(node);
+    }
+    let root = match nodes.len() {
+        0 => {
+            return Err("No nodes, expected 8x8 leaves or \"level nw ne sw se\" lines".to_string())
+        }
+        count => count,
+    };
+    let mut populations = vec![0; nodes.len() + 1];
+    for index in 1..=nodes.len() {
+        populations[index] = match &nodes[index - 1] {
+            Node::Leaf(cells) => cells.len() as u64,
+            Node::States(states) => states.iter().filter(|state| **state > 0).count() as u64,
+            Node::Branch(_, children) => children
+                .iter()
+                .fold(0u64, |sum, child| sum.saturating_add(populations[*child])),
+        };
+    }
+    if populations[root] > MAX_MACROCELL_CELLS {
+        return Err(format!(
+            "Pattern has {} cells, at most {} can be loaded",
+            populations[root], MAX_MACROCELL_CELLS
+        ));
+    }
+    let mut cells = Vec::new();
+    collect(&nodes, root, 0, 0, &mut cells);
+    let min_x = cells.iter().map(|(x, _)| *x).min().unwrap_or(0);
+    let min_y = cells.iter().map(|(_, y)| *y).min().unwrap_or(0);
+    let max = cells
+        .iter()
+        .map(|(x, y)| (x - min_x).max(y - min_y))
+        .max()
+        .unwrap_or(0);
+    if max > i32::MAX as i64 {
+        return Err("Pattern is too large to fit on a plane of 2^31 cells".to_string());
+    }
+    file.pattern = Pattern::new(
+        cells
+            .into_iter()
+            .map(|(x, y)| ((x - min_x) as i32, (y - min_y) as i32))
+            .collect(),
+    );
+    Ok(file)
+}
+
+fn node_level(node: &Node) -> u32 {
+    match node {
+        Node::Leaf(_) => LEAF_LEVEL,
+        Node::States(_) => 1,
+        Node::Branch(level, _) => *level,
+    }
+}
+
+/// Rows of `.` and `*` each ended by `$`, at most 8 by 8.
+fn parse_leaf(line: &str) -> Result<Node, (usize, String)> {
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    for (column, c) in line.chars().enumerate() {
+        match c {
+            '.' | '*' if x >= 8 || y >= 8 => {
+                return Err((column + 1, "leaf is larger than 8x8".to_string()))
+            }
+            '.' => x += 1,
+            '*' => {
+                cells.push((x, y));
+                x += 1;
+            }
+            '$' => {
+                x = 0;
+                y += 1;
+            }
+            _ => return Err((column + 1, format!("unexpected character {:?}", c))),
+        }
+    }
+    Ok(Node::Leaf(cells))
+}
+
+/// `level nw ne sw se`, children refer to earlier lines one level lower.
+fn parse_node(line: &str, nodes: &[Node]) -> Result<Node, (usize, String)> {
+    let mut values = Vec::new();
+    let mut column = 1;
+    for item in line.split(' ') {
+        if !item.is_empty() {
+            let value: usize = item
+                .parse()
+                .map_err(|_| (column, format!("invalid number {:?}", item)))?;
+            values.push((column, value));
+        }
+        column += item.chars().count() + 1;
+    }
+    if values.len() != 5 {
+        return Err((
+            1,
+            format!("expected \"level nw ne sw se\", found {:?}", line),
+        ));
+    }
+    let level = values[0].1 as u32;
+    if !(1..=63).contains(&level) {
+        return Err((1, format!("level {} is not between 1 and 63", level)));
+    }
+    if level == 1 {
+        let mut states = [0; 4];
+        for (state, (_, value)) in states.iter_mut().zip(&values[1..]) {
+            *state = *value as u32;
+        }
+        return Ok(Node::States(states));
+    }
+    let mut children = [0; 4];
+    for (child, (column, value)) in children.iter_mut().zip(&values[1..]) {
+        if *value > nodes.len() {
+            return Err((*column, format!("node {} isn't defined yet", value)));
+        }
+        if *value > 0 && node_level(&nodes[*value - 1]) != level - 1 {
+            return Err((
+                *column,
+                format!("node {} isn't of level {}", value, level - 1),
+            ));
+        }
+        *child = *value;
+    }
+    Ok(Node::Branch(level, children))
+}
+
+/// Adds alive cells of node `index` with top left corner at `x`, `y`.
+fn collect(nodes: &[Node], index: usize, x: i64, y: i64, cells: &mut Vec<(i64, i64)>) {
+    if index == 0 {
+        return;
+    }
+    match &nodes[index - 1] {
+        Node::Leaf(leaf) => {
+            cells.extend(leaf.iter().map(|(cx, cy)| (x + *cx as i64, y + *cy as i64)));
+        }
+        Node::States(states) => {
+            for (quadrant, state) in states.iter().enumerate() {
+                if *state > 0 {
+                    cells.push((x + quadrant as i64 % 2, y + quadrant as i64 / 2));
+                }
+            }
+        }
+        Node::Branch(level, children) => {
+            let half = 1i64 << (level - 1).min(62);
+            for (quadrant, child) in children.iter().enumerate() {
+                let (qx, qy) = (quadrant as i64 % 2, quadrant as i64 / 2);
+                collect(nodes, *child, x + qx * half, y + qy * half, cells);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::macrocell::*;
+
+    #[test]
+    fn it_shares_identical_subtrees() {
+        // The same glider in each quadrant of 64x64 square
+        let glider = Pattern::from_rows(".O./..O/OOO");
+        let mut cells = Vec::new();
+        for (dx, dy) in [(0, 0), (32, 0), (0, 32), (32, 32)] {
+            cells.extend(glider.translated(dx, dy).cells().iter().cloned());
+        }
+        let file = PatternFile::new(Pattern::new(cells), Some(Rule::conway()));
+        let text = to_text(&file);
+        // One leaf, and each of levels 4 and 5 has one node with the glider, plus root
+        assert_eq!(
+            text,
+            "[M2] (game_of_life)\n#R B3/S23\n.*$..*$***$\n4 1 0 0 0\n5 2 0 0 0\n6 3 3 3 3\n"
+        );
+        assert_eq!(parse(&text).unwrap(), file);
+    }
+
+    #[test]
+    fn it_reads_multi_state_nodes() {
+        let text = "[M2]\n1 0 2 1 0\n2 1 0 0 1\n";
+        let file = parse(text).unwrap();
+        assert_eq!(file.pattern, Pattern::from_rows(".O/O./...O/..O."));
+        assert_eq!(
+            parse("[M2]\n.*$\n5 1 0 0 0"),
+            Err("Line 3, column 3: node 1 isn't of level 4".to_string())
+        );
+        assert_eq!(
+            parse("[M2]\n.*x$"),
+            Err("Line 2, column 3: unexpected character 'x'".to_string())
+        );
+    }
+
+    #[test]
+    fn it_rejects_pattern_wider_than_plane() {
+        // Two cells 2^40 apart
+        let mut text = "[M2]\n*$\n".to_string();
+        for level in 4..=40 {
+            text.push_str(&format!("{} {} 0 0 0\n", level, level - 3));
+        }
+        text.push_str("41 38 0 0 38\n");
+        assert_eq!(
+            parse(&text),
+            Err("Pattern is too large to fit on a plane of 2^31 cells".to_string())
+        );
+    }
+}
@@ -1,16 +1,125 @@
 extern crate rand;
 extern crate sdl2;
 
+mod apgcode;
 mod app;
 mod app_state;
+mod bitmap;
 mod button;
 mod cell_state;
+mod census;
+mod cli;
+mod collision;
+mod enumerate;
+mod explore;
+mod gallery;
+mod genetic;
+mod gif;
+mod graph;
+mod headless;
+mod life;
 mod managers;
+mod macrocell;
 mod map;
+mod mcell;
+mod methuselah;
+mod parallel;
+mod pattern;
+mod pattern_file;
+mod plaintext;
+mod predecessor;
+mod prompt;
+mod recorder;
 mod renderer;
+mod rle;
+mod rule;
+mod sat;
+mod screenshot;
+mod search;
+mod session;
+mod simulation;
+mod soup;
+mod spaceship;
+mod speed;
+mod stability;
+mod statistics;
+mod svg;
+mod timing;
 mod ui;
 
+use std::env;
+use std::process;
+
+use crate::cli::{Options, USAGE};
+
 fn main() {
-    let mut app = app::App::new();
-    app.run();
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(1);
+        }
+    };
+    if options.help {
+        println!("{}", USAGE);
+        return;
+    }
+    if let Some(code) = &options.apgcode {
+        exit_on_error(headless::apgcode(&options, code));
+        return;
+    }
+    if let Some(soups) = options.search {
+        exit_on_error(headless::search(&options, soups));
+        return;
+    }
+    if let Some(size) = options.enumerate {
+        exit_on_error(headless::enumerate(&options, size));
+        return;
+    }
+    if let Some(target) = &options.parent {
+        exit_on_error(headless::find_parent(&options, target));
+        return;
+    }
+    if let Some(pattern) = &options.lifespan {
+        exit_on_error(headless::lifespan(&options, pattern));
+        return;
+    }
+    if let Some(directory) = &options.lifespan_dir {
+        exit_on_error(headless::lifespan_dir(&options, directory));
+        return;
+    }
+    if let Some((first, second)) = &options.collide {
+        exit_on_error(headless::collide(&options, first, second));
+        return;
+    }
+    if let Some(generations) = options.evolve {
+        exit_on_error(headless::evolve(&options, generations));
+        return;
+    }
+    if let Some(count) = options.explore {
+        exit_on_error(headless::explore(&options, count));
+        return;
+    }
+    if let Some(speed) = options.ship {
+        exit_on_error(headless::ship_search(&options, speed));
+        return;
+    }
+    if let Some(path) = &options.record {
+        exit_on_error(headless::record(&options, path));
+        return;
+    }
+    if options.headless {
+        exit_on_error(headless::run(&options));
+        return;
+    }
+    let mut app = app::App::new(options.vsync);
+    app.run(&options);
+}
+
+/// Headless commands print their error and end the process with failure status.
+fn exit_on_error(result: Result<(), String>) {
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
 }
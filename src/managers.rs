@@ -5,11 +5,14 @@ use std::env;
 use std::hash::Hash;
 use std::rc::Rc;
 
-use sdl2::image::LoadTexture;
+use sdl2::image::{LoadSurface, LoadTexture};
 use sdl2::render::Texture;
 use sdl2::render::TextureCreator;
+use sdl2::surface::Surface;
 use sdl2::ttf::{Font, Sdl2TtfContext};
 
+use crate::bitmap::Bitmap;
+
 pub trait ResourceLoader<'l, R> {
     type Args: ?Sized;
 
@@ -43,6 +46,9 @@ impl<'a> From<&'a FontDetails> for FontDetails {
 pub type TextureManager<'l, T> = ResourceManager<'l, String, Texture<'l>, TextureCreator<T>>;
 pub type FontManager<'l> = ResourceManager<'l, FontDetails, Font<'l, 'static>, Sdl2TtfContext>;
 
+/// Reads images SDL_image supports, PNG and BMP among them, into pixels of `Bitmap`.
+pub struct BitmapLoader;
+
 #[derive(Clone)]
 pub struct ResourceManager<'l, K, R, L>
 where
@@ -99,3 +105,14 @@ impl<'l> ResourceLoader<'l, Font<'l, 'static>> for Sdl2TtfContext {
         self.load_font(&data.path, data.size)
     }
 }
+
+impl<'l> ResourceLoader<'l, Bitmap> for BitmapLoader {
+    type Args = str;
+
+    fn load(&'l self, path: &str) -> Result<Bitmap, String> {
+        println!("Loading image {}...", path);
+        Surface::from_file(path)
+            .and_then(|surface| Bitmap::from_surface(&surface))
+            .map_err(|e| format!("Can't read {}: {}", path, e))
+    }
+}
@@ -1,25 +1,98 @@
-use super::cell_state::CellState;
+use super::cell_state::{CellState, GRID_COLOR};
 use crate::app::WindowCanvas;
 use crate::app::CELL_SIZE;
 use crate::app::LEFT_MARGIN;
 use crate::app::TOP_MARGIN;
 use crate::renderer::Render;
 use crate::renderer::Renderer;
-use sdl2::pixels::Color;
+use crate::rule::Rule;
 use sdl2::rect::Rect;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Topology {
+    /// Cells outside of the map are always dead.
+    Bounded,
+    /// Opposite edges are glued together.
+    Torus,
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Topology::Bounded => write!(f, "bounded"),
+            Topology::Torus => write!(f, "torus"),
+        }
+    }
+}
+
+impl FromStr for Topology {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bounded" => Ok(Topology::Bounded),
+            "torus" => Ok(Topology::Torus),
+            _ => Err(format!("Unknown topology {:?}, expected bounded or torus", s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Map {
     cells: [CellState; 400],
+    topology: Topology,
 }
 
 impl Map {
     pub fn new() -> Self {
         Self {
             cells: [CellState::Dead; 400],
+            topology: Topology::Bounded,
         }
     }
 
+    pub fn width(&self) -> i32 {
+        20
+    }
+
+    pub fn height(&self) -> i32 {
+        20
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells
+            .iter()
+            .filter(|cell| **cell == CellState::Alive)
+            .count()
+    }
+
+    /// Coordinates of alive cells, row by row.
+    pub fn alive_cells(&self) -> Vec<(i32, i32)> {
+        let mut cells = Vec::new();
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                if self.get_at(x, y) == CellState::Alive {
+                    cells.push((x, y));
+                }
+            }
+        }
+        cells
+    }
+
+    pub fn clear(&mut self) {
+        self.cells = [CellState::Dead; 400];
+    }
+
     pub fn get_at(&self, x: i32, y: i32) -> CellState {
         self.cells[((y * 20) + x) as usize]
     }
@@ -37,102 +110,53 @@ impl Map {
     }
 
     pub fn get_neighbours(&self, x: i32, y: i32) -> [CellState; 9] {
+        // [ 0 1 2 ]
+        // [ 3 4 5 ]
+        // [ 6 7 8 ]
         let mut d9 = [CellState::Dead; 9];
-        let x = if x < 0 {
-            0
-        } else if x > 19 {
-            19
-        } else {
-            x
-        };
-        let y = if y < 0 {
-            0
-        } else if y > 19 {
-            19
-        } else {
-            y
-        };
-        match (x, y) {
-            (0, 0) => {
-                // [ 0 1 2 ]
-                // [ 3 4 5 ]
-                d9[4] = self.get_at(x, y);
-                d9[5] = self.get_at(x + 1, y);
-                // [ 6 7 8 ]
-                d9[7] = self.get_at(x, y + 1);
-                d9[8] = self.get_at(x + 1, y + 1);
-            }
-            (19, 19) => {
-                // [ 0 1 2 ]
-                d9[0] = self.get_at(x - 1, y - 1);
-                d9[1] = self.get_at(x, y - 1);
-                // [ 3 4 5 ]
-                d9[3] = self.get_at(x - 1, y);
-                d9[4] = self.get_at(x, y);
-                // [ 6 7 8 ]
-            }
-            (_, 0) => {
-                // [ 0 1 2 ]
-                // [ 3 4 5 ]
-                d9[3] = self.get_at(x - 1, y);
-                d9[4] = self.get_at(x, y);
-                d9[5] = self.get_at(x + 1, y);
-                // [ 6 7 8 ]
-                d9[6] = self.get_at(x - 1, y + 1);
-                d9[7] = self.get_at(x, y + 1);
-                d9[8] = self.get_at(x + 1, y + 1);
-            }
-            (_, 19) => {
-                // [ 0 1 2 ]
-                d9[0] = self.get_at(x - 1, y - 1);
-                d9[1] = self.get_at(x, y - 1);
-                d9[2] = self.get_at(x + 1, y - 1);
-                // [ 3 4 5 ]
-                d9[3] = self.get_at(x - 1, y);
-                d9[4] = self.get_at(x, y);
-                d9[5] = self.get_at(x + 1, y);
-                // [ 6 7 8 ]
-            }
-            (0, _) => {
-                // [ 0 1 2 ]
-                d9[1] = self.get_at(x, y - 1);
-                d9[2] = self.get_at(x + 1, y - 1);
-                // [ 3 4 5 ]
-                d9[4] = self.get_at(x, y);
-                d9[5] = self.get_at(x + 1, y);
-                // [ 6 7 8 ]
-                d9[7] = self.get_at(x, y + 1);
-                d9[8] = self.get_at(x + 1, y + 1);
-            }
-            (19, _) => {
-                // [ 0 1 2 ]
-                d9[0] = self.get_at(x - 1, y - 1);
-                d9[1] = self.get_at(x, y - 1);
-                // [ 3 4 5 ]
-                d9[3] = self.get_at(x - 1, y);
-                d9[4] = self.get_at(x, y);
-                // [ 6 7 8 ]
-                d9[6] = self.get_at(x - 1, y + 1);
-                d9[7] = self.get_at(x, y + 1);
-            }
-            _ => {
-                // [ 0 1 2 ]
-                d9[0] = self.get_at(x - 1, y - 1);
-                d9[1] = self.get_at(x, y - 1);
-                d9[2] = self.get_at(x + 1, y - 1);
-                // [ 3 4 5 ]
-                d9[3] = self.get_at(x - 1, y);
-                d9[4] = self.get_at(x, y);
-                d9[5] = self.get_at(x + 1, y);
-                // [ 6 7 8 ]
-                d9[6] = self.get_at(x - 1, y + 1);
-                d9[7] = self.get_at(x, y + 1);
-                d9[8] = self.get_at(x + 1, y + 1);
+        let x = x.clamp(0, 19);
+        let y = y.clamp(0, 19);
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (nx, ny) = match self.topology {
+                    Topology::Bounded => (x + dx, y + dy),
+                    Topology::Torus => ((x + dx).rem_euclid(20), (y + dy).rem_euclid(20)),
+                };
+                if (0..20).contains(&nx) && (0..20).contains(&ny) {
+                    d9[((dy + 1) * 3 + dx + 1) as usize] = self.get_at(nx, ny);
+                }
             }
         }
         d9
     }
 
+    /// Computes the following generation, every cell looks at the current one only.
+    pub fn next_generation(&self, rule: &Rule) -> Self {
+        let mut next = self.clone();
+        for y in 0..20 {
+            for x in 0..20 {
+                let alive = self
+                    .get_neighbours(x, y)
+                    .iter()
+                    .filter(|cell| **cell == CellState::Alive)
+                    .count();
+                match self.get_at(x, y) {
+                    CellState::Alive => {
+                        if !rule.survives(alive - 1) {
+                            next.set_dead(x, y);
+                        }
+                    }
+                    CellState::Dead => {
+                        if rule.is_born(alive) {
+                            next.set_alive(x, y);
+                        }
+                    }
+                }
+            }
+        }
+        next
+    }
+
     fn get_draw_rect(x: i32, y: i32) -> Rect {
         Rect::new(
             LEFT_MARGIN as i32 + (x * CELL_SIZE as i32),
@@ -149,9 +173,7 @@ impl<'a> Render for Map {
             for x in 0..20 {
                 let rect = Self::get_draw_rect(x, y);
                 self.get_at(x, y).draw(&rect, canvas);
-
-                let frame_color = Color::RGBA(200, 200, 200, 255);
-                renderer.render_frame(canvas, &rect, &frame_color);
+                renderer.render_frame(canvas, &rect, &GRID_COLOR);
             }
         }
     }
@@ -161,6 +183,7 @@ impl<'a> Render for Map {
 mod tests {
     use crate::cell_state::*;
     use crate::map::*;
+    use crate::rule::Rule;
     use sdl2::rect::Rect;
 
     #[test]
@@ -212,6 +235,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_does_not_wrap_rows() {
+        let mut map = Map::new();
+        map.set_alive(0, 1);
+        assert_eq!(
+            map.get_neighbours(19, 0).to_vec(),
+            [CellState::Dead; 9].to_vec()
+        );
+    }
+
+    #[test]
+    fn it_wraps_torus_edges() {
+        let mut map = Map::new();
+        map.set_topology(Topology::Torus);
+        map.set_alive(0, 1);
+        map.set_alive(19, 19);
+        let neighbours = map.get_neighbours(19, 0);
+        assert_eq!(neighbours[5], CellState::Dead);
+        assert_eq!(neighbours[8], CellState::Alive);
+        assert_eq!(neighbours[1], CellState::Alive);
+    }
+
+    #[test]
+    fn it_oscillates_blinker() {
+        let mut map = Map::new();
+        map.set_alive(4, 5);
+        map.set_alive(5, 5);
+        map.set_alive(6, 5);
+        let next = map.next_generation(&Rule::conway());
+        assert_eq!(next.get_at(5, 4), CellState::Alive);
+        assert_eq!(next.get_at(5, 5), CellState::Alive);
+        assert_eq!(next.get_at(5, 6), CellState::Alive);
+        assert_eq!(next.get_at(4, 5), CellState::Dead);
+        assert_eq!(next.get_at(6, 5), CellState::Dead);
+        let next = next.next_generation(&Rule::conway());
+        assert_eq!(next.get_at(4, 5), CellState::Alive);
+        assert_eq!(next.get_at(6, 5), CellState::Alive);
+        assert_eq!(next.get_at(5, 4), CellState::Dead);
+    }
+
     #[test]
     fn it_give_valid_draw_rect() {
         assert_eq!(Map::get_draw_rect(0, 0), Rect::new(200, 100, 20, 20));
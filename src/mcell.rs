@@ -0,0 +1,302 @@
+use crate::life;
+use crate::map::Topology;
+use crate::pattern::Pattern;
+use crate::pattern_file::PatternFile;
+use crate::rule::Rule;
+
+pub const HEADER: &str = "#MCell";
+/// Longest `#L` line written.
+const LINE_LENGTH: usize = 70;
+/// Cell states written as `A` to `X`, higher ones get `a` to `j` prefix.
+const LETTERS: u32 = 24;
+/// Directives which only change how MCell shows the board, anything else not read is
+/// reported as the pattern would differ from the file.
+const COSMETIC: [&str; 6] = ["MCell", "BOARD", "SPEED", "PALETTE", "CCOLORS", "COLORING"];
+
+/// MCell rule families, only the ones expressible as two state B/S rule can be loaded.
+#[derive(Debug, Clone, PartialEq)]
+enum Game {
+    Life,
+    /// Cell is alive next generation when alive cells of its 3x3 neighbourhood, itself
+    /// included, are one of the rule digits.
+    VoteForLife,
+    /// `S/B/C` rule with `C` states, cells don't die at once but fade through the extra ones.
+    Generations,
+    Other(String),
+}
+
+impl Game {
+    fn from_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "life" => Game::Life,
+            "vote for life" => Game::VoteForLife,
+            "generations" => Game::Generations,
+            _ => Game::Other(name.to_string()),
+        }
+    }
+
+    fn rule(&self, notation: &str) -> Result<Rule, String> {
+        match self {
+            Game::Life => notation.parse(),
+            Game::VoteForLife => {
+                let mut totals = 0u16;
+                for c in notation.chars() {
+                    match c.to_digit(10) {
+                        Some(total) => totals |= 1 << total,
+                        None => return Err(format!("invalid vote total {:?}", c)),
+                    }
+                }
+                // Dead cell with n neighbours has n votes, alive one n + 1
+                Ok(Rule::new(totals, totals >> 1))
+            }
+            Game::Generations => {
+                let parts: Vec<&str> = notation.split('/').collect();
+                match parts[..] {
+                    [survival, birth, states] => match states.trim().parse::<u32>() {
+                        Ok(2) => format!("{}/{}", survival, birth).parse(),
+                        Ok(states) => Err(format!(
+                            "Generations rule {:?} has {} states, only 2 state rules like 23/3/2 are supported",
+                            notation, states
+                        )),
+                        Err(_) => Err(format!("invalid state count {:?}", states)),
+                    },
+                    _ => Err(format!(
+                        "Generations rule {:?} must be \"survival/birth/states\"",
+                        notation
+                    )),
+                }
+            }
+            Game::Other(name) => Err(format!(
+                "{} rules are not supported, only Life, Vote for Life and 2 state Generations",
+                name
+            )),
+        }
+    }
+}
+
+/// Life game with `#RULE` in survival first notation, `#WRAP` for torus and `#L` rows of
+/// `.` and `A` cells.
+pub fn to_text(file: &PatternFile) -> String {
+    let mut text = format!("{} 4.20\n#GAME Life\n", HEADER);
+    if let Some(rule) = file.rule {
+        text.push_str(&format!("#RULE {}\n", life::survival_birth(&rule)));
+    }
+    if let Some(topology) = file.topology {
+        text.push_str(&format!("#WRAP {}\n", (topology == Topology::Torus) as u8));
+    }
+    if let Some(name) = &file.name {
+        text.push_str(&format!("#D Name: {}\n", name));
+    }
+    if let Some(author) = &file.author {
+        text.push_str(&format!("#D Author: {}\n", author));
+    }
+    for comment in &file.comments {
+        text.push_str(&format!("#D {}\n", comment));
+    }
+    let (pattern, _) = file.pattern.normalized();
+    let mut line = String::new();
+    let mut row_ends = 0;
+    if let Some(bounds) = pattern.bounds() {
+        for y in 0..=bounds.max_y {
+            let last = match (0..=bounds.max_x).rev().find(|x| pattern.contains(*x, y)) {
+                Some(last) => last,
+                None => {
+                    row_ends += 1;
+                    continue;
+                }
+            };
+            line.push_str(&run(row_ends, '$'));
+            row_ends = 1;
+            let mut x = 0;
+            while x <= last {
+                let alive = pattern.contains(x, y);
+                let length = (x..=last)
+                    .take_while(|x| pattern.contains(*x, y) == alive)
+                    .count();
+                line.push_str(&run(length as u32, if alive { 'A' } else { '.' }));
+                x += length as i32;
+                if line.len() >= LINE_LENGTH {
+                    text.push_str(&format!("#L {}\n", line));
+                    line.clear();
+                }
+            }
+        }
+    }
+    if !line.is_empty() {
+        text.push_str(&format!("#L {}\n", line));
+    }
+    text
+}
+
+fn run(count: u32, c: char) -> String {
+    match count {
+        0 => String::new(),
+        1 => c.to_string(),
+        _ => format!("{}{}", count, c),
+    }
+}
+
+/// Reads `#GAME`, `#RULE`, `#WRAP`, `#D` and `#L` lines and skips cosmetic ones. Rule
+/// families which can't be expressed as B/S rule, cells of higher states and other
+/// directives are reported rather than approximated.
+pub fn parse(text: &str) -> Result<PatternFile, String> {
+    let mut file = PatternFile::default();
+    let mut game = (Game::Life, 0);
+    let mut rule = None;
+    let mut cells = Vec::new();
+    // Cells with state above 1 and where the first one is
+    let mut multi_state = None;
+    let (mut x, mut y) = (0, 0);
+    let mut count: Option<u32> = None;
+    let mut prefix: Option<u32> = None;
+    for (number, line) in text.lines().enumerate() {
+        let number = number + 1;
+        let line = line.trim_end();
+        let at = |column: usize, message: String| {
+            format!("Line {}, column {}: {}", number, column, message)
+        };
+        let directive = match line.strip_prefix('#') {
+            Some(directive) => directive,
+            None if line.trim().is_empty() => continue,
+            None => return Err(at(1, "expected line starting with '#'".to_string())),
+        };
+        let (name, value) = directive.split_once(' ').unwrap_or((directive, ""));
+        let value_column = name.len() + 3;
+        let value = value.trim();
+        match name {
+            "GAME" => game = (Game::from_name(value), number),
+            "RULE" => rule = Some((value.to_string(), number, value_column)),
+            "WRAP" => {
+                file.topology = match value {
+                    "0" => Some(Topology::Bounded),
+                    "1" => Some(Topology::Torus),
+                    _ => return Err(at(value_column, format!("invalid wrap {:?}", value))),
+                }
+            }
+            "D" => {
+                if let Some(name) = value.strip_prefix("Name:") {
+                    file.name = Some(name.trim().to_string());
+                } else if let Some(author) = value.strip_prefix("Author:") {
+                    file.author = Some(author.trim().to_string());
+                } else {
+                    file.comments.push(value.to_string());
+                }
+            }
+            "L" => {
+                for (index, c) in directive.chars().enumerate().skip(2) {
+                    let column = index + 2;
+                    let repeat = count.unwrap_or(1);
+                    match c {
+                        '0'..='9' if prefix.is_none() => {
+                            let digit = c.to_digit(10).unwrap_or(0);
+                            count =
+                                Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                            continue;
+                        }
+                        'a'..='j' if prefix.is_none() => {
+                            prefix = Some(c as u32 - 'a' as u32 + 1);
+                            continue;
+                        }
+                        '.' if prefix.is_none() => x += repeat as i32,
+                        '$' if prefix.is_none() => {
+                            x = 0;
+                            y += repeat as i32;
+                        }
+                        'A'..='X' => {
+                            let state =
+                                prefix.take().unwrap_or(0) * LETTERS + c as u32 - 'A' as u32 + 1;
+                            if state > 1 && multi_state.is_none() {
+                                multi_state = Some((state, number, column));
+                            }
+                            for _ in 0..repeat {
+                                cells.push((x, y));
+                                x += 1;
+                            }
+                        }
+                        ' ' => {}
+                        _ => return Err(at(column, format!("unexpected character {:?}", c))),
+                    }
+                    count = None;
+                }
+            }
+            name if COSMETIC.contains(&name) => {}
+            _ => return Err(at(2, format!("directive #{} is not supported", name))),
+        }
+    }
+    let (game, game_line) = game;
+    if let Game::Other(_) = game {
+        return Err(format!(
+            "Line {}, column 7: {}",
+            game_line,
+            game.rule("").unwrap_err()
+        ));
+    }
+    if let Some((notation, number, column)) = rule {
+        let rule = game
+            .rule(&notation)
+            .map_err(|e| format!("Line {}, column {}: {}", number, column, e))?;
+        file.rule = Some(rule);
+    }
+    if let Some((state, number, column)) = multi_state {
+        let cell = match game {
+            Game::Generations => "fading cell",
+            _ => "cell",
+        };
+        return Err(format!(
+            "Line {}, column {}: state {} of {} isn't supported by 2 state rule",
+            number, column, state, cell
+        ));
+    }
+    file.pattern = Pattern::new(cells);
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mcell::*;
+
+    #[test]
+    fn it_reads_life_and_vote_games() {
+        let text = "#MCell 4.20\n#GAME Life\n#RULE 23/36\n#BOARD 80x60\n#WRAP 1\n#D Glider\n#L .A$2.A\n#L $3A\n";
+        let file = parse(text).unwrap();
+        assert_eq!(file.rule, Some("B36/S23".parse().unwrap()));
+        assert_eq!(file.topology, Some(Topology::Torus));
+        assert_eq!(file.comments, vec!["Glider".to_string()]);
+        assert_eq!(file.pattern, Pattern::from_rows(".O./..O/OOO"));
+        assert_eq!(parse(&to_text(&file)), Ok(file));
+        // Majority vote: 5 or more of 9 cells
+        let vote = parse("#MCell 4.20\n#GAME Vote for Life\n#RULE 56789\n#L A").unwrap();
+        assert_eq!(vote.rule, Some("B5678/S45678".parse().unwrap()));
+    }
+
+    #[test]
+    fn it_reports_unsupported_games() {
+        assert_eq!(
+            parse("#MCell 4.20\n#GAME Weighted Life\n#RULE NW5,NN1,ME0\n#L A"),
+            Err("Line 2, column 7: Weighted Life rules are not supported, only Life, Vote for Life and 2 state Generations".to_string())
+        );
+        assert_eq!(
+            parse("#MCell 4.20\n#GAME Generations\n#RULE 345/2/4\n#L A"),
+            Err("Line 3, column 7: Generations rule \"345/2/4\" has 4 states, only 2 state rules like 23/3/2 are supported".to_string())
+        );
+        assert_eq!(
+            parse("#MCell 4.20\n#GAME Generations\n#RULE 23/3/2\n#L A.B"),
+            Err(
+                "Line 4, column 6: state 2 of fading cell isn't supported by 2 state rule"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            parse("#MCell 4.20\n#GAME Life\n#L A.B"),
+            Err("Line 3, column 6: state 2 of cell isn't supported by 2 state rule".to_string())
+        );
+        assert_eq!(
+            parse("#MCell 4.20\n#BOARD 80x60\n#DIV 3\n#L A"),
+            Err("Line 3, column 2: directive #DIV is not supported".to_string())
+        );
+        assert_eq!(
+            parse("#MCell 4.20\n#L A.!"),
+            Err("Line 2, column 6: unexpected character '!'".to_string())
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::census::{classify, Census, Classification, DEFAULT_INTERACTION_DISTANCE, MAX_PERIOD};
+use crate::pattern::Pattern;
+use crate::pattern_file::PatternFile;
+use crate::rule::Rule;
+use crate::statistics::BoundingBox;
+
+pub const DEFAULT_MAX_GENERATIONS: u64 = 50_000;
+pub const DEFAULT_LIFESPAN_FILE: &str = "lifespan.csv";
+/// Generations between looking for spaceships leaving the rest behind.
+const ESCAPE_INTERVAL: u64 = 16;
+/// Spaceship further than this from everything else can't be caught by debris.
+const ESCAPE_DISTANCE: i32 = 12;
+const CSV_HEADER: &str = "name,cells,lifespan,max_population,max_population_generation,final_population,width,height,escaping_gliders,census";
+
+/// What became of pattern evolved on unbounded plane.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lifespan {
+    pub initial_population: usize,
+    /// Generation remaining objects started repeating at, `None` when they didn't within limit.
+    pub lifespan: Option<u64>,
+    pub max_population: usize,
+    pub max_population_generation: u64,
+    /// Population without escaped spaceships.
+    pub final_population: usize,
+    /// Box of remaining objects without escaped spaceships.
+    pub bounds: Option<BoundingBox>,
+    pub escaping_gliders: usize,
+    /// Remaining objects together with escaped spaceships.
+    pub census: Census,
+}
+
+impl Lifespan {
+    fn size(&self) -> (i32, i32) {
+        self.bounds
+            .map_or((0, 0), |b| (b.max_x - b.min_x + 1, b.max_y - b.min_y + 1))
+    }
+
+    pub fn census_summary(&self) -> String {
+        summary(&self.census)
+    }
+
+    pub fn to_csv_row(&self, name: &str) -> String {
+        let (width, height) = self.size();
+        format!(
+            "{},{},{},{},{},{},{},{},{},{}",
+            name.replace(',', " "),
+            self.initial_population,
+            self.lifespan.map_or(String::new(), |lifespan| lifespan.to_string()),
+            self.max_population,
+            self.max_population_generation,
+            self.final_population,
+            width,
+            height,
+            self.escaping_gliders,
+            self.census_summary()
+        )
+    }
+}
+
+impl fmt::Display for Lifespan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.lifespan {
+            Some(lifespan) => write!(f, "Lifespan {}", lifespan)?,
+            None => write!(f, "Still evolving")?,
+        }
+        let (width, height) = self.size();
+        write!(
+            f,
+            ", max population {} at {}, final population {} in {}x{}, {} gliders escaped",
+            self.max_population,
+            self.max_population_generation,
+            self.final_population,
+            width,
+            height,
+            self.escaping_gliders
+        )
+    }
+}
+
+/// Census in one line, like `3 block; 1 blinker`.
+pub fn summary(census: &Census) -> String {
+    census
+        .entries
+        .iter()
+        .map(|entry| format!("{} {}", entry.count, entry.label()))
+        .collect::<Vec<String>>()
+        .join("; ")
+}
+
+/// Evolves `pattern` until what is left repeats or `max_generations` pass.
+/// Spaceships flying away from the rest are taken out of the pattern once far enough.
+pub fn measure(pattern: &Pattern, rule: &Rule, max_generations: u64) -> Lifespan {
+    let mut current = pattern.clone();
+    let mut states = vec![current.clone()];
+    let mut seen: HashMap<Pattern, u64> = HashMap::new();
+    seen.insert(current.clone(), 0);
+    let mut escaped: Vec<Pattern> = Vec::new();
+    // Escaped spaceships still count to population, glider keeps its 5 cells in every phase
+    let mut escaped_cells = 0;
+    let mut max_population = (current.population(), 0);
+    let mut cycle = None;
+    for generation in 1..=max_generations {
+        current = current.step(rule);
+        let population = current.population() + escaped_cells;
+        if population > max_population.0 {
+            max_population = (population, generation);
+        }
+        if generation % ESCAPE_INTERVAL == 0 {
+            let (rest, ships) = remove_escaping(&current, rule);
+            if !ships.is_empty() {
+                escaped_cells += ships.iter().map(|ship| ship.population()).sum::<usize>();
+                escaped.extend(ships);
+                current = rest;
+                seen.clear();
+            }
+        }
+        states.push(current.clone());
+        if let Some(previous) = seen.insert(current.clone(), generation) {
+            cycle = Some((previous, generation - previous));
+            break;
+        }
+        if seen.len() as u64 > MAX_PERIOD {
+            let oldest = generation - MAX_PERIOD;
+            seen.retain(|_, seen_at| *seen_at > oldest);
+        }
+    }
+
+    let lifespan = cycle.map(|(start, period)| settled_since(&states, start, period));
+    let mut objects = current.components(DEFAULT_INTERACTION_DISTANCE);
+    let escaping_gliders = escaped
+        .iter()
+        .filter(|ship| {
+            classify(ship, rule).0
+                == Classification::Spaceship {
+                    period: 4,
+                    dx: 1,
+                    dy: 1,
+                }
+        })
+        .count();
+    objects.extend(escaped);
+    Lifespan {
+        initial_population: pattern.population(),
+        lifespan,
+        max_population: max_population.0,
+        max_population_generation: max_population.1,
+        final_population: current.population(),
+        bounds: current.bounds(),
+        escaping_gliders,
+        census: Census::of_objects(objects, rule, states.len() as u64 - 1),
+    }
+}
+
+/// Earliest generation from which area of the final cycle repeats with `period`,
+/// spaceships still leaving are outside of it and don't delay settling.
+fn settled_since(states: &[Pattern], start: u64, period: u64) -> u64 {
+    let cycle = &states[start as usize..];
+    let area = cycle.iter().filter_map(|state| state.bounds()).fold(
+        None,
+        |area: Option<BoundingBox>, b| {
+            Some(match area {
+                None => b,
+                Some(a) => BoundingBox {
+                    min_x: a.min_x.min(b.min_x),
+                    min_y: a.min_y.min(b.min_y),
+                    max_x: a.max_x.max(b.max_x),
+                    max_y: a.max_y.max(b.max_y),
+                },
+            })
+        },
+    );
+    let margin = DEFAULT_INTERACTION_DISTANCE;
+    let inside = |state: &Pattern| -> Vec<(i32, i32)> {
+        state
+            .cells()
+            .iter()
+            .filter(|(x, y)| match area {
+                Some(a) => {
+                    (a.min_x - margin..=a.max_x + margin).contains(x)
+                        && (a.min_y - margin..=a.max_y + margin).contains(y)
+                }
+                None => false,
+            })
+            .cloned()
+            .collect()
+    };
+    let mut since = start as usize;
+    while since > 0 && inside(&states[since - 1]) == inside(&states[since - 1 + period as usize]) {
+        since -= 1;
+    }
+    since as u64
+}
+
+/// Splits spaceships far from everything else and moving away from it off the pattern.
+fn remove_escaping(pattern: &Pattern, rule: &Rule) -> (Pattern, Vec<Pattern>) {
+    let objects = pattern.components(DEFAULT_INTERACTION_DISTANCE);
+    let mut ships = Vec::new();
+    let mut rest = Vec::new();
+    for (index, object) in objects.iter().enumerate() {
+        let others: Vec<(i32, i32)> = objects
+            .iter()
+            .enumerate()
+            .filter(|(other, _)| *other != index)
+            .flat_map(|(_, other)| other.cells().iter().cloned())
+            .collect();
+        let others = Pattern::new(others);
+        if is_escaping(object, &others, rule) {
+            ships.push(object.clone());
+        } else {
+            rest.extend(object.cells().iter().cloned());
+        }
+    }
+    (Pattern::new(rest), ships)
+}
+
+fn is_escaping(object: &Pattern, others: &Pattern, rule: &Rule) -> bool {
+    let period = match classify(object, rule).0 {
+        Classification::Spaceship { period, .. } => period,
+        _ => return false,
+    };
+    let others = match others.bounds() {
+        Some(bounds) => bounds,
+        None => return true,
+    };
+    let mut moved = object.clone();
+    for _ in 0..period {
+        moved = moved.step(rule);
+    }
+    let before = gap(object, &others);
+    before > ESCAPE_DISTANCE && gap(&moved, &others) > before
+}
+
+/// Distance between boxes along the axis they are further apart on.
+fn gap(object: &Pattern, others: &BoundingBox) -> i32 {
+    let b = object.bounds().unwrap();
+    let dx = (others.min_x - b.max_x).max(b.min_x - others.max_x);
+    let dy = (others.min_y - b.max_y).max(b.min_y - others.max_y);
+    dx.max(dy)
+}
+
+/// Measures every pattern file in `directory` under its own rule, or `rule` when it names
+/// none, and writes one CSV row per file.
+pub fn measure_directory<P: AsRef<Path>>(
+    directory: P,
+    rule: &Rule,
+    max_generations: u64,
+    output: &str,
+) -> Result<Vec<(String, Lifespan)>, String> {
+    let directory = directory.as_ref();
+    let mut paths: Vec<_> = fs::read_dir(directory)
+        .map_err(|e| format!("Can't read {}: {}", directory.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+    let mut results = Vec::new();
+    let mut csv = format!("{}\n", CSV_HEADER);
+    for path in paths {
+        let (pattern, file_rule) = match PatternFile::load(&path) {
+            Ok(file) => (file.pattern, file.rule.unwrap_or(*rule)),
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        };
+        let name = path
+            .file_name()
+            .map_or(String::new(), |name| name.to_string_lossy().to_string());
+        let lifespan = measure(&pattern, &file_rule, max_generations);
+        csv.push_str(&lifespan.to_csv_row(&name));
+        csv.push('\n');
+        results.push((name, lifespan));
+    }
+    fs::write(output, csv).map_err(|e| format!("Can't write {}: {}", output, e))?;
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::methuselah::*;
+
+    #[test]
+    fn it_measures_r_pentomino() {
+        let lifespan = measure(&Pattern::from_rows(".OO/OO./.O."), &Rule::conway(), 2000);
+        assert_eq!(lifespan.lifespan, Some(1103));
+        // 116 cells with the gliders
+        assert_eq!(lifespan.final_population, 86);
+        assert_eq!(lifespan.escaping_gliders, 6);
+        assert_eq!(
+            (lifespan.max_population, lifespan.max_population_generation),
+            (319, 821)
+        );
+        assert_eq!(lifespan.initial_population, 5);
+    }
+
+    #[test]
+    fn it_stops_at_generation_limit() {
+        let lifespan = measure(&Pattern::from_rows(".OO/OO./.O."), &Rule::conway(), 100);
+        assert_eq!(lifespan.lifespan, None);
+        let lifespan = measure(&Pattern::from_rows("OOO"), &Rule::conway(), 100);
+        assert_eq!(lifespan.lifespan, Some(0));
+    }
+
+    #[test]
+    fn it_measures_files_under_their_own_rule() {
+        let directory = std::env::temp_dir().join(format!("gol_lifespans_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join("a.rle"),
+            "x = 2, y = 2, rule = B3/S\n2o$2o!\n",
+        )
+        .unwrap();
+        fs::write(directory.join("b.rle"), "x = 2, y = 2\n2o$2o!\n").unwrap();
+        let output = directory.join("lifespans.csv");
+        let results = measure_directory(&directory, &Rule::conway(), 100, output.to_str().unwrap());
+        fs::remove_dir_all(&directory).unwrap();
+        let block = Pattern::from_rows("OO/OO");
+        let results = results.unwrap();
+        assert_eq!(results[0].1, measure(&block, &"B3/S".parse().unwrap(), 100));
+        assert_eq!(results[1].1, measure(&block, &Rule::conway(), 100));
+        assert_ne!(results[0].1, results[1].1);
+    }
+}
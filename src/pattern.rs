@@ -0,0 +1,228 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::map::Map;
+use crate::rule::Rule;
+use crate::statistics::BoundingBox;
+
+/// Sparse set of alive cells on unbounded plane, kept sorted row by row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Pattern {
+    cells: Vec<(i32, i32)>,
+}
+
+impl Pattern {
+    pub fn new(mut cells: Vec<(i32, i32)>) -> Self {
+        cells.sort_by_key(|(x, y)| (*y, *x));
+        cells.dedup();
+        Self { cells }
+    }
+
+    /// Reads rows of `.` (dead) and `O` or `*` (alive) separated by `/` or new lines.
+    pub fn from_rows(rows: &str) -> Self {
+        let mut cells = Vec::new();
+        for (y, row) in rows.split(['/', '\n']).enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == 'O' || c == '*' {
+                    cells.push((x as i32, y as i32));
+                }
+            }
+        }
+        Self::new(cells)
+    }
+
+    pub fn cells(&self) -> &[(i32, i32)] {
+        &self.cells
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        let first = self.cells.first()?;
+        let mut bounds = BoundingBox {
+            min_x: first.0,
+            min_y: first.1,
+            max_x: first.0,
+            max_y: first.1,
+        };
+        for (x, y) in &self.cells {
+            bounds.min_x = bounds.min_x.min(*x);
+            bounds.min_y = bounds.min_y.min(*y);
+            bounds.max_x = bounds.max_x.max(*x);
+            bounds.max_y = bounds.max_y.max(*y);
+        }
+        Some(bounds)
+    }
+
+    pub fn translated(&self, dx: i32, dy: i32) -> Self {
+        Self {
+            cells: self.cells.iter().map(|(x, y)| (x + dx, y + dy)).collect(),
+        }
+    }
+
+    /// Moves pattern so its bounding box starts at 0,0 and returns previous corner.
+    pub fn normalized(&self) -> (Self, (i32, i32)) {
+        match self.bounds() {
+            Some(b) => (self.translated(-b.min_x, -b.min_y), (b.min_x, b.min_y)),
+            None => (self.clone(), (0, 0)),
+        }
+    }
+
+    /// One of 8 rotations and reflections of a square, `0` is identity.
+    pub fn transformed(&self, transform: u8) -> Self {
+        Self::new(
+            self.cells
+                .iter()
+                .map(|(x, y)| {
+                    let (x, y) = if transform & 4 != 0 { (*y, *x) } else { (*x, *y) };
+                    let x = if transform & 1 != 0 { -x } else { x };
+                    let y = if transform & 2 != 0 { -y } else { y };
+                    (x, y)
+                })
+                .collect(),
+        )
+    }
+
+    /// Smallest normalized form among all rotations and reflections.
+    pub fn canonical(&self) -> Self {
+        (0..8)
+            .map(|transform| self.transformed(transform).normalized().0)
+            .min()
+            .unwrap_or_default()
+    }
+
+    /// Next generation on unbounded plane, births without neighbours (B0) are ignored.
+    pub fn step(&self, rule: &Rule) -> Self {
+        let mut neighbours: HashMap<(i32, i32), usize> = HashMap::new();
+        for (x, y) in &self.cells {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if dx != 0 || dy != 0 {
+                        *neighbours.entry((x + dx, y + dy)).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+        let mut cells: Vec<(i32, i32)> = neighbours
+            .iter()
+            .filter(|(cell, count)| {
+                if self.contains(cell.0, cell.1) {
+                    rule.survives(**count)
+                } else {
+                    rule.is_born(**count)
+                }
+            })
+            .map(|(cell, _)| *cell)
+            .collect();
+        if rule.survives(0) {
+            cells.extend(self.cells.iter().filter(|cell| !neighbours.contains_key(cell)));
+        }
+        Self::new(cells)
+    }
+
+    /// Rows of `.` and `O` separated by `/`, counterpart of `from_rows`.
+    pub fn to_rows(&self) -> String {
+        let (normalized, _) = self.normalized();
+        let bounds = match normalized.bounds() {
+            Some(bounds) => bounds,
+            None => return String::new(),
+        };
+        let mut rows = vec![vec!['.'; bounds.max_x as usize + 1]; bounds.max_y as usize + 1];
+        for (x, y) in normalized.cells() {
+            rows[*y as usize][*x as usize] = 'O';
+        }
+        rows.iter()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+
+    /// Sets cells alive on `map` with pattern origin at `x`, `y`, cells outside are dropped.
+    pub fn place_on(&self, map: &mut Map, x: i32, y: i32) {
+        for (cx, cy) in &self.cells {
+            let (mx, my) = (cx + x, cy + y);
+            if (0..map.width()).contains(&mx) && (0..map.height()).contains(&my) {
+                map.set_alive(mx, my);
+            }
+        }
+    }
+
+    /// Places pattern in the middle of `map`.
+    pub fn place_centered_on(&self, map: &mut Map) {
+        let (normalized, _) = self.normalized();
+        if let Some(bounds) = normalized.bounds() {
+            normalized.place_on(
+                map,
+                (map.width() - bounds.max_x - 1) / 2,
+                (map.height() - bounds.max_y - 1) / 2,
+            );
+        }
+    }
+
+    /// Groups of cells where each cell is within `distance` of another one.
+    pub fn components(&self, distance: i32) -> Vec<Pattern> {
+        let mut unvisited: HashSet<(i32, i32)> = self.cells.iter().cloned().collect();
+        let mut objects = Vec::new();
+        for start in &self.cells {
+            if !unvisited.remove(start) {
+                continue;
+            }
+            let mut stack = vec![*start];
+            let mut cells = Vec::new();
+            while let Some((x, y)) = stack.pop() {
+                cells.push((x, y));
+                for dy in -distance..=distance {
+                    for dx in -distance..=distance {
+                        if unvisited.remove(&(x + dx, y + dy)) {
+                            stack.push((x + dx, y + dy));
+                        }
+                    }
+                }
+            }
+            objects.push(Pattern::new(cells));
+        }
+        objects
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        self.cells
+            .binary_search_by_key(&(y, x), |(cx, cy)| (*cy, *cx))
+            .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pattern::*;
+
+    #[test]
+    fn it_canonicalizes_rotations() {
+        let glider = Pattern::from_rows(".O./..O/OOO");
+        let rotated = glider.transformed(5).translated(7, -3);
+        assert_eq!(glider.canonical(), rotated.canonical());
+        assert_ne!(glider.canonical(), Pattern::from_rows("OOO").canonical());
+    }
+
+    #[test]
+    fn it_steps_on_unbounded_plane() {
+        let glider = Pattern::from_rows(".O./..O/OOO");
+        let mut next = glider.clone();
+        for _ in 0..4 {
+            next = next.step(&Rule::conway());
+        }
+        assert_eq!(next, glider.translated(1, 1));
+    }
+
+    #[test]
+    fn it_converts_rows() {
+        let boat = Pattern::from_rows("OO./O.O/.O.");
+        assert_eq!(boat.to_rows(), "OO./O.O/.O.");
+        assert!(boat.contains(2, 1));
+        assert!(!boat.contains(2, 0));
+    }
+}
@@ -0,0 +1,280 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::life;
+use crate::macrocell;
+use crate::map::{Map, Topology};
+use crate::mcell;
+use crate::pattern::Pattern;
+use crate::plaintext;
+use crate::rle::Rle;
+use crate::rule::Rule;
+
+/// Pattern exchange formats, see LifeWiki for their descriptions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternFormat {
+    Rle,
+    /// `.cells` rows of `.` and `O` with `!` comments.
+    Plaintext,
+    /// Blocks of rows each starting at `#P x y`.
+    Life105,
+    /// One `x y` line per alive cell.
+    Life106,
+    /// Quadtree of the pattern with identical subtrees written once, for huge patterns.
+    Macrocell,
+    /// MCell `#L` rows with `#GAME` and `#RULE` of one of many rule families.
+    MCell,
+}
+
+pub const PATTERN_FORMATS: [PatternFormat; 6] = [
+    PatternFormat::Rle,
+    PatternFormat::Plaintext,
+    PatternFormat::Life105,
+    PatternFormat::Life106,
+    PatternFormat::Macrocell,
+    PatternFormat::MCell,
+];
+
+impl PatternFormat {
+    /// Format written to `path`, `.lif` files are written as Life 1.06 and unknown
+    /// extensions as RLE.
+    pub fn from_path(path: &str) -> Self {
+        let path = path.to_lowercase();
+        if path.ends_with(".cells") || path.ends_with(".txt") {
+            PatternFormat::Plaintext
+        } else if path.ends_with(".lif") || path.ends_with(".life") {
+            PatternFormat::Life106
+        } else if path.ends_with(".mc") {
+            PatternFormat::Macrocell
+        } else if path.ends_with(".mcl") {
+            PatternFormat::MCell
+        } else {
+            PatternFormat::Rle
+        }
+    }
+
+    /// Format of file content, `None` when it looks like none of them.
+    pub fn sniff(text: &str) -> Option<Self> {
+        let first = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+        if first.starts_with("#Life 1.06") {
+            return Some(PatternFormat::Life106);
+        }
+        if first.starts_with("#Life 1.05") {
+            return Some(PatternFormat::Life105);
+        }
+        if first.starts_with(macrocell::HEADER) {
+            return Some(PatternFormat::Macrocell);
+        }
+        if first.starts_with(mcell::HEADER) {
+            return Some(PatternFormat::MCell);
+        }
+        let content = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('!'))?;
+        if content.starts_with('x') && content.contains('=') {
+            Some(PatternFormat::Rle)
+        } else if content.chars().all(|c| ".O*".contains(c)) {
+            Some(PatternFormat::Plaintext)
+        } else if content.split_whitespace().all(|n| n.parse::<i32>().is_ok()) {
+            Some(PatternFormat::Life106)
+        } else {
+            None
+        }
+    }
+
+    /// Extension decides for `.rle`, `.cells`, `.mc` and `.mcl`, content for the rest.
+    pub fn detect(path: &str, text: &str) -> Result<Self, String> {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".rle") {
+            return Ok(PatternFormat::Rle);
+        }
+        if lower.ends_with(".cells") {
+            return Ok(PatternFormat::Plaintext);
+        }
+        if lower.ends_with(".mc") {
+            return Ok(PatternFormat::Macrocell);
+        }
+        if lower.ends_with(".mcl") {
+            return Ok(PatternFormat::MCell);
+        }
+        Self::sniff(text).ok_or_else(|| format!("{}: unknown pattern format", path))
+    }
+}
+
+impl fmt::Display for PatternFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            PatternFormat::Rle => "rle",
+            PatternFormat::Plaintext => "cells",
+            PatternFormat::Life105 => "life105",
+            PatternFormat::Life106 => "life106",
+            PatternFormat::Macrocell => "mc",
+            PatternFormat::MCell => "mcl",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for PatternFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PATTERN_FORMATS
+            .iter()
+            .find(|format| format.to_string().eq_ignore_ascii_case(s))
+            .cloned()
+            .ok_or_else(|| {
+                format!(
+                    "Unknown pattern format {:?}, expected rle, cells, life105, life106, mc or mcl",
+                    s
+                )
+            })
+    }
+}
+
+/// Pattern read from or written to file in any format, with its name, author and comments.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PatternFile {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub comments: Vec<String>,
+    /// Rule given by file, `None` when it has none.
+    pub rule: Option<Rule>,
+    /// Board edges given by file, `None` when it doesn't say.
+    pub topology: Option<Topology>,
+    pub pattern: Pattern,
+}
+
+impl PatternFile {
+    pub fn new(pattern: Pattern, rule: Option<Rule>) -> Self {
+        Self {
+            pattern,
+            rule,
+            ..Self::default()
+        }
+    }
+
+    /// Alive cells of `map` in their bounding box.
+    pub fn from_map(map: &Map, rule: &Rule) -> Self {
+        Self {
+            topology: Some(map.topology()),
+            ..Rle::from_map(map, rule).into()
+        }
+    }
+
+    /// Clears `map` and places pattern in its middle, returns cells which didn't fit.
+    pub fn place_on(&self, map: &mut Map) -> usize {
+        map.clear();
+        self.pattern.place_centered_on(map);
+        self.pattern.population() - map.population()
+    }
+
+    pub fn parse(text: &str, format: PatternFormat) -> Result<Self, String> {
+        match format {
+            PatternFormat::Rle => Rle::parse(text).map(Self::from),
+            PatternFormat::Plaintext => plaintext::parse(text),
+            PatternFormat::Life105 => life::parse_105(text),
+            PatternFormat::Life106 => life::parse_106(text),
+            PatternFormat::Macrocell => macrocell::parse(text),
+            PatternFormat::MCell => mcell::parse(text),
+        }
+    }
+
+    pub fn to_text(&self, format: PatternFormat) -> String {
+        match format {
+            PatternFormat::Rle => Rle::from(self.clone()).to_text(),
+            PatternFormat::Plaintext => plaintext::to_text(self),
+            PatternFormat::Life105 => life::to_105_text(self),
+            PatternFormat::Life106 => life::to_106_text(self),
+            PatternFormat::Macrocell => macrocell::to_text(self),
+            PatternFormat::MCell => mcell::to_text(self),
+        }
+    }
+
+    /// Reads file in format detected from its extension and content.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let name = path.as_ref().display().to_string();
+        let text =
+            fs::read_to_string(path.as_ref()).map_err(|e| format!("Can't read {}: {}", name, e))?;
+        let format = PatternFormat::detect(&name, &text)?;
+        Self::parse(&text, format).map_err(|e| format!("{}: {}", name, e))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P, format: PatternFormat) -> Result<(), String> {
+        fs::write(path.as_ref(), self.to_text(format))
+            .map_err(|e| format!("Can't write {}: {}", path.as_ref().display(), e))
+    }
+}
+
+/// RLE keeps no topology, so it is lost on the way.
+impl From<PatternFile> for Rle {
+    fn from(file: PatternFile) -> Self {
+        Self {
+            name: file.name,
+            author: file.author,
+            comments: file.comments,
+            rule: file.rule,
+            pattern: file.pattern,
+        }
+    }
+}
+
+impl From<Rle> for PatternFile {
+    fn from(rle: Rle) -> Self {
+        Self {
+            name: rle.name,
+            author: rle.author,
+            comments: rle.comments,
+            rule: rle.rule,
+            topology: None,
+            pattern: rle.pattern,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pattern_file::*;
+
+    #[test]
+    fn it_detects_format() {
+        assert_eq!(
+            PatternFormat::sniff("#N Glider\nx = 3, y = 3\nbo$2bo$3o!"),
+            Some(PatternFormat::Rle)
+        );
+        assert_eq!(
+            PatternFormat::sniff("!Name: Glider\n.O\n..O\nOOO"),
+            Some(PatternFormat::Plaintext)
+        );
+        assert_eq!(
+            PatternFormat::sniff("#Life 1.05\n#P 0 0\n.*"),
+            Some(PatternFormat::Life105)
+        );
+        assert_eq!(
+            PatternFormat::sniff("#Life 1.06\n0 -1"),
+            Some(PatternFormat::Life106)
+        );
+        assert_eq!(PatternFormat::sniff("hello"), None);
+        assert_eq!(PatternFormat::detect("a.rle", ""), Ok(PatternFormat::Rle));
+        assert!(PatternFormat::detect("a.lif", "").is_err());
+        assert_eq!(
+            PatternFormat::from_path("glider.CELLS"),
+            PatternFormat::Plaintext
+        );
+    }
+
+    #[test]
+    fn it_converts_between_formats() {
+        let mut file = PatternFile::new(Pattern::from_rows(".O./..O/OOO"), Some(Rule::conway()));
+        file.name = Some("Glider".to_string());
+        for format in PATTERN_FORMATS {
+            let text = file.to_text(format);
+            assert_eq!(PatternFormat::sniff(&text), Some(format), "{}", text);
+            let read = PatternFile::parse(&text, format).unwrap();
+            assert_eq!(read.pattern.normalized().0, file.pattern, "{}", format);
+        }
+    }
+}
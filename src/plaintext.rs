@@ -0,0 +1,87 @@
+use crate::pattern::Pattern;
+use crate::pattern_file::PatternFile;
+
+/// Rows of `.` and `O` after `!Name:`, `!Author:` and other `!` comment lines.
+pub fn to_text(file: &PatternFile) -> String {
+    let mut text = String::new();
+    if let Some(name) = &file.name {
+        text.push_str(&format!("!Name: {}\n", name));
+    }
+    if let Some(author) = &file.author {
+        text.push_str(&format!("!Author: {}\n", author));
+    }
+    for comment in &file.comments {
+        text.push_str(&format!("!{}\n", comment));
+    }
+    let (pattern, _) = file.pattern.normalized();
+    if let Some(bounds) = pattern.bounds() {
+        for y in 0..=bounds.max_y {
+            let row: String = (0..=bounds.max_x)
+                .map(|x| if pattern.contains(x, y) { 'O' } else { '.' })
+                .collect();
+            text.push_str(&row);
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// Alive cells are `O` or `*`, rows may be shorter than the widest one.
+pub fn parse(text: &str) -> Result<PatternFile, String> {
+    let mut file = PatternFile::default();
+    let mut cells = Vec::new();
+    let mut y = 0;
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        if let Some(comment) = line.strip_prefix('!') {
+            let comment = comment.trim();
+            if let Some(name) = comment.strip_prefix("Name:") {
+                file.name = Some(name.trim().to_string());
+            } else if let Some(author) = comment.strip_prefix("Author:") {
+                file.author = Some(author.trim().to_string());
+            } else if !comment.is_empty() {
+                file.comments.push(comment.to_string());
+            }
+            continue;
+        }
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                '.' => {}
+                'O' | '*' => cells.push((x as i32, y)),
+                _ => {
+                    return Err(format!(
+                        "Line {}, column {}: unexpected character {:?}",
+                        number + 1,
+                        x + 1,
+                        c
+                    ))
+                }
+            }
+        }
+        y += 1;
+    }
+    file.pattern = Pattern::new(cells);
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::plaintext::*;
+
+    #[test]
+    fn it_reads_and_writes_cells() {
+        let text = "!Name: Glider\n!The smallest spaceship.\n.O\n..O\nOOO\n";
+        let file = parse(text).unwrap();
+        assert_eq!(file.name.as_deref(), Some("Glider"));
+        assert_eq!(file.comments, vec!["The smallest spaceship.".to_string()]);
+        assert_eq!(file.pattern, Pattern::from_rows(".O./..O/OOO"));
+        assert_eq!(
+            to_text(&file),
+            "!Name: Glider\n!The smallest spaceship.\n.O.\n..O\nOOO\n"
+        );
+        assert_eq!(
+            parse(".O\nOxO"),
+            Err("Line 2, column 2: unexpected character 'x'".to_string())
+        );
+    }
+}
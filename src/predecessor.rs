@@ -0,0 +1,414 @@
+use std::fmt;
+
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+use crate::sat::{Lit, SolveResult, Solver};
+use crate::statistics::BoundingBox;
+
+pub const DEFAULT_PARENT_GENERATIONS: u64 = 1;
+pub const DEFAULT_PARENT_MARGIN: i32 = 1;
+/// Keeps editor responsive on boxes too large to settle.
+pub const DEFAULT_CONFLICT_LIMIT: u64 = 200_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predecessor {
+    /// Pattern which turns into target after requested generations.
+    Found(Pattern),
+    /// Proof that nothing inside the box evolves into target.
+    NotFound,
+    /// Solver gave up before answering.
+    Unknown,
+}
+
+impl fmt::Display for Predecessor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Predecessor::Found(pattern) => write!(f, "{}", pattern.to_rows()),
+            Predecessor::NotFound => write!(f, "no predecessor within box"),
+            Predecessor::Unknown => write!(f, "gave up"),
+        }
+    }
+}
+
+/// Looks for patterns which evolve into target on unbounded plane by solving
+/// the transition written as boolean formula.
+/// Cells of the parent may only lie in the target box grown by `margin`,
+/// everything outside is dead, so unsatisfiable formula proves no such parent exists.
+#[derive(Debug, Clone)]
+pub struct PredecessorSearch {
+    rule: Rule,
+    generations: u64,
+    margin: i32,
+    conflict_limit: Option<u64>,
+    /// Cells of the parent outside these are dead, like beyond edges of bounded board.
+    bounds: Option<BoundingBox>,
+    /// Cells around the parent which stay as they are, like rest of the board.
+    surroundings: Option<Pattern>,
+}
+
+impl PredecessorSearch {
+    pub fn new(rule: Rule) -> Self {
+        Self {
+            rule,
+            generations: DEFAULT_PARENT_GENERATIONS,
+            margin: DEFAULT_PARENT_MARGIN,
+            conflict_limit: None,
+            bounds: None,
+            surroundings: None,
+        }
+    }
+
+    pub fn with_generations(mut self, generations: u64) -> Self {
+        self.generations = generations.max(1);
+        self
+    }
+
+    pub fn with_margin(mut self, margin: i32) -> Self {
+        self.margin = margin.max(0);
+        self
+    }
+
+    pub fn with_conflict_limit(mut self, limit: u64) -> Self {
+        self.conflict_limit = Some(limit);
+        self
+    }
+
+    pub fn with_bounds(mut self, bounds: BoundingBox) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Alive cells of `surroundings` around the parent take part in its evolution, cells near
+    /// the area have to end up as surroundings alone would make them instead of dead.
+    pub fn with_surroundings(mut self, surroundings: Pattern) -> Self {
+        self.surroundings = Some(surroundings);
+        self
+    }
+
+    /// Parent of `target` alone, area defaults to its bounding box.
+    pub fn find(&self, target: &Pattern) -> Predecessor {
+        match target.bounds() {
+            Some(area) => self.find_in(target, area),
+            None => Predecessor::Found(Pattern::default()),
+        }
+    }
+
+    /// Parent of `target` where every cell of `area` not in target must end up dead,
+    /// target cells outside `area` are ignored.
+    pub fn find_in(&self, target: &Pattern, area: BoundingBox) -> Predecessor {
+        let mut solver = match self.conflict_limit {
+            Some(limit) => Solver::new().with_conflict_limit(limit),
+            None => Solver::new(),
+        };
+        let truth = solver.new_var().positive();
+        solver.add_clause(&[truth]);
+
+        let mut parent_region = Region::new(area, self.margin);
+        // Fixed ring reaches everything evolving into cells within 1 of the parent region
+        let ring = if self.surroundings.is_some() {
+            self.generations as i32 + 1
+        } else {
+            0
+        };
+        let mut first = Region::new(area, self.margin + ring);
+        if let Some(bounds) = self.bounds {
+            parent_region = parent_region.clipped(&bounds);
+            first = first.clipped(&bounds);
+            if parent_region.min_x > parent_region.max_x
+                || parent_region.min_y > parent_region.max_y
+            {
+                return Predecessor::NotFound;
+            }
+        }
+        let mut region = first;
+        let mut cells = Vec::with_capacity(region.len());
+        for y in first.min_y..=first.max_y {
+            for x in first.min_x..=first.max_x {
+                let cell = match &self.surroundings {
+                    Some(surroundings) if parent_region.index(x, y).is_none() => {
+                        if surroundings.contains(x, y) {
+                            truth
+                        } else {
+                            !truth
+                        }
+                    }
+                    _ => solver.new_var().positive(),
+                };
+                cells.push(cell);
+            }
+        }
+        let parent = cells.clone();
+        // Around the area cells end up as surroundings alone would make them, as far as the
+        // fixed ring decides them
+        let checked = Region::new(area, self.margin + 1);
+        let expected = self.surroundings.as_ref().map(|surroundings| {
+            let mut expected = surroundings.clone();
+            for _ in 0..self.generations {
+                expected = expected.step(&self.rule);
+            }
+            expected
+        });
+        for _ in 0..self.generations {
+            let next = region.grown();
+            let mut next_cells = Vec::with_capacity(next.len());
+            for y in next.min_y..=next.max_y {
+                for x in next.min_x..=next.max_x {
+                    let centre = region.index(x, y).map(|index| cells[index]);
+                    let neighbours: Vec<Lit> = NEIGHBOURS
+                        .iter()
+                        .filter_map(|(dx, dy)| region.index(x + dx, y + dy))
+                        .map(|index| cells[index])
+                        .collect();
+                    let cell = solver.new_var().positive();
+                    self.encode_transition(&mut solver, truth, centre, &neighbours, cell);
+                    next_cells.push(cell);
+                }
+            }
+            region = next;
+            cells = next_cells;
+        }
+        for y in region.min_y..=region.max_y {
+            for x in region.min_x..=region.max_x {
+                let inside = area_contains(&area, x, y);
+                let alive = match &expected {
+                    _ if inside => target.contains(x, y),
+                    Some(_) if checked.index(x, y).is_none() => continue,
+                    Some(expected) => expected.contains(x, y),
+                    None => false,
+                };
+                let cell = cells[region.index(x, y).unwrap()];
+                solver.add_clause(&[if alive { cell } else { !cell }]);
+            }
+        }
+
+        match solver.solve() {
+            SolveResult::Satisfiable(model) => {
+                let mut found = Vec::new();
+                for y in parent_region.min_y..=parent_region.max_y {
+                    for x in parent_region.min_x..=parent_region.max_x {
+                        let lit = parent[first.index(x, y).unwrap()];
+                        if model[lit.var().index()] {
+                            found.push((x, y));
+                        }
+                    }
+                }
+                Predecessor::Found(Pattern::new(found))
+            }
+            SolveResult::Unsatisfiable => Predecessor::NotFound,
+            SolveResult::Unknown => Predecessor::Unknown,
+        }
+    }
+
+    /// Ties `next` to rule applied on `centre` and count of alive `neighbours`,
+    /// missing centre lies outside region and is dead.
+    fn encode_transition(
+        &self,
+        solver: &mut Solver,
+        truth: Lit,
+        centre: Option<Lit>,
+        neighbours: &[Lit],
+        next: Lit,
+    ) {
+        let counts = count(solver, truth, neighbours);
+        for n in 0..=neighbours.len() {
+            // Exactly `n` neighbours: at least `n` and not at least `n + 1`
+            let exactly = [!counts[n], counts[n + 1]];
+            let born = self.rule.is_born(n) && n > 0;
+            let mut dead = exactly.to_vec();
+            dead.push(if born { next } else { !next });
+            match centre {
+                Some(centre) => {
+                    dead.push(centre);
+                    solver.add_clause(&dead);
+                    let mut alive = exactly.to_vec();
+                    alive.push(!centre);
+                    alive.push(if self.rule.survives(n) { next } else { !next });
+                    solver.add_clause(&alive);
+                }
+                None => solver.add_clause(&dead),
+            }
+        }
+    }
+}
+
+fn area_contains(area: &BoundingBox, x: i32, y: i32) -> bool {
+    (area.min_x..=area.max_x).contains(&x) && (area.min_y..=area.max_y).contains(&y)
+}
+
+const NEIGHBOURS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// Literals meaning "at least `n` of inputs are true" for `n` from 0 to `inputs.len() + 1`,
+/// built as sequential counter constrained in both directions.
+fn count(solver: &mut Solver, truth: Lit, inputs: &[Lit]) -> Vec<Lit> {
+    let mut counts = vec![truth, !truth];
+    for &input in inputs {
+        let mut next = vec![truth];
+        for n in 1..counts.len() {
+            let previous = counts[n];
+            let below = counts[n - 1];
+            let at_least = solver.new_var().positive();
+            solver.add_clause(&[!previous, at_least]);
+            solver.add_clause(&[!input, !below, at_least]);
+            solver.add_clause(&[!at_least, previous, input]);
+            solver.add_clause(&[!at_least, previous, below]);
+            next.push(at_least);
+        }
+        next.push(!truth);
+        counts = next;
+    }
+    counts
+}
+
+/// Rectangle of cells which may be alive in one generation.
+#[derive(Debug, Clone, Copy)]
+struct Region {
+    min_x: i32,
+    min_y: i32,
+    max_x: i32,
+    max_y: i32,
+}
+
+impl Region {
+    fn new(area: BoundingBox, margin: i32) -> Self {
+        Self {
+            min_x: area.min_x - margin,
+            min_y: area.min_y - margin,
+            max_x: area.max_x + margin,
+            max_y: area.max_y + margin,
+        }
+    }
+
+    /// Cells missing from region are dead, so clipping keeps the parent within `bounds`.
+    fn clipped(&self, bounds: &BoundingBox) -> Self {
+        Self {
+            min_x: self.min_x.max(bounds.min_x),
+            min_y: self.min_y.max(bounds.min_y),
+            max_x: self.max_x.min(bounds.max_x),
+            max_y: self.max_y.min(bounds.max_y),
+        }
+    }
+
+    fn grown(&self) -> Self {
+        Self {
+            min_x: self.min_x - 1,
+            min_y: self.min_y - 1,
+            max_x: self.max_x + 1,
+            max_y: self.max_y + 1,
+        }
+    }
+
+    fn width(&self) -> i32 {
+        self.max_x - self.min_x + 1
+    }
+
+    fn len(&self) -> usize {
+        (self.width() * (self.max_y - self.min_y + 1)) as usize
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < self.min_x || x > self.max_x || y < self.min_y || y > self.max_y {
+            return None;
+        }
+        Some(((y - self.min_y) * self.width() + x - self.min_x) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::predecessor::*;
+
+    fn evolve(pattern: &Pattern, generations: u64) -> Pattern {
+        let mut pattern = pattern.clone();
+        for _ in 0..generations {
+            pattern = pattern.step(&Rule::conway());
+        }
+        pattern
+    }
+
+    #[test]
+    fn it_finds_parents_evolving_into_target() {
+        for (rows, generations) in &[(".O./..O/OOO", 1), ("OO/OO", 1), ("O.O/.O./O.O", 2)] {
+            let target = Pattern::from_rows(rows);
+            let search = PredecessorSearch::new(Rule::conway()).with_generations(*generations);
+            match search.find(&target) {
+                Predecessor::Found(parent) => assert_eq!(evolve(&parent, *generations), target),
+                result => panic!("{}: {:?}", rows, result),
+            }
+        }
+    }
+
+    #[test]
+    fn it_proves_no_parent_within_box() {
+        // Lonely cell dies, so nothing in its own box produces it
+        let target = Pattern::from_rows("O");
+        let search = PredecessorSearch::new(Rule::conway()).with_margin(0);
+        assert_eq!(search.find(&target), Predecessor::NotFound);
+        let search = search.with_margin(1);
+        match search.find(&target) {
+            Predecessor::Found(parent) => assert_eq!(evolve(&parent, 1), target),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn it_keeps_parent_within_bounds() {
+        // Block in the corner of board, parent cells left or above it would fall off
+        let target = Pattern::from_rows("OO/OO");
+        let board = BoundingBox {
+            min_x: 0,
+            min_y: 0,
+            max_x: 19,
+            max_y: 19,
+        };
+        let search = PredecessorSearch::new(Rule::conway()).with_bounds(board);
+        match search.find(&target) {
+            Predecessor::Found(parent) => {
+                assert!(parent.cells().iter().all(|(x, y)| *x >= 0 && *y >= 0));
+                assert_eq!(evolve(&parent, 1), target);
+            }
+            result => panic!("{:?}", result),
+        }
+        let outside = BoundingBox {
+            min_x: 5,
+            min_y: 5,
+            max_x: 9,
+            max_y: 9,
+        };
+        let search = search.with_bounds(outside);
+        assert_eq!(search.find(&target), Predecessor::NotFound);
+    }
+
+    #[test]
+    fn it_keeps_surroundings_in_evolution() {
+        let target = Pattern::from_rows("OO/OO").translated(5, 5);
+        let area = target.bounds().unwrap();
+        // Block just outside of the parent region, whose cells reach 4 to 7
+        let block = Pattern::from_rows("OO/OO").translated(8, 5);
+        let search = PredecessorSearch::new(Rule::conway()).with_surroundings(block.clone());
+        match search.find_in(&target, area) {
+            Predecessor::Found(parent) => {
+                assert!(parent.cells().iter().all(|(x, _)| *x < 8));
+                let mut cells = parent.cells().to_vec();
+                cells.extend(block.cells());
+                let next = Pattern::new(cells).step(&Rule::conway());
+                let near: Vec<(i32, i32)> = next
+                    .cells()
+                    .iter()
+                    .filter(|(x, y)| (3..=8).contains(x) && (3..=8).contains(y))
+                    .cloned()
+                    .collect();
+                assert_eq!(near, vec![(5, 5), (6, 5), (8, 5), (5, 6), (6, 6), (8, 6)]);
+            }
+            result => panic!("{:?}", result),
+        }
+    }
+}
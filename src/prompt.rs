@@ -0,0 +1,93 @@
+#[derive(Debug, Clone, PartialEq)]
+pub enum PromptKind {
+    JumpToGeneration,
+    Rule,
+    ExportStatistics,
+    ExportCensus,
+    SoupSeed,
+    ImportPattern,
+    ExportPattern,
+    Apgcode,
+    SaveSession,
+    LoadSession,
+    Screenshot,
+    Poster,
+    Svg,
+}
+
+/// Single line text input shown above the grid.
+#[derive(Debug, Clone)]
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub input: String,
+}
+
+impl Prompt {
+    pub fn new(kind: PromptKind) -> Self {
+        Self {
+            kind,
+            input: String::new(),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self.kind {
+            PromptKind::JumpToGeneration => "Jump to generation",
+            PromptKind::Rule => "Rule",
+            PromptKind::ExportStatistics => "Export statistics to",
+            PromptKind::ExportCensus => "Export census to",
+            PromptKind::SoupSeed => "Soup seed",
+            PromptKind::ImportPattern => "Import pattern from",
+            PromptKind::ExportPattern => "Export pattern to",
+            PromptKind::Apgcode => "Paste apgcode",
+            PromptKind::SaveSession => "Save session to",
+            PromptKind::LoadSession => "Load session from",
+            PromptKind::Screenshot => "Save screenshot to",
+            PromptKind::Poster => "Save poster to",
+            PromptKind::Svg => "Save SVG to",
+        }
+    }
+
+    pub fn push(&mut self, text: &str) {
+        for c in text.chars() {
+            if self.accepts(c) {
+                self.input.push(c);
+            }
+        }
+    }
+
+    pub fn pop(&mut self) {
+        self.input.pop();
+    }
+
+    fn accepts(&self, c: char) -> bool {
+        match self.kind {
+            PromptKind::JumpToGeneration | PromptKind::SoupSeed => c.is_ascii_digit(),
+            PromptKind::Rule => c.is_ascii_digit() || "BbSs/".contains(c),
+            PromptKind::ExportStatistics
+            | PromptKind::ExportCensus
+            | PromptKind::ImportPattern
+            | PromptKind::ExportPattern
+            | PromptKind::SaveSession
+            | PromptKind::LoadSession
+            | PromptKind::Screenshot
+            | PromptKind::Poster
+            | PromptKind::Svg => !c.is_control(),
+            PromptKind::Apgcode => c.is_ascii_alphanumeric() || c == '_',
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prompt::*;
+
+    #[test]
+    fn it_filters_generation_input() {
+        let mut prompt = Prompt::new(PromptKind::JumpToGeneration);
+        prompt.push("1a2 3");
+        assert_eq!(prompt.input, "123");
+        prompt.pop();
+        assert_eq!(prompt.input, "12");
+    }
+}
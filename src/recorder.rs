@@ -0,0 +1,209 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+use crate::gif::GifWriter;
+use crate::screenshot::{PaletteIndex, Poster};
+use crate::simulation::Simulation;
+
+pub const DEFAULT_RECORD_TO: u64 = 100;
+/// Hundredths of second each frame is shown.
+pub const DEFAULT_FRAME_DELAY: u16 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// Single animation.
+    Gif,
+    /// Numbered image per frame.
+    Png,
+    /// Numbered binary `P6` image per frame, written without SDL.
+    Ppm,
+}
+
+impl RecordFormat {
+    pub fn from_path(path: &str) -> Result<Self, String> {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".gif") {
+            Ok(RecordFormat::Gif)
+        } else if lower.ends_with(".png") {
+            Ok(RecordFormat::Png)
+        } else if lower.ends_with(".ppm") {
+            Ok(RecordFormat::Ppm)
+        } else {
+            Err(format!(
+                "{}: recording must be .gif, or .png or .ppm frame sequence",
+                path
+            ))
+        }
+    }
+}
+
+/// Captures every `every`-th generation from `from` to `to` as it is computed, so frames
+/// don't depend on how fast the window draws.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recorder {
+    pub from: u64,
+    pub to: u64,
+    pub every: u64,
+    pub delay: u16,
+    /// Times animation repeats, 0 repeats forever.
+    pub loop_count: u16,
+    pub poster: Poster,
+}
+
+impl Recorder {
+    pub fn new(from: u64, to: u64) -> Self {
+        Self {
+            from,
+            to,
+            every: 1,
+            delay: DEFAULT_FRAME_DELAY,
+            loop_count: 0,
+            poster: Poster::new(1),
+        }
+    }
+
+    pub fn with_every(mut self, every: u64) -> Self {
+        self.every = every.max(1);
+        self
+    }
+
+    pub fn with_delay(mut self, delay: u16) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn with_loop_count(mut self, loop_count: u16) -> Self {
+        self.loop_count = loop_count;
+        self
+    }
+
+    pub fn with_poster(mut self, poster: Poster) -> Self {
+        self.poster = poster;
+        self
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        if self.to < self.from {
+            0
+        } else {
+            (self.to - self.from) / self.every + 1
+        }
+    }
+
+    /// Runs `simulation` up to `to` and writes frames to `path`, `progress` gets generation
+    /// of every frame. Returns number of frames.
+    pub fn record<F: FnMut(u64)>(
+        &self,
+        simulation: &mut Simulation,
+        path: &str,
+        mut progress: F,
+    ) -> Result<u64, String> {
+        let format = RecordFormat::from_path(path)?;
+        let (width, height) = self.poster.size(simulation.map());
+        let mut gif = match format {
+            RecordFormat::Gif => {
+                if width > u16::MAX as u32 || height > u16::MAX as u32 {
+                    return Err(format!("{}x{} is too large for GIF", width, height));
+                }
+                let file =
+                    File::create(path).map_err(|e| format!("Can't write {}: {}", path, e))?;
+                let gif = GifWriter::new(
+                    BufWriter::new(file),
+                    width as u16,
+                    height as u16,
+                    &self.poster.palette(),
+                    self.loop_count,
+                )
+                .map_err(|e| format!("Can't write {}: {}", path, e))?;
+                Some(if self.poster.transparent {
+                    gif.with_transparent(PaletteIndex::Background as u8)
+                } else {
+                    gif
+                })
+            }
+            RecordFormat::Png | RecordFormat::Ppm => None,
+        };
+        while simulation.generation() < self.from {
+            simulation.next_generation();
+        }
+        for frame in 0..self.frame_count() {
+            if frame > 0 {
+                for _ in 0..self.every {
+                    simulation.next_generation();
+                }
+            }
+            let frame_path = numbered_path(path, frame);
+            match (format, &mut gif) {
+                (RecordFormat::Gif, Some(gif)) => gif
+                    .add_frame(&self.poster.indexed(simulation.map()), self.delay)
+                    .map_err(|e| format!("Can't write {}: {}", path, e))?,
+                (RecordFormat::Png, _) => self.poster.save(simulation.map(), &frame_path)?,
+                _ => fs::write(&frame_path, self.ppm(simulation))
+                    .map_err(|e| format!("Can't write {}: {}", frame_path, e))?,
+            }
+            progress(simulation.generation());
+        }
+        if let Some(gif) = gif {
+            gif.finish()
+                .map_err(|e| format!("Can't write {}: {}", path, e))?;
+        }
+        Ok(self.frame_count())
+    }
+
+    fn ppm(&self, simulation: &Simulation) -> Vec<u8> {
+        let (width, height) = self.poster.size(simulation.map());
+        let palette = self.poster.palette();
+        let mut bytes = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        for index in self.poster.indexed(simulation.map()) {
+            bytes.extend(&palette[index as usize]);
+        }
+        bytes
+    }
+}
+
+/// `run.png` becomes `run_0000.png` for the first frame.
+pub fn numbered_path(path: &str, frame: u64) -> String {
+    let file = Path::new(path);
+    let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or("frame");
+    let name = match file.extension().and_then(|e| e.to_str()) {
+        Some(extension) => format!("{}_{:04}.{}", stem, frame, extension),
+        None => format!("{}_{:04}", stem, frame),
+    };
+    file.with_file_name(name).display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::Map;
+    use crate::pattern::Pattern;
+    use crate::recorder::*;
+
+    #[test]
+    fn it_records_range_of_generations() {
+        let mut map = Map::new();
+        Pattern::from_rows("OOO").place_on(&mut map, 5, 5);
+        let mut simulation = Simulation::new(map);
+        let path = std::env::temp_dir().join("game_of_life_blinker.ppm");
+        let path = path.display().to_string();
+        let recorder = Recorder::new(3, 7).with_every(2);
+        assert_eq!(recorder.frame_count(), 3);
+        let mut generations = Vec::new();
+        let frames = recorder.record(&mut simulation, &path, |generation| {
+            generations.push(generation)
+        });
+        assert_eq!(frames, Ok(3));
+        assert_eq!(generations, vec![3, 5, 7]);
+        // Vertical blinker in odd generations
+        let first = fs::read(numbered_path(&path, 0)).unwrap();
+        let header = b"P6\n20 20\n255\n".len();
+        let pixel = |x: usize, y: usize| &first[header + (y * 20 + x) * 3..][..3];
+        assert_eq!(pixel(6, 4), &[20, 20, 20]);
+        assert_eq!(pixel(5, 5), &[220, 220, 220]);
+        for frame in 0..3 {
+            fs::remove_file(numbered_path(&path, frame)).unwrap();
+        }
+        assert_eq!(numbered_path("out/run.png", 12), "out/run_0012.png");
+        assert!(RecordFormat::from_path("run.mp4").is_err());
+    }
+}
@@ -1,12 +1,14 @@
+use sdl2::gfx::primitives::DrawRenderer;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::render::TextureCreator;
 use sdl2::ttf::Sdl2TtfContext;
 use sdl2::video::WindowContext;
 
-use crate::managers::{FontManager, TextureManager};
+use crate::managers::{FontDetails, FontManager, TextureManager};
 
-use crate::app::WindowCanvas;
+use crate::app::{WindowCanvas, FONT_PATH, LABEL_FONT_SIZE};
+use sdl2::rect::Point;
 use sdl2::render::Texture;
 use std::rc::Rc;
 
@@ -70,4 +72,40 @@ impl<'a, 'b> Renderer<'a, 'b> {
         canvas.set_draw_color(color.clone());
         canvas.fill_rect(rect.clone()).unwrap();
     }
+
+    pub fn render_line(&mut self, canvas: &mut WindowCanvas, from: Point, to: Point, color: &Color) {
+        canvas
+            .line(
+                from.x() as i16,
+                from.y() as i16,
+                to.x() as i16,
+                to.y() as i16,
+                *color,
+            )
+            .unwrap();
+    }
+
+    pub fn render_text(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        text: &str,
+        position: Point,
+        color: &Color,
+    ) {
+        // SDL_ttf refuses to render empty strings
+        if text.is_empty() {
+            return;
+        }
+        let font = self
+            .font_manager
+            .load(&FontDetails::new(FONT_PATH, LABEL_FONT_SIZE))
+            .unwrap();
+        let surface = font.render(text).blended(*color).unwrap();
+        let tex = self
+            .texture_creator
+            .create_texture_from_surface(&surface)
+            .unwrap();
+        let dest = Rect::new(position.x(), position.y(), surface.width(), surface.height());
+        canvas.copy(&tex, None, Some(dest)).unwrap();
+    }
 }
@@ -0,0 +1,315 @@
+use crate::map::Map;
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+
+/// Longest data line written, as recommended by the format.
+const LINE_LENGTH: usize = 70;
+
+/// Pattern in Run Length Encoded format with its `#N`, `#O` and `#C` comments.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Rle {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub comments: Vec<String>,
+    /// Rule from header, `None` when it has none.
+    pub rule: Option<Rule>,
+    /// Cells relative to top left corner of box given by header.
+    pub pattern: Pattern,
+}
+
+impl Rle {
+    pub fn new(pattern: Pattern, rule: Option<Rule>) -> Self {
+        Self {
+            pattern,
+            rule,
+            ..Self::default()
+        }
+    }
+
+    /// Alive cells of `map` in their bounding box.
+    pub fn from_map(map: &Map, rule: &Rule) -> Self {
+        Self::new(Pattern::new(map.alive_cells()).normalized().0, Some(*rule))
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        if let Some(name) = &self.name {
+            text.push_str(&format!("#N {}\n", name));
+        }
+        if let Some(author) = &self.author {
+            text.push_str(&format!("#O {}\n", author));
+        }
+        for comment in &self.comments {
+            text.push_str(&format!("#C {}\n", comment));
+        }
+        let (pattern, _) = self.pattern.normalized();
+        let (width, height) = pattern
+            .bounds()
+            .map_or((0, 0), |b| (b.max_x + 1, b.max_y + 1));
+        text.push_str(&format!("x = {}, y = {}", width, height));
+        if let Some(rule) = self.rule {
+            text.push_str(&format!(", rule = {}", rule));
+        }
+        text.push('\n');
+
+        let mut line = String::new();
+        let mut push = |token: String, text: &mut String| {
+            if line.len() + token.len() > LINE_LENGTH {
+                text.push_str(&line);
+                text.push('\n');
+                line.clear();
+            }
+            line.push_str(&token);
+        };
+        let mut row_ends = 0;
+        for y in 0..height {
+            let cells: Vec<i32> = pattern
+                .cells()
+                .iter()
+                .filter(|(_, cy)| *cy == y)
+                .map(|(x, _)| *x)
+                .collect();
+            if cells.is_empty() {
+                row_ends += 1;
+                continue;
+            }
+            if row_ends > 0 {
+                push(run(row_ends, '$'), &mut text);
+            }
+            let mut x = 0;
+            let mut index = 0;
+            while index < cells.len() {
+                if cells[index] > x {
+                    push(run(cells[index] - x, 'b'), &mut text);
+                }
+                let start = cells[index];
+                while index + 1 < cells.len() && cells[index + 1] == cells[index] + 1 {
+                    index += 1;
+                }
+                push(run(cells[index] - start + 1, 'o'), &mut text);
+                x = cells[index] + 1;
+                index += 1;
+            }
+            row_ends = 1;
+        }
+        push("!".to_string(), &mut text);
+        text.push_str(&line);
+        text.push('\n');
+        text
+    }
+
+    /// Accepts two state `b` and `o` cells as well as multi-state letters, every state
+    /// other than 0 is alive.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut rle = Self::default();
+        let mut size = None;
+        let mut cells = Vec::new();
+        let (mut x, mut y) = (0, 0);
+        let mut count: Option<i32> = None;
+        let mut prefix: Option<(char, usize)> = None;
+        let mut finished = false;
+        let mut last = (0, 0);
+        'lines: for (number, line) in text.lines().enumerate() {
+            let number = number + 1;
+            let trimmed = line.trim();
+            if trimmed.starts_with('#') {
+                let value = trimmed.get(2..).unwrap_or("").trim().to_string();
+                match trimmed.get(..2) {
+                    Some("#N") => rle.name = Some(value),
+                    Some("#O") => rle.author = Some(value),
+                    Some("#C") | Some("#c") => rle.comments.push(value),
+                    // Offsets (#P, #R) and anything else are irrelevant once centred
+                    _ => {}
+                }
+                continue;
+            }
+            if trimmed.is_empty() {
+                continue;
+            }
+            let (width, height) = match size {
+                Some(size) => size,
+                None => {
+                    let (width, height, rule) = parse_header(line, number)?;
+                    size = Some((width, height));
+                    rle.rule = rule;
+                    continue;
+                }
+            };
+            for (column, c) in line.chars().enumerate() {
+                let column = column + 1;
+                last = (number, column);
+                let at =
+                    |message: String| format!("Line {}, column {}: {}", number, column, message);
+                if let Some((letter, _)) = prefix {
+                    if !c.is_ascii_uppercase() || c > 'X' {
+                        return Err(at(format!(
+                            "expected state letter A to X after {:?}, found {:?}",
+                            letter, c
+                        )));
+                    }
+                }
+                let state = match c {
+                    '0'..='9' => {
+                        let digit = c as i32 - '0' as i32;
+                        let value = count
+                            .unwrap_or(0)
+                            .checked_mul(10)
+                            .and_then(|value| value.checked_add(digit))
+                            .ok_or_else(|| at("run count is too large".to_string()))?;
+                        count = Some(value);
+                        continue;
+                    }
+                    c if c.is_whitespace() => continue,
+                    'b' | '.' => 0,
+                    'o' => 1,
+                    'p'..='y' => {
+                        prefix = Some((c, (c as usize - 'p' as usize + 1) * 24));
+                        continue;
+                    }
+                    'A'..='X' => {
+                        prefix.take().map_or(0, |(_, base)| base) + c as usize - 'A' as usize + 1
+                    }
+                    '$' => {
+                        y += count.take().unwrap_or(1);
+                        x = 0;
+                        continue;
+                    }
+                    '!' => {
+                        finished = true;
+                        break 'lines;
+                    }
+                    _ => return Err(at(format!("unexpected character {:?}", c))),
+                };
+                let length = count.take().unwrap_or(1);
+                if state > 0 {
+                    if x + length > width || y >= height {
+                        return Err(at(format!(
+                            "alive cell outside of x = {}, y = {} box from header",
+                            width, height
+                        )));
+                    }
+                    cells.extend((x..x + length).map(|cx| (cx, y)));
+                }
+                x += length;
+            }
+        }
+        if size.is_none() {
+            return Err("Missing header line like \"x = 3, y = 3, rule = B3/S23\"".to_string());
+        }
+        if !finished {
+            return Err(format!(
+                "Line {}, column {}: pattern isn't terminated by '!'",
+                last.0,
+                last.1 + 1
+            ));
+        }
+        rle.pattern = Pattern::new(cells);
+        Ok(rle)
+    }
+}
+
+/// Count is left out for runs of 1.
+fn run(length: i32, tag: char) -> String {
+    if length == 1 {
+        tag.to_string()
+    } else {
+        format!("{}{}", length, tag)
+    }
+}
+
+/// Reads `x = 3, y = 3, rule = B3/S23`, rule is optional and may carry `:T` bounded grid suffix.
+fn parse_header(line: &str, number: usize) -> Result<(i32, i32, Option<Rule>), String> {
+    let mut width = None;
+    let mut height = None;
+    let mut rule = None;
+    let mut column = 1;
+    let mut rest = line;
+    while !rest.is_empty() {
+        // Rule comes last and its bounded grid suffix may contain commas
+        let item = if rest.trim_start().starts_with("rule") {
+            rest
+        } else {
+            rest.split(',').next().unwrap_or(rest)
+        };
+        let at = |message: String| format!("Line {}, column {}: {}", number, column, message);
+        let (key, value) = item
+            .split_once('=')
+            .ok_or_else(|| at(format!("expected \"key = value\", found {:?}", item.trim())))?;
+        let value = value.trim();
+        match key.trim() {
+            "x" | "y" => {
+                let size: i32 = value
+                    .parse()
+                    .ok()
+                    .filter(|size| *size >= 0)
+                    .ok_or_else(|| at(format!("invalid size {:?}", value)))?;
+                if key.trim() == "x" {
+                    width = Some(size);
+                } else {
+                    height = Some(size);
+                }
+            }
+            "rule" => {
+                let notation = value.split(':').next().unwrap_or(value);
+                rule = Some(notation.parse().map_err(&at)?);
+            }
+            key => return Err(at(format!("unknown header key {:?}", key))),
+        }
+        column += item.chars().count() + 1;
+        rest = rest.get(item.len() + 1..).unwrap_or("");
+    }
+    match (width, height) {
+        (Some(width), Some(height)) => Ok((width, height, rule)),
+        _ => Err(format!(
+            "Line {}, column 1: header needs both x and y",
+            number
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rle::*;
+
+    const GLIDER: &str = "#N Glider\n#O Richard K. Guy\n#C The smallest spaceship.\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n";
+
+    #[test]
+    fn it_reads_and_writes_glider() {
+        let rle = Rle::parse(GLIDER).unwrap();
+        assert_eq!(rle.pattern, Pattern::from_rows(".O./..O/OOO"));
+        assert_eq!(rle.name.as_deref(), Some("Glider"));
+        assert_eq!(rle.author.as_deref(), Some("Richard K. Guy"));
+        assert_eq!(rle.comments, vec!["The smallest spaceship.".to_string()]);
+        assert_eq!(rle.rule, Some(Rule::conway()));
+        assert_eq!(rle.to_text(), GLIDER);
+    }
+
+    #[test]
+    fn it_reads_runs_across_lines_and_states() {
+        let rle = Rle::parse("x = 12, y = 4, rule = 23/36:T20,20\n10b\n2o2$\nA.pA!").unwrap();
+        assert_eq!(rle.rule, Some("B36/S23".parse().unwrap()));
+        assert_eq!(rle.pattern.cells(), &[(10, 0), (11, 0), (0, 2), (2, 2)]);
+        let written = Rle::new(rle.pattern.clone(), None).to_text();
+        assert_eq!(written, "x = 12, y = 3\n10b2o2$obo!\n");
+        assert_eq!(Rle::parse(&written).unwrap().pattern, rle.pattern);
+    }
+
+    #[test]
+    fn it_reports_error_position() {
+        assert_eq!(
+            Rle::parse("x = 3, y = 3\nbo$2bz$3o!"),
+            Err("Line 2, column 6: unexpected character 'z'".to_string())
+        );
+        assert_eq!(
+            Rle::parse("x = 2, y = 3\n3o!"),
+            Err("Line 2, column 2: alive cell outside of x = 2, y = 3 box from header".to_string())
+        );
+        assert_eq!(
+            Rle::parse("x = 3, rule = B3/Sx"),
+            Err("Line 1, column 7: Invalid neighbour count 'x'".to_string())
+        );
+        assert!(Rle::parse("x = 3, y = 3\n3o$")
+            .unwrap_err()
+            .contains("terminated"));
+    }
+}
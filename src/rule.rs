@@ -0,0 +1,152 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Life-like rule, bit `n` set means transition happens with `n` alive neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rule {
+    birth: u16,
+    survival: u16,
+}
+
+impl Rule {
+    pub fn new(birth: u16, survival: u16) -> Self {
+        Self {
+            birth: birth & 0x1ff,
+            survival: survival & 0x1ff,
+        }
+    }
+
+    /// B3/S23
+    pub fn conway() -> Self {
+        Self::new(1 << 3, (1 << 2) | (1 << 3))
+    }
+
+    /// Bit `n` set when cell is born with `n` neighbours.
+    pub fn birth(&self) -> u16 {
+        self.birth
+    }
+
+    pub fn survival(&self) -> u16 {
+        self.survival
+    }
+
+    pub fn is_born(&self, neighbours: usize) -> bool {
+        self.birth & (1 << neighbours) != 0
+    }
+
+    pub fn survives(&self, neighbours: usize) -> bool {
+        self.survival & (1 << neighbours) != 0
+    }
+
+    /// Next state of centre cell for every 3x3 neighbourhood packed as 3 bits of row above,
+    /// 3 bits of own row and 3 bits of row below, centre is bit 4. Births without neighbours
+    /// (B0) are ignored.
+    pub fn transition_table(&self) -> [bool; 512] {
+        let mut table = [false; 512];
+        for (index, next) in table.iter_mut().enumerate().skip(1) {
+            let alive = index & (1 << 4) != 0;
+            let neighbours = (index & !(1 << 4)).count_ones() as usize;
+            *next = if alive {
+                self.survives(neighbours)
+            } else {
+                self.is_born(neighbours)
+            };
+        }
+        table
+    }
+
+    fn parse_digits(digits: &str) -> Result<u16, String> {
+        let mut mask = 0;
+        for c in digits.chars() {
+            match c.to_digit(10) {
+                Some(n) if n <= 8 => mask |= 1 << n,
+                _ => return Err(format!("Invalid neighbour count {:?}", c)),
+            }
+        }
+        Ok(mask)
+    }
+
+    fn format_digits(mask: u16) -> String {
+        (0..=8)
+            .filter(|n| mask & (1 << n) != 0)
+            .map(|n| n.to_string())
+            .collect()
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self::conway()
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    /// Accepts `B3/S23` and the older survival first `23/3` notation.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let parts: Vec<&str> = s.split('/').collect();
+        if parts.len() != 2 {
+            return Err(format!("Rule {:?} must have two parts separated by '/'", s));
+        }
+        let (first, second) = (parts[0], parts[1]);
+        let starts_with =
+            |part: &str, c: char| part.starts_with(c) || part.starts_with(c.to_ascii_lowercase());
+        if starts_with(first, 'B') && starts_with(second, 'S') {
+            Ok(Self::new(
+                Self::parse_digits(&first[1..])?,
+                Self::parse_digits(&second[1..])?,
+            ))
+        } else if starts_with(first, 'S') && starts_with(second, 'B') {
+            Ok(Self::new(
+                Self::parse_digits(&second[1..])?,
+                Self::parse_digits(&first[1..])?,
+            ))
+        } else {
+            Ok(Self::new(
+                Self::parse_digits(second)?,
+                Self::parse_digits(first)?,
+            ))
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "B{}/S{}",
+            Self::format_digits(self.birth),
+            Self::format_digits(self.survival)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rule::*;
+
+    #[test]
+    fn it_parses_notations() {
+        assert_eq!("B3/S23".parse::<Rule>(), Ok(Rule::conway()));
+        assert_eq!("b3/s23".parse::<Rule>(), Ok(Rule::conway()));
+        assert_eq!("S23/B3".parse::<Rule>(), Ok(Rule::conway()));
+        assert_eq!("23/3".parse::<Rule>(), Ok(Rule::conway()));
+        assert_eq!(
+            "B36/S23".parse::<Rule>().map(|r| r.to_string()),
+            Ok("B36/S23".to_string())
+        );
+        assert_eq!(
+            "B2/S".parse::<Rule>().map(|r| r.to_string()),
+            Ok("B2/S".to_string())
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_rules() {
+        assert!("B9/S23".parse::<Rule>().is_err());
+        assert!("B3S23".parse::<Rule>().is_err());
+        assert!("Bx/S23".parse::<Rule>().is_err());
+    }
+}
@@ -0,0 +1,528 @@
+use std::ops::Not;
+
+/// Conflicts before first restart, following restarts are scaled by Luby sequence.
+const RESTART_BASE: u64 = 100;
+const ACTIVITY_DECAY: f64 = 0.95;
+const ACTIVITY_LIMIT: f64 = 1e100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Var(u32);
+
+impl Var {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn positive(self) -> Lit {
+        Lit(self.0 << 1)
+    }
+
+    pub fn negative(self) -> Lit {
+        Lit((self.0 << 1) | 1)
+    }
+
+    /// Literal which is true when variable has given value.
+    pub fn lit(self, value: bool) -> Lit {
+        if value {
+            self.positive()
+        } else {
+            self.negative()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Lit(u32);
+
+impl Lit {
+    pub fn var(self) -> Var {
+        Var(self.0 >> 1)
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 & 1 != 0
+    }
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Not for Lit {
+    type Output = Lit;
+
+    fn not(self) -> Lit {
+        Lit(self.0 ^ 1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolveResult {
+    /// Value of every variable.
+    Satisfiable(Vec<bool>),
+    Unsatisfiable,
+    /// Conflict limit was reached first.
+    Unknown,
+}
+
+/// Conflict driven clause learning solver with two watched literals, first UIP learning,
+/// activity based decisions, phase saving and Luby restarts.
+pub struct Solver {
+    clauses: Vec<Vec<Lit>>,
+    /// Clauses watching literal, visited when it becomes false.
+    watches: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    phases: Vec<bool>,
+    activity: Vec<f64>,
+    activity_increment: f64,
+    heap: VarHeap,
+    trail: Vec<Lit>,
+    trail_limits: Vec<usize>,
+    propagated: usize,
+    inconsistent: bool,
+    conflict_limit: Option<u64>,
+    conflicts: u64,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self {
+            clauses: Vec::new(),
+            watches: Vec::new(),
+            values: Vec::new(),
+            levels: Vec::new(),
+            reasons: Vec::new(),
+            phases: Vec::new(),
+            activity: Vec::new(),
+            activity_increment: 1.0,
+            heap: VarHeap::default(),
+            trail: Vec::new(),
+            trail_limits: Vec::new(),
+            propagated: 0,
+            inconsistent: false,
+            conflict_limit: None,
+            conflicts: 0,
+        }
+    }
+
+    /// Gives up with `SolveResult::Unknown` after this many conflicts.
+    pub fn with_conflict_limit(mut self, limit: u64) -> Self {
+        self.conflict_limit = Some(limit);
+        self
+    }
+
+    pub fn var_count(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn new_var(&mut self) -> Var {
+        let var = Var(self.values.len() as u32);
+        self.values.push(None);
+        self.levels.push(0);
+        self.reasons.push(None);
+        self.phases.push(false);
+        self.activity.push(0.0);
+        self.watches.push(Vec::new());
+        self.watches.push(Vec::new());
+        self.heap.insert(var.index(), &self.activity);
+        var
+    }
+
+    pub fn add_clause(&mut self, literals: &[Lit]) {
+        if self.inconsistent {
+            return;
+        }
+        let mut clause: Vec<Lit> = Vec::new();
+        for &lit in literals {
+            if clause.contains(&!lit) || self.value(lit) == Some(true) {
+                return;
+            }
+            if !clause.contains(&lit) && self.value(lit) != Some(false) {
+                clause.push(lit);
+            }
+        }
+        match clause.len() {
+            0 => self.inconsistent = true,
+            1 => {
+                self.assign(clause[0], None);
+                if self.propagate().is_some() {
+                    self.inconsistent = true;
+                }
+            }
+            _ => {
+                self.attach(clause);
+            }
+        }
+    }
+
+    fn attach(&mut self, clause: Vec<Lit>) -> usize {
+        let id = self.clauses.len();
+        self.watches[clause[0].index()].push(id);
+        self.watches[clause[1].index()].push(id);
+        self.clauses.push(clause);
+        id
+    }
+
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.values[lit.var().index()].map(|value| value != lit.is_negative())
+    }
+
+    fn level(&self) -> usize {
+        self.trail_limits.len()
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = lit.var().index();
+        self.values[var] = Some(!lit.is_negative());
+        self.levels[var] = self.level();
+        self.reasons[var] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Unit propagation, returns conflicting clause.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let falsified = !self.trail[self.propagated];
+            self.propagated += 1;
+            let watching = std::mem::take(&mut self.watches[falsified.index()]);
+            let mut kept = Vec::with_capacity(watching.len());
+            let mut conflict = None;
+            for (position, &id) in watching.iter().enumerate() {
+                if conflict.is_some() {
+                    kept.extend_from_slice(&watching[position..]);
+                    break;
+                }
+                let values = &self.values;
+                let value = |lit: Lit| values[lit.var().index()].map(|v| v != lit.is_negative());
+                let clause = &mut self.clauses[id];
+                if clause[0] == falsified {
+                    clause.swap(0, 1);
+                }
+                let first = clause[0];
+                if value(first) == Some(true) {
+                    kept.push(id);
+                    continue;
+                }
+                let replacement = (2..clause.len()).find(|&i| value(clause[i]) != Some(false));
+                if let Some(i) = replacement {
+                    clause.swap(1, i);
+                    let watched = clause[1];
+                    self.watches[watched.index()].push(id);
+                    continue;
+                }
+                kept.push(id);
+                if self.value(first) == Some(false) {
+                    conflict = Some(id);
+                } else {
+                    self.assign(first, Some(id));
+                }
+            }
+            self.watches[falsified.index()] = kept;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// First UIP clause learnt from conflict with level to jump back to.
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let mut seen = vec![false; self.var_count()];
+        let mut learnt = vec![Lit(0)];
+        let mut pending = 0;
+        let mut clause = conflict;
+        let mut index = self.trail.len();
+        let mut uip = None;
+        loop {
+            let start = if uip.is_some() { 1 } else { 0 };
+            for i in start..self.clauses[clause].len() {
+                let lit = self.clauses[clause][i];
+                let var = lit.var().index();
+                if seen[var] || self.levels[var] == 0 {
+                    continue;
+                }
+                seen[var] = true;
+                self.bump(var);
+                if self.levels[var] == self.level() {
+                    pending += 1;
+                } else {
+                    learnt.push(lit);
+                }
+            }
+            loop {
+                index -= 1;
+                if seen[self.trail[index].var().index()] {
+                    break;
+                }
+            }
+            let lit = self.trail[index];
+            seen[lit.var().index()] = false;
+            pending -= 1;
+            uip = Some(lit);
+            if pending == 0 {
+                break;
+            }
+            clause = self.reasons[lit.var().index()].unwrap();
+        }
+        learnt[0] = !uip.unwrap();
+        // Second watched literal must be the one assigned last
+        let mut level = 0;
+        for i in 1..learnt.len() {
+            let var_level = self.levels[learnt[i].var().index()];
+            if var_level > level {
+                level = var_level;
+                learnt.swap(1, i);
+            }
+        }
+        self.decay();
+        (learnt, level)
+    }
+
+    fn bump(&mut self, var: usize) {
+        self.activity[var] += self.activity_increment;
+        if self.activity[var] > ACTIVITY_LIMIT {
+            for activity in self.activity.iter_mut() {
+                *activity /= ACTIVITY_LIMIT;
+            }
+            self.activity_increment /= ACTIVITY_LIMIT;
+        }
+        self.heap.increased(var, &self.activity);
+    }
+
+    fn decay(&mut self) {
+        self.activity_increment /= ACTIVITY_DECAY;
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.level() <= level {
+            return;
+        }
+        let limit = self.trail_limits[level];
+        for lit in self.trail.drain(limit..).rev() {
+            let var = lit.var().index();
+            self.phases[var] = !lit.is_negative();
+            self.values[var] = None;
+            self.reasons[var] = None;
+            self.heap.insert(var, &self.activity);
+        }
+        self.trail_limits.truncate(level);
+        self.propagated = self.trail.len();
+    }
+
+    fn decide(&mut self) -> Option<Lit> {
+        while let Some(var) = self.heap.pop(&self.activity) {
+            if self.values[var].is_none() {
+                return Some(Var(var as u32).lit(self.phases[var]));
+            }
+        }
+        None
+    }
+
+    pub fn solve(&mut self) -> SolveResult {
+        if self.inconsistent || self.propagate().is_some() {
+            self.inconsistent = true;
+            return SolveResult::Unsatisfiable;
+        }
+        let mut restart = 1;
+        let mut conflicts_until_restart = RESTART_BASE;
+        loop {
+            if let Some(conflict) = self.propagate() {
+                self.conflicts += 1;
+                if self.level() == 0 {
+                    self.inconsistent = true;
+                    return SolveResult::Unsatisfiable;
+                }
+                if self.conflict_limit.is_some_and(|limit| self.conflicts >= limit) {
+                    self.backtrack(0);
+                    return SolveResult::Unknown;
+                }
+                let (learnt, level) = self.analyze(conflict);
+                self.backtrack(level);
+                if learnt.len() == 1 {
+                    self.assign(learnt[0], None);
+                } else {
+                    let asserting = learnt[0];
+                    let id = self.attach(learnt);
+                    self.assign(asserting, Some(id));
+                }
+                conflicts_until_restart -= 1;
+                if conflicts_until_restart == 0 {
+                    restart += 1;
+                    conflicts_until_restart = RESTART_BASE * luby(restart);
+                    self.backtrack(0);
+                }
+                continue;
+            }
+            match self.decide() {
+                Some(lit) => {
+                    self.trail_limits.push(self.trail.len());
+                    self.assign(lit, None);
+                }
+                None => {
+                    let model = self.values.iter().map(|value| *value == Some(true)).collect();
+                    self.backtrack(0);
+                    return SolveResult::Satisfiable(model);
+                }
+            }
+        }
+    }
+}
+
+/// `i`-th element of 1, 1, 2, 1, 1, 2, 4, 1, ... counted from 1.
+fn luby(i: u64) -> u64 {
+    let mut x = i - 1;
+    let mut size = 1;
+    let mut exponent = 0;
+    while size < x + 1 {
+        exponent += 1;
+        size = 2 * size + 1;
+    }
+    while size - 1 != x {
+        size = (size - 1) / 2;
+        exponent -= 1;
+        x %= size;
+    }
+    1 << exponent
+}
+
+/// Binary max heap of variables ordered by activity.
+#[derive(Debug, Default)]
+struct VarHeap {
+    heap: Vec<usize>,
+    positions: Vec<Option<usize>>,
+}
+
+impl VarHeap {
+    fn insert(&mut self, var: usize, activity: &[f64]) {
+        if self.positions.len() <= var {
+            self.positions.resize(var + 1, None);
+        }
+        if self.positions[var].is_some() {
+            return;
+        }
+        self.heap.push(var);
+        self.positions[var] = Some(self.heap.len() - 1);
+        self.up(self.heap.len() - 1, activity);
+    }
+
+    fn increased(&mut self, var: usize, activity: &[f64]) {
+        if let Some(position) = self.positions[var] {
+            self.up(position, activity);
+        }
+    }
+
+    fn pop(&mut self, activity: &[f64]) -> Option<usize> {
+        let top = *self.heap.first()?;
+        let last = self.heap.pop().unwrap();
+        self.positions[top] = None;
+        if !self.heap.is_empty() {
+            self.heap[0] = last;
+            self.positions[last] = Some(0);
+            self.down(0, activity);
+        }
+        Some(top)
+    }
+
+    fn up(&mut self, mut position: usize, activity: &[f64]) {
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            if activity[self.heap[parent]] >= activity[self.heap[position]] {
+                break;
+            }
+            self.swap(position, parent);
+            position = parent;
+        }
+    }
+
+    fn down(&mut self, mut position: usize, activity: &[f64]) {
+        loop {
+            let mut largest = position;
+            for child in &[position * 2 + 1, position * 2 + 2] {
+                if *child < self.heap.len() && activity[self.heap[*child]] > activity[self.heap[largest]]
+                {
+                    largest = *child;
+                }
+            }
+            if largest == position {
+                break;
+            }
+            self.swap(position, largest);
+            position = largest;
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.positions[self.heap[a]] = Some(a);
+        self.positions[self.heap[b]] = Some(b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sat::*;
+
+    fn satisfies(clauses: &[Vec<Lit>], model: &[bool]) -> bool {
+        clauses.iter().all(|clause| {
+            clause
+                .iter()
+                .any(|lit| model[lit.var().index()] != lit.is_negative())
+        })
+    }
+
+    #[test]
+    fn it_solves_satisfiable_formula() {
+        let mut solver = Solver::new();
+        let vars: Vec<Var> = (0..4).map(|_| solver.new_var()).collect();
+        let clauses = vec![
+            vec![vars[0].positive(), vars[1].positive()],
+            vec![vars[0].negative(), vars[2].positive()],
+            vec![vars[1].negative(), vars[2].negative(), vars[3].positive()],
+            vec![vars[2].negative(), vars[3].negative()],
+            vec![vars[3].positive(), vars[0].negative()],
+        ];
+        for clause in &clauses {
+            solver.add_clause(clause);
+        }
+        match solver.solve() {
+            SolveResult::Satisfiable(model) => assert!(satisfies(&clauses, &model)),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn it_proves_pigeonhole_unsatisfiable() {
+        // 5 pigeons don't fit in 4 holes
+        let mut solver = Solver::new();
+        let holes = 4;
+        let pigeons: Vec<Vec<Var>> = (0..=holes)
+            .map(|_| (0..holes).map(|_| solver.new_var()).collect())
+            .collect();
+        for pigeon in &pigeons {
+            let clause: Vec<Lit> = pigeon.iter().map(|var| var.positive()).collect();
+            solver.add_clause(&clause);
+        }
+        for hole in 0..holes {
+            for a in 0..pigeons.len() {
+                for b in a + 1..pigeons.len() {
+                    solver.add_clause(&[pigeons[a][hole].negative(), pigeons[b][hole].negative()]);
+                }
+            }
+        }
+        assert_eq!(solver.solve(), SolveResult::Unsatisfiable);
+    }
+
+    #[test]
+    fn it_counts_luby_sequence() {
+        let sequence: Vec<u64> = (1..=15).map(luby).collect();
+        assert_eq!(sequence, vec![1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+    }
+}
@@ -0,0 +1,277 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use sdl2::image::SaveSurface;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::surface::Surface;
+
+use crate::cell_state::{CellState, GRID_COLOR};
+use crate::map::Map;
+
+pub const DEFAULT_POSTER_SCALE: u32 = 4;
+/// Cells smaller than this have no room for grid lines.
+const MIN_GRID_SCALE: u32 = 3;
+/// Pixels are read back and saved as bytes in R, G, B, A order.
+pub const PIXEL_FORMAT: PixelFormatEnum = PixelFormatEnum::ABGR8888;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    /// Colours of the window, taken from `CellState::color`.
+    Light,
+    Dark,
+    /// Black cells on white without dead cell shading.
+    Print,
+}
+
+pub const THEMES: [Theme; 3] = [Theme::Light, Theme::Dark, Theme::Print];
+
+impl Theme {
+    pub fn background(&self) -> Color {
+        match self {
+            Theme::Light | Theme::Print => Color::RGBA(255, 255, 255, 255),
+            Theme::Dark => Color::RGBA(15, 15, 20, 255),
+        }
+    }
+
+    pub fn cell(&self, state: CellState) -> Color {
+        match (self, state) {
+            (Theme::Light, state) => state.color(),
+            (Theme::Dark, CellState::Alive) => Color::RGBA(120, 230, 140, 255),
+            (Theme::Dark, CellState::Dead) => Color::RGBA(35, 35, 45, 255),
+            (Theme::Print, CellState::Alive) => Color::RGBA(0, 0, 0, 255),
+            (Theme::Print, CellState::Dead) => Color::RGBA(255, 255, 255, 255),
+        }
+    }
+
+    pub fn grid(&self) -> Color {
+        match self {
+            Theme::Light => GRID_COLOR,
+            Theme::Dark => Color::RGBA(60, 60, 75, 255),
+            Theme::Print => Color::RGBA(210, 210, 210, 255),
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Theme::Light => write!(f, "light"),
+            Theme::Dark => write!(f, "dark"),
+            Theme::Print => write!(f, "print"),
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        THEMES
+            .iter()
+            .find(|theme| theme.to_string() == s.to_lowercase())
+            .cloned()
+            .ok_or_else(|| format!("Unknown theme {:?}, expected light, dark or print", s))
+    }
+}
+
+/// Colours of `Poster::palette`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteIndex {
+    Background,
+    Dead,
+    Alive,
+    Grid,
+}
+
+/// Whole board drawn at any scale onto offscreen surface, independent of the window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Poster {
+    /// Pixels per cell.
+    pub scale: u32,
+    pub grid: bool,
+    pub theme: Theme,
+    /// Background and dead cells are left fully transparent.
+    pub transparent: bool,
+}
+
+impl Poster {
+    pub fn new(scale: u32) -> Self {
+        Self {
+            scale: scale.max(1),
+            grid: false,
+            theme: Theme::Light,
+            transparent: false,
+        }
+    }
+
+    pub fn with_grid(mut self, grid: bool) -> Self {
+        self.grid = grid;
+        self
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn with_transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    /// Grid is drawn only when asked for and cells leave room for it.
+    pub fn has_grid(&self) -> bool {
+        self.grid && self.scale >= MIN_GRID_SCALE
+    }
+
+    /// Image size of `map`, grid adds closing line on the right and bottom.
+    pub fn size(&self, map: &Map) -> (u32, u32) {
+        let border = self.has_grid() as u32;
+        (
+            map.width() as u32 * self.scale + border,
+            map.height() as u32 * self.scale + border,
+        )
+    }
+
+    /// Area of cell, grid lines take the first row and column of its pixels.
+    pub fn cell_rect(&self, x: i32, y: i32) -> Rect {
+        let scale = self.scale as i32;
+        let line = self.has_grid() as i32;
+        Rect::new(
+            x * scale + line,
+            y * scale + line,
+            (scale - line) as u32,
+            (scale - line) as u32,
+        )
+    }
+
+    /// Vertical and horizontal lines between and around cells.
+    pub fn grid_lines(&self, map: &Map) -> Vec<Rect> {
+        if !self.has_grid() {
+            return Vec::new();
+        }
+        let (width, height) = self.size(map);
+        let scale = self.scale as i32;
+        let mut lines: Vec<Rect> = (0..=map.width())
+            .map(|x| Rect::new(x * scale, 0, 1, height))
+            .collect();
+        lines.extend((0..=map.height()).map(|y| Rect::new(0, y * scale, width, 1)));
+        lines
+    }
+
+    /// Background, dead cell, alive cell and grid colours in order of `PaletteIndex`.
+    pub fn palette(&self) -> Vec<[u8; 3]> {
+        let colors = [
+            self.theme.background(),
+            self.theme.cell(CellState::Dead),
+            self.theme.cell(CellState::Alive),
+            self.theme.grid(),
+        ];
+        colors.iter().map(|color| [color.r, color.g, color.b]).collect()
+    }
+
+    /// Row by row palette indices of poster, for image formats written without SDL.
+    pub fn indexed(&self, map: &Map) -> Vec<u8> {
+        let (width, height) = self.size(map);
+        let mut pixels = vec![PaletteIndex::Background as u8; (width * height) as usize];
+        let mut fill = |rect: Rect, index: PaletteIndex| {
+            for y in rect.top()..rect.bottom() {
+                let row = y as usize * width as usize;
+                pixels[row + rect.left() as usize..row + rect.right() as usize]
+                    .fill(index as u8);
+            }
+        };
+        for y in 0..map.height() {
+            for x in 0..map.width() {
+                let index = match map.get_at(x, y) {
+                    CellState::Dead if self.transparent => continue,
+                    CellState::Dead => PaletteIndex::Dead,
+                    CellState::Alive => PaletteIndex::Alive,
+                };
+                fill(self.cell_rect(x, y), index);
+            }
+        }
+        for line in self.grid_lines(map) {
+            fill(line, PaletteIndex::Grid);
+        }
+        pixels
+    }
+
+    /// Drawn on a software surface rather than through `Render`, which needs the window canvas
+    /// and draws the window layout, as posters are saved by headless runs too.
+    pub fn render(&self, map: &Map) -> Result<Surface<'static>, String> {
+        let (width, height) = self.size(map);
+        let mut surface = Surface::new(width, height, PIXEL_FORMAT)?;
+        let clear = Color::RGBA(0, 0, 0, 0);
+        let background = if self.transparent {
+            clear
+        } else {
+            self.theme.background()
+        };
+        surface.fill_rect(None, background)?;
+        for y in 0..map.height() {
+            for x in 0..map.width() {
+                let state = map.get_at(x, y);
+                let color = match state {
+                    CellState::Dead if self.transparent => continue,
+                    state => self.theme.cell(state),
+                };
+                surface.fill_rect(self.cell_rect(x, y), color)?;
+            }
+        }
+        for line in self.grid_lines(map) {
+            surface.fill_rect(line, self.theme.grid())?;
+        }
+        Ok(surface)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, map: &Map, path: P) -> Result<(), String> {
+        self.render(map)?
+            .save(path.as_ref())
+            .map_err(|e| format!("Can't write {}: {}", path.as_ref().display(), e))
+    }
+}
+
+/// Writes pixels read back in `PIXEL_FORMAT` as PNG.
+pub fn save_png<P: AsRef<Path>>(
+    pixels: &mut [u8],
+    width: u32,
+    height: u32,
+    path: P,
+) -> Result<(), String> {
+    Surface::from_data(pixels, width, height, width * 4, PIXEL_FORMAT)?
+        .save(path.as_ref())
+        .map_err(|e| format!("Can't write {}: {}", path.as_ref().display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::screenshot::*;
+
+    #[test]
+    fn it_lays_out_poster() {
+        let map = Map::new();
+        let poster = Poster::new(4);
+        assert_eq!(poster.size(&map), (80, 80));
+        assert_eq!(poster.cell_rect(2, 3), Rect::new(8, 12, 4, 4));
+        assert!(poster.grid_lines(&map).is_empty());
+        let poster = poster.with_grid(true);
+        assert_eq!(poster.size(&map), (81, 81));
+        assert_eq!(poster.cell_rect(2, 3), Rect::new(9, 13, 3, 3));
+        assert_eq!(poster.grid_lines(&map).len(), 42);
+        // No room for lines between single pixel cells
+        assert_eq!(Poster::new(1).with_grid(true).size(&map), (20, 20));
+        assert_eq!("Dark".parse(), Ok(Theme::Dark));
+        // Light posters look like the window
+        assert_eq!(Theme::Light.cell(CellState::Alive), CellState::Alive.color());
+        assert_eq!(Theme::Light.grid(), GRID_COLOR);
+        let mut map = Map::new();
+        map.set_alive(1, 0);
+        let pixels = Poster::new(3).with_grid(true).indexed(&map);
+        assert_eq!(pixels.len(), 61 * 61);
+        // Grid line, dead cell, grid line, alive cell and grid line across the first row of cells
+        assert_eq!(&pixels[61..61 + 8], &[3, 1, 1, 3, 2, 2, 3, 1]);
+    }
+}
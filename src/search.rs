@@ -0,0 +1,445 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::census::{classify, known_objects, Census, Classification};
+use crate::map::{Map, Topology};
+use crate::parallel::parallel_map;
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+use crate::simulation::Simulation;
+use crate::soup::Soup;
+use crate::stability::OnStable;
+
+pub const DEFAULT_SEARCH_FILE: &str = "search.txt";
+pub const DEFAULT_METHUSELAH: u64 = 200;
+/// Objects seen at most this many times are reported as rare.
+pub const RARE_COUNT: usize = 2;
+/// Soups evaluated between saves of search file.
+const BATCH_SIZE: u64 = 200;
+const FILE_HEADER: &str = "# game_of_life soup search";
+
+/// Everything that decides search results, resumed search must use the same settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchSettings {
+    pub rule: Rule,
+    pub topology: Topology,
+    /// Soup of the first search, following ones use consecutive seeds.
+    pub soup: Soup,
+    pub generations: u64,
+    pub census_distance: i32,
+    pub methuselah: u64,
+}
+
+impl SearchSettings {
+    fn to_text(&self) -> String {
+        format!(
+            "rule {}\ntopology {}\ndensity {}\nsymmetry {}\nsize {} {}\nfirst_seed {}\ngenerations {}\ncensus_distance {}\nmethuselah {}\n",
+            self.rule,
+            self.topology,
+            self.soup.density,
+            self.soup.symmetry,
+            self.soup.width,
+            self.soup.height,
+            self.soup.seed,
+            self.generations,
+            self.census_distance,
+            self.methuselah
+        )
+    }
+}
+
+/// Outcome of single soup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoupResult {
+    pub seed: u64,
+    /// Generation board settled at, `None` when it didn't within search limit.
+    pub lifespan: Option<u64>,
+    pub census: Census,
+}
+
+/// Evolves soup with given seed until it settles and takes census of what is left.
+pub fn evaluate(settings: &SearchSettings, seed: u64) -> SoupResult {
+    let mut map = Map::new();
+    map.set_topology(settings.topology);
+    let mut simulation = Simulation::new(map)
+        .with_rule(settings.rule)
+        .with_on_stable(OnStable::Pause)
+        .with_census_distance(settings.census_distance)
+        .with_soup(settings.soup.with_seed(seed));
+    while simulation.generation() < settings.generations && !simulation.stability().is_settled()
+    {
+        simulation.next_generation();
+    }
+    SoupResult {
+        seed,
+        lifespan: simulation.stability().since(),
+        census: simulation.census(),
+    }
+}
+
+/// Object found in any soup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchEntry {
+    pub name: Option<&'static str>,
+    pub classification: Classification,
+    pub key: Pattern,
+    pub count: usize,
+    /// Soup the object was found in first, enough to reproduce it.
+    pub first_seed: u64,
+}
+
+impl SearchEntry {
+    pub fn label(&self) -> String {
+        match self.name {
+            Some(name) => name.to_string(),
+            None => format!("{} {} cells", self.classification, self.key.population()),
+        }
+    }
+}
+
+/// Aggregated results of all soups searched so far.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchState {
+    pub settings: SearchSettings,
+    pub soups: u64,
+    pub entries: Vec<SearchEntry>,
+    /// Seeds of soups living at least `methuselah` generations with their lifespan.
+    pub methuselahs: Vec<(u64, Option<u64>)>,
+}
+
+impl SearchState {
+    pub fn new(settings: SearchSettings) -> Self {
+        Self {
+            settings,
+            soups: 0,
+            entries: Vec::new(),
+            methuselahs: Vec::new(),
+        }
+    }
+
+    pub fn next_seed(&self) -> u64 {
+        self.settings.soup.seed + self.soups
+    }
+
+    pub fn merge(&mut self, result: &SoupResult) {
+        self.soups += 1;
+        for found in &result.census.entries {
+            match self.entries.iter_mut().find(|entry| entry.key == found.key) {
+                Some(entry) => entry.count += found.count,
+                None => self.entries.push(SearchEntry {
+                    name: found.name,
+                    classification: found.classification,
+                    key: found.key.clone(),
+                    count: found.count,
+                    first_seed: result.seed,
+                }),
+            }
+        }
+        if result.lifespan.is_none_or(|lifespan| lifespan >= self.settings.methuselah) {
+            self.methuselahs.push((result.seed, result.lifespan));
+        }
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        self.entries.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then(a.classification.cmp(&b.classification))
+                .then(a.key.cmp(&b.key))
+        });
+        self.methuselahs.sort_by(|a, b| {
+            let lifespan = |l: Option<u64>| l.unwrap_or(u64::MAX);
+            lifespan(b.1).cmp(&lifespan(a.1)).then(a.0.cmp(&b.0))
+        });
+    }
+
+    pub fn rare(&self) -> Vec<&SearchEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.count <= RARE_COUNT)
+            .collect()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\n{}", FILE_HEADER, self.settings.to_text());
+        text.push_str(&format!("soups {}\n", self.soups));
+        for entry in &self.entries {
+            text.push_str(&format!(
+                "object {} {} {}\n",
+                entry.count,
+                entry.first_seed,
+                entry.key.to_rows()
+            ));
+        }
+        for (seed, lifespan) in &self.methuselahs {
+            match lifespan {
+                Some(lifespan) => text.push_str(&format!("long_lived {} {}\n", seed, lifespan)),
+                None => text.push_str(&format!("long_lived {} unsettled\n", seed)),
+            }
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut values: HashMap<&str, &str> = HashMap::new();
+        let mut objects = Vec::new();
+        let mut methuselahs = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.find(' ') {
+                Some(index) => (&line[..index], line[index + 1..].trim()),
+                None => return Err(format!("Line {}: missing value in {:?}", number + 1, line)),
+            };
+            let error = |e: String| format!("Line {}: {}", number + 1, e);
+            match key {
+                "object" => {
+                    let parts: Vec<&str> = value.split(' ').collect();
+                    if parts.len() != 3 {
+                        return Err(error(format!("invalid object {:?}", value)));
+                    }
+                    let count = parse_field(parts[0]).map_err(error)?;
+                    let seed = parse_field(parts[1]).map_err(error)?;
+                    objects.push((count, seed, Pattern::from_rows(parts[2])));
+                }
+                "long_lived" => {
+                    let parts: Vec<&str> = value.split(' ').collect();
+                    if parts.len() != 2 {
+                        return Err(error(format!("invalid long lived soup {:?}", value)));
+                    }
+                    let seed = parse_field(parts[0]).map_err(error)?;
+                    let lifespan = match parts[1] {
+                        "unsettled" => None,
+                        lifespan => Some(parse_field(lifespan).map_err(error)?),
+                    };
+                    methuselahs.push((seed, lifespan));
+                }
+                _ => {
+                    values.insert(key, value);
+                }
+            }
+        }
+        let value = |key: &str| {
+            values
+                .get(key)
+                .cloned()
+                .ok_or_else(|| format!("Missing {} in search file", key))
+        };
+        let size: Vec<&str> = value("size")?.split(' ').collect();
+        if size.len() != 2 {
+            return Err(format!("Invalid size {:?}", value("size")?));
+        }
+        let rule: Rule = parse_field(value("rule")?)?;
+        let settings = SearchSettings {
+            rule,
+            topology: parse_field(value("topology")?)?,
+            soup: Soup::new(
+                parse_field(value("first_seed")?)?,
+                parse_field(value("density")?)?,
+                parse_field(size[0])?,
+                parse_field(size[1])?,
+                parse_field(value("symmetry")?)?,
+            ),
+            generations: parse_field(value("generations")?)?,
+            census_distance: parse_field(value("census_distance")?)?,
+            methuselah: parse_field(value("methuselah")?)?,
+        };
+        let known = known_objects(&rule);
+        let entries = objects
+            .into_iter()
+            .map(|(count, first_seed, key)| {
+                let classification = classify(&key, &rule).0;
+                SearchEntry {
+                    name: known
+                        .iter()
+                        .find(|(known_key, _)| *known_key == key)
+                        .map(|(_, name)| *name),
+                    classification,
+                    key,
+                    count,
+                    first_seed,
+                }
+            })
+            .collect();
+        let mut state = Self {
+            settings,
+            soups: parse_field(value("soups")?)?,
+            entries,
+            methuselahs,
+        };
+        state.sort();
+        Ok(state)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let text = fs::read_to_string(path.as_ref())
+            .map_err(|e| format!("Can't read {}: {}", path.as_ref().display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.as_ref().display(), e))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        // Written aside first so interrupted save doesn't lose previous results
+        let temporary = path.as_ref().with_extension("tmp");
+        fs::write(&temporary, self.to_text())
+            .and_then(|_| fs::rename(&temporary, path.as_ref()))
+            .map_err(|e| format!("Can't write {}: {}", path.as_ref().display(), e))
+    }
+
+    pub fn report(&self) -> String {
+        let mut text = format!(
+            "Searched {} soups, rule {}, {} board, soups {}x{} {} density {}\n",
+            self.soups,
+            self.settings.rule,
+            self.settings.topology,
+            self.settings.soup.width,
+            self.settings.soup.height,
+            self.settings.soup.symmetry,
+            self.settings.soup.density
+        );
+        text.push_str("Objects:\n");
+        for entry in &self.entries {
+            text.push_str(&format!(
+                "  {} {} - {}, {} cells\n",
+                entry.count,
+                entry.name.unwrap_or("unnamed"),
+                entry.classification,
+                entry.key.population()
+            ));
+        }
+        text.push_str(&format!("Rare objects (seen at most {} times):\n", RARE_COUNT));
+        for entry in self.rare() {
+            text.push_str(&format!(
+                "  {} in soup {}: {}\n",
+                entry.label(),
+                entry.first_seed,
+                entry.key.to_rows()
+            ));
+        }
+        text.push_str(&format!(
+            "Long-lived soups (at least {} generations):\n",
+            self.settings.methuselah
+        ));
+        for (seed, lifespan) in &self.methuselahs {
+            match lifespan {
+                Some(lifespan) => {
+                    text.push_str(&format!("  soup {} settled at {}\n", seed, lifespan))
+                }
+                None => text.push_str(&format!(
+                    "  soup {} still evolving after {}\n",
+                    seed, self.settings.generations
+                )),
+            }
+        }
+        text
+    }
+}
+
+fn parse_field<T>(value: &str) -> Result<T, String>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e| format!("invalid value {:?}: {}", value, e))
+}
+
+/// Searches until `soups` soups are done, continuing from results saved in `path`.
+/// Results are saved after every batch so interrupted search loses at most one batch.
+pub fn run(
+    settings: SearchSettings,
+    soups: u64,
+    threads: usize,
+    path: &str,
+) -> Result<SearchState, String> {
+    let mut state = if Path::new(path).exists() {
+        let state = SearchState::load(path)?;
+        if state.settings != settings {
+            return Err(format!(
+                "{} holds search with different settings:\n{}",
+                path,
+                state.settings.to_text()
+            ));
+        }
+        println!("Resuming search after {} soups", state.soups);
+        state
+    } else {
+        SearchState::new(settings)
+    };
+    let threads = threads.max(1);
+    while state.soups < soups {
+        let first = state.next_seed();
+        let count = BATCH_SIZE.min(soups - state.soups);
+        let results = evaluate_batch(&state.settings, first, count, threads);
+        for result in &results {
+            state.merge(result);
+        }
+        state.save(path)?;
+        println!(
+            "{} / {} soups, {} distinct objects",
+            state.soups,
+            soups,
+            state.entries.len()
+        );
+    }
+    Ok(state)
+}
+
+/// Soups with seeds `first..first + count` spread over worker threads, ordered by seed.
+fn evaluate_batch(
+    settings: &SearchSettings,
+    first: u64,
+    count: u64,
+    threads: usize,
+) -> Vec<SoupResult> {
+    let seeds: Vec<u64> = (first..first + count).collect();
+    parallel_map(&seeds, threads, |seed| evaluate(settings, *seed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search::*;
+    use crate::soup::Symmetry;
+
+    fn settings() -> SearchSettings {
+        SearchSettings {
+            rule: Rule::conway(),
+            topology: Topology::Torus,
+            soup: Soup::new(100, 0.4, 12, 12, Symmetry::C1),
+            generations: 500,
+            census_distance: 2,
+            methuselah: 50,
+        }
+    }
+
+    #[test]
+    fn it_saves_and_parses_results() {
+        let mut state = SearchState::new(settings());
+        for seed in 100..104 {
+            state.merge(&evaluate(&settings(), seed));
+        }
+        assert_eq!(state.soups, 4);
+        assert_eq!(SearchState::parse(&state.to_text()), Ok(state));
+    }
+
+    #[test]
+    fn it_resumes_search() {
+        let directory = std::env::temp_dir();
+        let resumed = directory.join(format!("gol_search_resumed_{}.txt", std::process::id()));
+        let single = directory.join(format!("gol_search_single_{}.txt", std::process::id()));
+        let (resumed, single) = (resumed.to_str().unwrap(), single.to_str().unwrap());
+        run(settings(), 3, 2, resumed).unwrap();
+        let state = run(settings(), 6, 3, resumed).unwrap();
+        assert_eq!(state, run(settings(), 6, 1, single).unwrap());
+        assert_eq!(state.soups, 6);
+        let mut other = settings();
+        other.soup.density = 0.3;
+        assert!(run(other, 6, 1, resumed).is_err());
+        fs::remove_file(resumed).unwrap();
+        fs::remove_file(single).unwrap();
+    }
+}
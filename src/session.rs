@@ -0,0 +1,389 @@
+use std::fs;
+use std::path::Path;
+
+use crate::app_state::AppStatus;
+use crate::map::{Map, Topology};
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+use crate::screenshot::{Poster, DEFAULT_POSTER_SCALE};
+use crate::simulation::Snapshot;
+use crate::soup::Soup;
+use crate::speed::{Speed, DEFAULT_SPEED, SPEEDS};
+use crate::stability::OnStable;
+use crate::svg::Svg;
+
+const FILE_HEADER: &str = "# game_of_life session";
+/// Version written by this build, older files are migrated when loaded.
+pub const SESSION_VERSION: u32 = 1;
+
+/// `key value` line of session file with its line number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub line: usize,
+    pub key: String,
+    pub value: String,
+}
+
+/// Upgrades fields of version `n` to version `n + 1`, index 0 upgrades version 1. Keys added
+/// later don't need one, missing keys keep their default; renamed or reinterpreted ones do.
+const MIGRATIONS: &[fn(&mut Vec<Field>)] = &[];
+
+/// Applies every migration from `version` on, so fields end up in the latest version.
+fn migrate(fields: &mut Vec<Field>, version: u32, migrations: &[fn(&mut Vec<Field>)]) {
+    for migration in &migrations[version as usize - 1..] {
+        migration(fields);
+    }
+}
+
+/// Everything needed to continue simulation where it was saved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub rule: Rule,
+    pub topology: Topology,
+    pub generation: u64,
+    pub status: AppStatus,
+    pub speed: Speed,
+    pub on_stable: OnStable,
+    /// Soup the board was filled with, reseeding continues with its settings.
+    pub soup: Option<Soup>,
+    /// Board size when saved, cells beyond current board are dropped on load.
+    pub width: i32,
+    pub height: i32,
+    /// Alive cells at their board coordinates.
+    pub cells: Pattern,
+    pub show_census: bool,
+    pub show_rules: bool,
+    /// Settings of posters and SVGs, SVG keeps the poster ones.
+    pub svg: Svg,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        let map = Map::new();
+        Self {
+            rule: Rule::conway(),
+            topology: Topology::Bounded,
+            generation: 0,
+            status: AppStatus::Paused,
+            speed: SPEEDS[DEFAULT_SPEED],
+            on_stable: OnStable::Continue,
+            soup: None,
+            width: map.width(),
+            height: map.height(),
+            cells: Pattern::default(),
+            show_census: false,
+            show_rules: false,
+            svg: Svg::new(Poster::new(DEFAULT_POSTER_SCALE)),
+        }
+    }
+}
+
+impl Session {
+    /// Simulation state of `snapshot`, panels are left hidden and image settings default.
+    pub fn from_snapshot(snapshot: &Snapshot) -> Self {
+        Self {
+            rule: snapshot.rule,
+            topology: snapshot.map.topology(),
+            generation: snapshot.generation,
+            status: snapshot.status.clone(),
+            speed: snapshot.speed,
+            on_stable: snapshot.on_stable,
+            soup: snapshot.soup,
+            width: snapshot.map.width(),
+            height: snapshot.map.height(),
+            cells: Pattern::new(snapshot.map.alive_cells()),
+            ..Self::default()
+        }
+    }
+
+    /// Clears `map`, sets its topology and places cells where they were, returns cells
+    /// which didn't fit.
+    pub fn place_on(&self, map: &mut Map) -> usize {
+        map.clear();
+        map.set_topology(self.topology);
+        self.cells.place_on(map, 0, 0);
+        self.cells.population() - map.population()
+    }
+
+    /// Header and version line followed by one `key value` line per field, cells as
+    /// offset of their bounding box and its rows.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\nversion {}\n", FILE_HEADER, SESSION_VERSION);
+        text.push_str(&format!("rule {}\n", self.rule));
+        text.push_str(&format!("topology {}\n", self.topology));
+        text.push_str(&format!("generation {}\n", self.generation));
+        text.push_str(&format!("status {}\n", status_name(&self.status)));
+        text.push_str(&format!("speed {}\n", self.speed));
+        text.push_str(&format!("on-stable {}\n", self.on_stable));
+        if let Some(soup) = self.soup {
+            text.push_str(&format!(
+                "soup {} {}x{} {} {}\n",
+                soup.seed, soup.width, soup.height, soup.symmetry, soup.density
+            ));
+        }
+        text.push_str(&format!("board {}x{}\n", self.width, self.height));
+        let (cells, (x, y)) = self.cells.normalized();
+        if !cells.is_empty() {
+            text.push_str(&format!("cells {} {} {}\n", x, y, cells.to_rows()));
+        }
+        text.push_str(&format!("census {}\n", on_off(self.show_census)));
+        text.push_str(&format!("rules {}\n", on_off(self.show_rules)));
+        let poster = &self.svg.poster;
+        text.push_str(&format!("poster-scale {}\n", poster.scale));
+        text.push_str(&format!("poster-grid {}\n", on_off(poster.grid)));
+        text.push_str(&format!("theme {}\n", poster.theme));
+        text.push_str(&format!("transparent {}\n", on_off(poster.transparent)));
+        text.push_str(&format!("svg-shape {}\n", self.svg.shape));
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut fields = Vec::new();
+        let mut version = None;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let field = Field {
+                line: number + 1,
+                key: key.to_string(),
+                value: value.trim().to_string(),
+            };
+            if version.is_none() {
+                if field.key != "version" {
+                    return Err(format!(
+                        "Line {}: expected \"version N\" before {:?}",
+                        field.line, field.key
+                    ));
+                }
+                version = Some(field.value.parse::<u32>().map_err(|_| {
+                    format!("Line {}: invalid version {:?}", field.line, field.value)
+                })?);
+                continue;
+            }
+            fields.push(field);
+        }
+        let version = version.ok_or_else(|| "Session has no version line".to_string())?;
+        if version == 0 || version > SESSION_VERSION {
+            return Err(format!(
+                "Session version {} is not supported, this build reads versions 1 to {}",
+                version, SESSION_VERSION
+            ));
+        }
+        migrate(&mut fields, version, MIGRATIONS);
+        let mut session = Self::default();
+        for field in &fields {
+            session
+                .set(field)
+                .map_err(|e| format!("Line {}: {}", field.line, e))?;
+        }
+        Ok(session)
+    }
+
+    fn set(&mut self, field: &Field) -> Result<(), String> {
+        let value = field.value.as_str();
+        match field.key.as_str() {
+            "rule" => self.rule = value.parse()?,
+            "topology" => self.topology = value.parse()?,
+            "generation" => {
+                self.generation = value
+                    .parse()
+                    .map_err(|_| format!("invalid generation {:?}", value))?
+            }
+            "status" => {
+                self.status = match value {
+                    "running" => AppStatus::Running,
+                    "paused" => AppStatus::Paused,
+                    _ => return Err(format!("unknown status {:?}", value)),
+                }
+            }
+            "speed" => {
+                self.speed = SPEEDS
+                    .iter()
+                    .find(|speed| speed.to_string() == value)
+                    .cloned()
+                    .ok_or_else(|| format!("unknown speed {:?}", value))?
+            }
+            "on-stable" => self.on_stable = value.parse()?,
+            "soup" => self.soup = Some(parse_soup(value)?),
+            "board" => {
+                let (width, height) = parse_size(value)?;
+                self.width = width;
+                self.height = height;
+            }
+            "cells" => {
+                let items: Vec<&str> = value.splitn(3, ' ').collect();
+                match items[..] {
+                    [x, y, rows] => match (x.parse(), y.parse()) {
+                        (Ok(x), Ok(y)) => self.cells = Pattern::from_rows(rows).translated(x, y),
+                        _ => return Err(format!("invalid cells offset {} {}", x, y)),
+                    },
+                    _ => return Err(format!("expected \"cells x y rows\", found {:?}", value)),
+                }
+            }
+            "census" => self.show_census = parse_on_off(value)?,
+            "rules" => self.show_rules = parse_on_off(value)?,
+            "poster-scale" => {
+                self.svg.poster.scale = value
+                    .parse()
+                    .ok()
+                    .filter(|scale| *scale >= 1)
+                    .ok_or_else(|| format!("invalid poster scale {:?}", value))?
+            }
+            "poster-grid" => self.svg.poster.grid = parse_on_off(value)?,
+            "theme" => self.svg.poster.theme = value.parse()?,
+            "transparent" => self.svg.poster.transparent = parse_on_off(value)?,
+            "svg-shape" => self.svg.shape = value.parse()?,
+            key => return Err(format!("unknown key {:?}", key)),
+        }
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let text = fs::read_to_string(path.as_ref())
+            .map_err(|e| format!("Can't read {}: {}", path.as_ref().display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.as_ref().display(), e))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        fs::write(path.as_ref(), self.to_text())
+            .map_err(|e| format!("Can't write {}: {}", path.as_ref().display(), e))
+    }
+}
+
+fn status_name(status: &AppStatus) -> &'static str {
+    match status {
+        AppStatus::Running => "running",
+        AppStatus::Paused => "paused",
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+fn parse_on_off(value: &str) -> Result<bool, String> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(format!("expected on or off, found {:?}", value)),
+    }
+}
+
+fn parse_size(value: &str) -> Result<(i32, i32), String> {
+    value
+        .split_once('x')
+        .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+        .filter(|(width, height)| *width > 0 && *height > 0)
+        .ok_or_else(|| format!("invalid size {:?}, expected WIDTHxHEIGHT", value))
+}
+
+/// `seed WIDTHxHEIGHT symmetry density`
+fn parse_soup(value: &str) -> Result<Soup, String> {
+    let items: Vec<&str> = value.split_whitespace().collect();
+    match items[..] {
+        [seed, size, symmetry, density] => {
+            let (width, height) = parse_size(size)?;
+            let seed = seed
+                .parse()
+                .map_err(|_| format!("invalid soup seed {:?}", seed))?;
+            let density = density
+                .parse()
+                .map_err(|_| format!("invalid soup density {:?}", density))?;
+            Ok(Soup::new(seed, density, width, height, symmetry.parse()?))
+        }
+        _ => Err(format!(
+            "expected \"soup seed WIDTHxHEIGHT symmetry density\", found {:?}",
+            value
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::screenshot::Theme;
+    use crate::session::*;
+    use crate::soup::Symmetry;
+    use crate::svg::CellShape;
+
+    #[test]
+    fn it_restores_saved_session() {
+        let session = Session {
+            rule: "B36/S23".parse().unwrap(),
+            topology: Topology::Torus,
+            generation: 120,
+            status: AppStatus::Running,
+            speed: SPEEDS[9],
+            on_stable: OnStable::Reseed,
+            soup: Some(Soup::new(42, 0.35, 12, 12, Symmetry::D4)),
+            cells: Pattern::from_rows(".O./..O/OOO").translated(5, 7),
+            show_census: true,
+            svg: Svg::new(Poster::new(6).with_grid(true).with_theme(Theme::Dark))
+                .with_shape(CellShape::Circle),
+            ..Session::default()
+        };
+        assert_eq!(Session::parse(&session.to_text()), Ok(session.clone()));
+        let mut map = Map::new();
+        assert_eq!(session.place_on(&mut map), 0);
+        assert_eq!(
+            map.alive_cells(),
+            vec![(6, 7), (7, 8), (5, 9), (6, 9), (7, 9)]
+        );
+    }
+
+    #[test]
+    fn it_reads_version_1_files() {
+        // Written by the first version, must keep loading as the format evolves
+        let text = "# game_of_life session\nversion 1\nrule B3/S23\ntopology bounded\n\
+                    generation 7\nstatus paused\nspeed 2 gen/s\non-stable pause\n\
+                    board 20x20\ncells 1 2 OO/OO\ncensus off\nrules on\n";
+        let session = Session::parse(text).unwrap();
+        assert_eq!(session.generation, 7);
+        assert_eq!(session.on_stable, OnStable::Pause);
+        assert_eq!(session.cells, Pattern::from_rows("OO/OO").translated(1, 2));
+        assert!(session.show_rules);
+        assert_eq!(
+            Session::parse("version 9\nrule B3/S23"),
+            Err("Session version 9 is not supported, this build reads versions 1 to 1".to_string())
+        );
+        assert_eq!(
+            Session::parse("version 1\ncamera 0 0"),
+            Err("Line 2: unknown key \"camera\"".to_string())
+        );
+    }
+
+    #[test]
+    fn it_migrates_fields_from_older_versions() {
+        // Signature of every migration, even of those which don't add or remove fields
+        #[allow(clippy::ptr_arg)]
+        fn rename_grid(fields: &mut Vec<Field>) {
+            for field in fields.iter_mut().filter(|field| field.key == "grid") {
+                field.key = "poster-grid".to_string();
+            }
+        }
+        fn drop_camera(fields: &mut Vec<Field>) {
+            fields.retain(|field| field.key != "camera");
+        }
+        let migrations: &[fn(&mut Vec<Field>)] = &[rename_grid, drop_camera];
+        let field = |key: &str, value: &str| Field {
+            line: 2,
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        let mut fields = vec![field("grid", "on"), field("camera", "0 0")];
+        migrate(&mut fields, 1, migrations);
+        assert_eq!(fields, vec![field("poster-grid", "on")]);
+        let mut fields = vec![field("grid", "on"), field("camera", "0 0")];
+        migrate(&mut fields, 2, migrations);
+        assert_eq!(fields, vec![field("grid", "on")]);
+        let mut fields = vec![field("grid", "on")];
+        migrate(&mut fields, 3, migrations);
+        assert_eq!(fields, vec![field("grid", "on")]);
+    }
+}
@@ -0,0 +1,796 @@
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::app::{SECOND, TICKS_PER_SECOND};
+use crate::app_state::AppStatus;
+use crate::cell_state::CellState;
+use crate::census::{Census, DEFAULT_INTERACTION_DISTANCE};
+use crate::map::{Map, Topology};
+use crate::methuselah::{self, Lifespan, DEFAULT_MAX_GENERATIONS};
+use crate::pattern::Pattern;
+use crate::pattern_file::{PatternFile, PatternFormat};
+use crate::predecessor::{
+    Predecessor, PredecessorSearch, DEFAULT_CONFLICT_LIMIT, DEFAULT_PARENT_MARGIN,
+};
+use crate::rule::Rule;
+use crate::session::Session;
+use crate::soup::{Soup, Symmetry, DEFAULT_DENSITY};
+use crate::speed::{Speed, SpeedControl};
+use crate::stability::{CycleDetector, OnStable, Stability};
+use crate::statistics::{
+    BoundingBox, ExportFormat, GenerationStats, Statistics, RECENT_GENERATIONS,
+};
+use crate::svg::board_area;
+
+/// Requests sent from UI to simulation thread, applied between generations.
+#[derive(Debug, Clone)]
+pub enum SimCommand {
+    Run,
+    Pause,
+    Step,
+    JumpTo(u64),
+    SetCell(i32, i32, CellState),
+    SetRule(Rule),
+    SetTopology(Topology),
+    SetOnStable(OnStable),
+    Faster,
+    Slower,
+    /// Writes statistics of every generation to file, format is taken from extension.
+    ExportStatistics(String),
+    /// Identifies objects on current board.
+    TakeCensus,
+    /// Takes census and writes it as text to file.
+    ExportCensus(String),
+    /// Replaces board with soup and starts counting generations again.
+    LoadSoup(Soup),
+    /// Replaces board with pattern placed in its middle and starts counting generations again.
+    LoadPattern(Pattern),
+    /// Adds pattern to the board with its top left corner at given cell.
+    PlacePattern(Pattern, i32, i32),
+    /// Replaces cells of area and its margin with pattern evolving into them in one
+    /// generation together with the rest of the board, searched on worker thread.
+    FindParent(BoundingBox),
+    /// Writes board with current rule to pattern file, format is taken from extension.
+    ExportPattern(String),
+    /// Replaces board and everything about simulation with saved session.
+    LoadSession(Box<Session>),
+    /// Evolves copy of board on infinite plane on worker thread until it settles, lifespan
+    /// is reported once measured.
+    MeasureLifespan,
+    Shutdown,
+}
+
+/// Parent search on worker thread, with board it started on.
+struct ParentSearch {
+    result: Receiver<Predecessor>,
+    area: BoundingBox,
+    target: Pattern,
+    map: Arc<Map>,
+}
+
+/// Fast forward to given generation without publishing intermediate ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Jump {
+    pub from: u64,
+    pub to: u64,
+}
+
+impl Jump {
+    pub fn progress(&self, generation: u64) -> f64 {
+        (generation - self.from) as f64 / (self.to - self.from) as f64
+    }
+}
+
+/// Immutable state of simulation published for rendering.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub map: Arc<Map>,
+    pub generation: u64,
+    pub status: AppStatus,
+    pub speed: Speed,
+    pub rule: Rule,
+    pub jump: Option<Jump>,
+    pub stability: Stability,
+    pub on_stable: OnStable,
+    pub recent_stats: Vec<GenerationStats>,
+    pub census: Option<Arc<Census>>,
+    pub soup: Option<Soup>,
+    /// Outcome of last command worth showing to user.
+    pub message: Option<String>,
+    pub commands_processed: u64,
+}
+
+pub struct Simulation {
+    map: Arc<Map>,
+    generation: u64,
+    status: AppStatus,
+    speed: SpeedControl,
+    rule: Rule,
+    jump: Option<Jump>,
+    detector: CycleDetector,
+    on_stable: OnStable,
+    statistics: Statistics,
+    census_distance: i32,
+    census: Option<Arc<Census>>,
+    soup: Option<Soup>,
+    message: Option<String>,
+    /// Result of measurement still running on worker thread.
+    lifespan: Option<Receiver<Lifespan>>,
+    parent: Option<ParentSearch>,
+    commands_processed: u64,
+    stopped: bool,
+}
+
+impl Simulation {
+    pub fn new(map: Map) -> Self {
+        Self {
+            map: Arc::new(map),
+            generation: 0,
+            status: AppStatus::Paused,
+            speed: SpeedControl::new(),
+            rule: Rule::conway(),
+            jump: None,
+            detector: CycleDetector::new(),
+            on_stable: OnStable::Continue,
+            statistics: Statistics::new(),
+            census_distance: DEFAULT_INTERACTION_DISTANCE,
+            census: None,
+            soup: None,
+            message: None,
+            lifespan: None,
+            parent: None,
+            commands_processed: 0,
+            stopped: false,
+        }
+    }
+
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    pub fn with_on_stable(mut self, on_stable: OnStable) -> Self {
+        self.on_stable = on_stable;
+        self
+    }
+
+    pub fn with_census_distance(mut self, distance: i32) -> Self {
+        self.census_distance = distance;
+        self
+    }
+
+    /// Continues saved session.
+    pub fn with_session(mut self, session: &Session) -> Self {
+        self.load_session(session);
+        self
+    }
+
+    /// Board filled with `soup`, reseeding draws new seeds with the same settings.
+    pub fn with_soup(mut self, soup: Soup) -> Self {
+        self.load_soup(soup);
+        self
+    }
+
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn stability(&self) -> Stability {
+        self.detector.stability()
+    }
+
+    pub fn statistics(&self) -> &Statistics {
+        &self.statistics
+    }
+
+    pub fn census(&self) -> Census {
+        Census::take(&self.map, &self.rule, self.generation, self.census_distance)
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            map: self.map.clone(),
+            generation: self.generation,
+            status: self.status.clone(),
+            speed: self.speed.speed(),
+            rule: self.rule,
+            jump: self.jump.clone(),
+            stability: self.detector.stability(),
+            on_stable: self.on_stable,
+            recent_stats: self.statistics.recent(RECENT_GENERATIONS),
+            census: self.census.clone(),
+            soup: self.soup,
+            message: self.message.clone(),
+            commands_processed: self.commands_processed,
+        }
+    }
+
+    pub fn handle(&mut self, command: SimCommand) {
+        self.commands_processed += 1;
+        match command {
+            SimCommand::Run => self.status = AppStatus::Running,
+            SimCommand::Pause => self.status = AppStatus::Paused,
+            SimCommand::Step => {
+                if self.status == AppStatus::Paused && self.jump.is_none() {
+                    self.next_generation();
+                }
+            }
+            SimCommand::JumpTo(target) => self.jump_to(target),
+            SimCommand::SetCell(x, y, CellState::Alive) => {
+                Arc::make_mut(&mut self.map).set_alive(x, y);
+                self.detector.reset();
+            }
+            SimCommand::SetCell(x, y, CellState::Dead) => {
+                Arc::make_mut(&mut self.map).set_dead(x, y);
+                self.detector.reset();
+            }
+            SimCommand::SetRule(rule) => {
+                self.rule = rule;
+                self.detector.reset();
+            }
+            SimCommand::SetTopology(topology) => {
+                Arc::make_mut(&mut self.map).set_topology(topology);
+                self.detector.reset();
+            }
+            SimCommand::SetOnStable(on_stable) => self.on_stable = on_stable,
+            SimCommand::Faster => self.speed.faster(),
+            SimCommand::Slower => self.speed.slower(),
+            SimCommand::ExportStatistics(path) => {
+                match self.statistics.save(&path, ExportFormat::from_path(&path)) {
+                    Ok(()) => println!("Statistics saved to {}", path),
+                    Err(e) => println!("{}", e),
+                }
+            }
+            SimCommand::TakeCensus => self.census = Some(Arc::new(self.census())),
+            SimCommand::ExportCensus(path) => {
+                let census = self.census();
+                match census.save(&path) {
+                    Ok(()) => println!("Census saved to {}", path),
+                    Err(e) => println!("{}", e),
+                }
+                self.census = Some(Arc::new(census));
+            }
+            SimCommand::ExportPattern(path) => {
+                let file = PatternFile::from_map(&self.map, &self.rule);
+                match file.save(&path, PatternFormat::from_path(&path)) {
+                    Ok(()) => println!("Pattern saved to {}", path),
+                    Err(e) => println!("{}", e),
+                }
+            }
+            SimCommand::LoadSoup(soup) => self.load_soup(soup),
+            SimCommand::LoadPattern(pattern) => {
+                let map = Arc::make_mut(&mut self.map);
+                map.clear();
+                pattern.place_centered_on(map);
+                self.soup = None;
+                self.restart();
+            }
+            SimCommand::PlacePattern(pattern, x, y) => {
+                pattern.place_on(Arc::make_mut(&mut self.map), x, y);
+                self.detector.reset();
+            }
+            SimCommand::FindParent(area) => self.find_parent(area),
+            SimCommand::LoadSession(session) => self.load_session(&session),
+            SimCommand::MeasureLifespan => self.measure_lifespan(),
+            SimCommand::Shutdown => self.stopped = true,
+        }
+    }
+
+    fn find_parent(&mut self, area: BoundingBox) {
+        if self.parent.is_some() {
+            self.message = Some("Still looking for parent".to_string());
+            return;
+        }
+        let target = Pattern::new(
+            self.map
+                .alive_cells()
+                .into_iter()
+                .filter(|(x, y)| area_contains(&area, *x, *y))
+                .collect(),
+        );
+        // Cells around the replaced region stay, so they take part in the search
+        let region = grown(&area, DEFAULT_PARENT_MARGIN);
+        let surroundings = Pattern::new(
+            self.map
+                .alive_cells()
+                .into_iter()
+                .filter(|(x, y)| !area_contains(&region, *x, *y))
+                .collect(),
+        );
+        let mut search = PredecessorSearch::new(self.rule)
+            .with_conflict_limit(DEFAULT_CONFLICT_LIMIT)
+            .with_surroundings(surroundings);
+        if self.map.topology() == Topology::Bounded {
+            search = search.with_bounds(board_area(&self.map));
+        }
+        let (sender, receiver) = channel();
+        let request = target.clone();
+        thread::Builder::new()
+            .name("parent".to_string())
+            .spawn(move || {
+                let _ = sender.send(search.find_in(&request, area));
+            })
+            .unwrap();
+        self.parent = Some(ParentSearch {
+            result: receiver,
+            area,
+            target,
+            map: self.map.clone(),
+        });
+        self.message = Some("Looking for parent".to_string());
+    }
+
+    /// Replaces region of the search with parent once found, returns whether search ended.
+    fn poll_parent(&mut self) -> bool {
+        let result = match self.parent.as_ref().map(|search| search.result.try_recv()) {
+            Some(Ok(result)) => result,
+            Some(Err(TryRecvError::Empty)) | None => return false,
+            Some(Err(TryRecvError::Disconnected)) => {
+                self.parent = None;
+                return false;
+            }
+        };
+        let search = self.parent.take().unwrap();
+        let message = match result {
+            _ if *search.map != *self.map => {
+                "Board changed while looking for parent, board is left as it was".to_string()
+            }
+            Predecessor::Found(parent) => {
+                let area = search.area;
+                let mut map = (*self.map).clone();
+                let region = grown(&area, DEFAULT_PARENT_MARGIN);
+                for y in region.min_y.max(0)..=region.max_y.min(map.height() - 1) {
+                    for x in region.min_x.max(0)..=region.max_x.min(map.width() - 1) {
+                        map.set_dead(x, y);
+                    }
+                }
+                let board = board_area(&map);
+                let on_board = parent
+                    .cells()
+                    .iter()
+                    .all(|(x, y)| area_contains(&board, *x, *y));
+                parent.place_on(&mut map, 0, 0);
+                // Wrapping of torus isn't part of the search, so the check covers it
+                let next = map.next_generation(&self.rule);
+                let returns = next
+                    .alive_cells()
+                    .into_iter()
+                    .filter(|(x, y)| area_contains(&area, *x, *y))
+                    .eq(search.target.cells().iter().cloned());
+                if !on_board {
+                    "Parent found but it doesn't fit on board, board is left as it was".to_string()
+                } else if !returns {
+                    "Parent found but cells around it change the next generation, board is left as it was"
+                        .to_string()
+                } else {
+                    self.map = Arc::new(map);
+                    self.soup = None;
+                    self.restart();
+                    format!("Parent found, {} cells", parent.population())
+                }
+            }
+            result => format!("Parent: {}", result),
+        };
+        println!("{}", message);
+        self.message = Some(message);
+        true
+    }
+
+    fn measure_lifespan(&mut self) {
+        if self.lifespan.is_some() {
+            self.message = Some("Still measuring lifespan".to_string());
+            return;
+        }
+        let pattern = Pattern::new(self.map.alive_cells());
+        let rule = self.rule;
+        let (sender, receiver) = channel();
+        thread::Builder::new()
+            .name("lifespan".to_string())
+            .spawn(move || {
+                let _ = sender.send(methuselah::measure(
+                    &pattern,
+                    &rule,
+                    DEFAULT_MAX_GENERATIONS,
+                ));
+            })
+            .unwrap();
+        self.lifespan = Some(receiver);
+        self.message = Some("Measuring lifespan on infinite plane".to_string());
+    }
+
+    /// Shows lifespan once measured, returns whether it was.
+    fn poll_lifespan(&mut self) -> bool {
+        let lifespan = match self.lifespan.as_ref().map(|receiver| receiver.try_recv()) {
+            Some(Ok(lifespan)) => lifespan,
+            Some(Err(TryRecvError::Empty)) | None => return false,
+            Some(Err(TryRecvError::Disconnected)) => {
+                self.lifespan = None;
+                return false;
+            }
+        };
+        self.lifespan = None;
+        // Edges of the board don't take part, so bounded or wrapped board may evolve differently
+        let message = format!("On infinite plane: {}", lifespan);
+        println!("{}", message);
+        print!("{}", lifespan.census.to_text());
+        self.message = Some(message);
+        true
+    }
+
+    fn jump_to(&mut self, target: u64) {
+        if target <= self.generation {
+            println!(
+                "Can't jump back from generation {} to {}",
+                self.generation, target
+            );
+            return;
+        }
+        self.jump = Some(Jump {
+            from: self.generation,
+            to: target,
+        });
+    }
+
+    /// Nothing changes until next command.
+    pub fn is_idle(&self) -> bool {
+        self.status == AppStatus::Paused && self.jump.is_none()
+    }
+
+    /// Worker thread will report back.
+    fn is_waiting(&self) -> bool {
+        self.lifespan.is_some() || self.parent.is_some()
+    }
+
+    /// Computes generations due in single tick, stops earlier when `budget` is exhausted.
+    pub fn tick(&mut self, budget: Duration) {
+        let count = match &self.jump {
+            Some(jump) => jump.to - self.generation,
+            None if self.status == AppStatus::Running => {
+                u64::from(self.speed.generations_for_tick())
+            }
+            None => 0,
+        };
+        let start = Instant::now();
+        for _ in 0..count {
+            self.next_generation();
+            if start.elapsed() >= budget {
+                break;
+            }
+        }
+        if let Some(jump) = &self.jump {
+            if self.generation >= jump.to {
+                self.jump = None;
+            }
+        }
+    }
+
+    /// Advances board by one generation and reacts when it settles.
+    /// Settled board is reseeded on the following call so its status can be seen first.
+    pub fn next_generation(&mut self) {
+        let settled = self.detector.stability().is_settled();
+        if settled && self.on_stable == OnStable::Reseed {
+            self.reseed();
+            return;
+        }
+        if self.detector.is_fresh() {
+            self.detector.observe(&self.map, self.generation);
+        }
+        if self.statistics.is_empty() {
+            self.statistics
+                .push(GenerationStats::measure(&self.map, &self.map, self.generation));
+        }
+        let previous = self.map.clone();
+        self.map = Arc::new(self.map.next_generation(&self.rule));
+        self.generation += 1;
+        self.statistics
+            .push(GenerationStats::measure(&previous, &self.map, self.generation));
+        let stability = self.detector.observe(&self.map, self.generation);
+        if !settled && stability.is_settled() && self.on_stable == OnStable::Pause {
+            self.status = AppStatus::Paused;
+            self.jump = None;
+        }
+    }
+
+    /// New soup with settings of the last one, generation count goes on.
+    fn reseed(&mut self) {
+        let soup = match self.soup {
+            Some(soup) => soup.with_seed(Soup::random_seed()),
+            None => Soup::new(
+                Soup::random_seed(),
+                DEFAULT_DENSITY,
+                self.map.width(),
+                self.map.height(),
+                Symmetry::C1,
+            ),
+        };
+        println!("Reseeding with {}", soup);
+        soup.fill(Arc::make_mut(&mut self.map));
+        self.soup = Some(soup);
+        self.detector.reset();
+    }
+
+    fn load_soup(&mut self, soup: Soup) {
+        soup.fill(Arc::make_mut(&mut self.map));
+        self.soup = Some(soup);
+        self.restart();
+    }
+
+    fn load_session(&mut self, session: &Session) {
+        let dropped = session.place_on(Arc::make_mut(&mut self.map));
+        if dropped > 0 {
+            println!("{} cells of session don't fit on board", dropped);
+        }
+        self.rule = session.rule;
+        self.on_stable = session.on_stable;
+        self.soup = session.soup;
+        self.restart();
+        self.generation = session.generation;
+        self.status = session.status.clone();
+        self.speed.set(session.speed);
+        // Restart dropped census of the previous board
+        if session.show_census {
+            self.census = Some(Arc::new(self.census()));
+        }
+    }
+
+    /// Forgets history of previous board.
+    fn restart(&mut self) {
+        self.generation = 0;
+        self.message = None;
+        self.lifespan = None;
+        self.jump = None;
+        self.detector.reset();
+        self.statistics = Statistics::new();
+        self.census = None;
+    }
+
+    pub fn spawn(self) -> SimulationHandle {
+        let (command_sender, command_receiver) = channel();
+        let (snapshot_sender, snapshot_receiver) = channel();
+        let initial = self.snapshot();
+        let thread = thread::Builder::new()
+            .name("simulation".to_string())
+            .spawn(move || self.run(command_receiver, snapshot_sender))
+            .unwrap();
+        SimulationHandle {
+            commands: command_sender,
+            snapshots: snapshot_receiver,
+            commands_sent: 0,
+            latest: initial,
+            thread: Some(thread),
+        }
+    }
+
+    fn run(mut self, commands: Receiver<SimCommand>, snapshots: Sender<Snapshot>) {
+        let timestep = Duration::new(0, SECOND / TICKS_PER_SECOND);
+        let mut next_tick = Instant::now();
+        while !self.stopped {
+            let command = if self.is_idle() && self.is_waiting() {
+                commands.recv_timeout(timestep)
+            } else if self.is_idle() {
+                commands.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            } else {
+                commands.recv_timeout(next_tick.saturating_duration_since(Instant::now()))
+            };
+            let mut changed = self.poll_lifespan() | self.poll_parent();
+            match command {
+                Ok(command) => {
+                    self.handle(command);
+                    while let Ok(command) = commands.try_recv() {
+                        self.handle(command);
+                    }
+                    changed = true;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            let now = Instant::now();
+            if !self.is_idle() && now >= next_tick {
+                let before = self.generation;
+                self.tick(timestep);
+                changed |= self.generation != before;
+                next_tick = (next_tick + timestep).max(now);
+            }
+            if changed && snapshots.send(self.snapshot()).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+fn area_contains(area: &BoundingBox, x: i32, y: i32) -> bool {
+    (area.min_x..=area.max_x).contains(&x) && (area.min_y..=area.max_y).contains(&y)
+}
+
+fn grown(area: &BoundingBox, margin: i32) -> BoundingBox {
+    BoundingBox {
+        min_x: area.min_x - margin,
+        min_y: area.min_y - margin,
+        max_x: area.max_x + margin,
+        max_y: area.max_y + margin,
+    }
+}
+
+/// UI side of simulation thread.
+pub struct SimulationHandle {
+    commands: Sender<SimCommand>,
+    snapshots: Receiver<Snapshot>,
+    commands_sent: u64,
+    latest: Snapshot,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SimulationHandle {
+    pub fn send(&mut self, command: SimCommand) {
+        self.commands_sent += 1;
+        if self.commands.send(command).is_err() {
+            println!("Simulation thread is gone");
+        }
+    }
+
+    /// Receives all published snapshots and keeps the newest one.
+    pub fn poll(&mut self) {
+        while let Ok(snapshot) = self.snapshots.try_recv() {
+            self.latest = snapshot;
+        }
+    }
+
+    pub fn snapshot(&self) -> &Snapshot {
+        &self.latest
+    }
+
+    /// Every command sent so far is visible in latest snapshot.
+    pub fn is_synchronized(&self) -> bool {
+        self.latest.commands_processed == self.commands_sent
+    }
+}
+
+impl Drop for SimulationHandle {
+    fn drop(&mut self) {
+        let _ = self.commands.send(SimCommand::Shutdown);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulation::*;
+
+    #[test]
+    fn it_steps_only_while_paused() {
+        let mut simulation = Simulation::new(Map::new());
+        simulation.handle(SimCommand::Step);
+        assert_eq!(simulation.snapshot().generation, 1);
+        simulation.handle(SimCommand::Run);
+        simulation.handle(SimCommand::Step);
+        assert_eq!(simulation.snapshot().generation, 1);
+    }
+
+    #[test]
+    fn it_jumps_to_generation() {
+        let mut simulation = Simulation::new(Map::new());
+        simulation.handle(SimCommand::JumpTo(500));
+        while !simulation.is_idle() {
+            simulation.tick(Duration::from_millis(10));
+        }
+        let snapshot = simulation.snapshot();
+        assert_eq!(snapshot.generation, 500);
+        assert_eq!(snapshot.jump, None);
+    }
+
+    #[test]
+    fn it_applies_commands_on_thread() {
+        let mut handle = Simulation::new(Map::new()).spawn();
+        handle.send(SimCommand::SetCell(4, 5, CellState::Alive));
+        handle.send(SimCommand::SetCell(5, 5, CellState::Alive));
+        handle.send(SimCommand::SetCell(6, 5, CellState::Alive));
+        handle.send(SimCommand::Step);
+        while !handle.is_synchronized() {
+            handle.poll();
+            thread::sleep(Duration::from_millis(1));
+        }
+        let snapshot = handle.snapshot();
+        assert_eq!(snapshot.generation, 1);
+        assert_eq!(snapshot.map.get_at(5, 4), CellState::Alive);
+        assert_eq!(snapshot.map.get_at(4, 5), CellState::Dead);
+    }
+
+    #[test]
+    fn it_replaces_selection_with_its_parent() {
+        let glider = Pattern::from_rows(".O./..O/OOO").translated(8, 8);
+        let mut map = Map::new();
+        glider.place_on(&mut map, 0, 0);
+        let mut simulation = Simulation::new(map);
+        simulation.handle(SimCommand::FindParent(BoundingBox {
+            min_x: 7,
+            min_y: 7,
+            max_x: 11,
+            max_y: 11,
+        }));
+        while !simulation.poll_parent() {
+            thread::sleep(Duration::from_millis(1));
+        }
+        let message = simulation.snapshot().message.unwrap();
+        assert!(message.starts_with("Parent found"), "{}", message);
+        simulation.handle(SimCommand::Step);
+        assert_eq!(Pattern::new(simulation.map().alive_cells()), glider);
+    }
+
+    #[test]
+    fn it_finds_parent_among_neighbours() {
+        // Block right next to the margin of selected glider
+        let glider = Pattern::from_rows(".O./..O/OOO").translated(8, 8);
+        let block = Pattern::from_rows("OO/OO").translated(13, 8);
+        let mut map = Map::new();
+        glider.place_on(&mut map, 0, 0);
+        block.place_on(&mut map, 0, 0);
+        let area = BoundingBox {
+            min_x: 8,
+            min_y: 8,
+            max_x: 10,
+            max_y: 10,
+        };
+        let mut simulation = Simulation::new(map);
+        simulation.handle(SimCommand::FindParent(area));
+        while !simulation.poll_parent() {
+            thread::sleep(Duration::from_millis(1));
+        }
+        let message = simulation.snapshot().message.unwrap();
+        assert!(message.starts_with("Parent found"), "{}", message);
+        simulation.handle(SimCommand::Step);
+        let selection: Vec<(i32, i32)> = simulation
+            .map()
+            .alive_cells()
+            .into_iter()
+            .filter(|(x, y)| area_contains(&area, *x, *y))
+            .collect();
+        assert_eq!(selection, glider.cells());
+    }
+
+    #[test]
+    fn it_measures_lifespan_on_worker_thread() {
+        let mut map = Map::new();
+        Pattern::from_rows("OOO").place_on(&mut map, 5, 5);
+        let mut simulation = Simulation::new(map);
+        simulation.handle(SimCommand::MeasureLifespan);
+        simulation.handle(SimCommand::MeasureLifespan);
+        assert_eq!(
+            simulation.snapshot().message.as_deref(),
+            Some("Still measuring lifespan")
+        );
+        while !simulation.poll_lifespan() {
+            thread::sleep(Duration::from_millis(1));
+        }
+        let message = simulation.snapshot().message.unwrap();
+        assert!(message.starts_with("On infinite plane: Lifespan 0"));
+        // Result of previous board is dropped
+        simulation.handle(SimCommand::MeasureLifespan);
+        simulation.handle(SimCommand::LoadPattern(Pattern::from_rows("OO/OO")));
+        assert!(!simulation.poll_lifespan());
+        assert_eq!(simulation.snapshot().message, None);
+    }
+
+    #[test]
+    fn it_shows_census_of_loaded_session() {
+        let session = Session {
+            cells: Pattern::from_rows("OO/OO").translated(3, 3),
+            show_census: true,
+            ..Session::default()
+        };
+        let mut simulation = Simulation::new(Map::new());
+        simulation.handle(SimCommand::TakeCensus);
+        simulation.handle(SimCommand::LoadSession(Box::new(session.clone())));
+        let census = simulation.snapshot().census.expect("census of loaded board");
+        assert!(census.to_text().contains("block"));
+        let simulation = Simulation::new(Map::new()).with_session(&session);
+        assert!(simulation.snapshot().census.is_some());
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+
+use crate::map::Map;
+use crate::pattern::Pattern;
+
+pub const DEFAULT_DENSITY: f64 = 0.5;
+
+/// Symmetry enforced on generated soup, named after apgsearch conventions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    /// No symmetry.
+    C1,
+    /// Unchanged by 180 degree rotation.
+    C2,
+    /// Unchanged by 90 degree rotation.
+    C4,
+    /// Mirrored left to right.
+    D2,
+    /// Mirrored left to right and top to bottom.
+    D4,
+    /// Unchanged by any rotation or reflection of a square.
+    D8,
+}
+
+pub const SYMMETRIES: [Symmetry; 6] = [
+    Symmetry::C1,
+    Symmetry::C2,
+    Symmetry::C4,
+    Symmetry::D2,
+    Symmetry::D4,
+    Symmetry::D8,
+];
+
+impl Symmetry {
+    pub fn next(self) -> Self {
+        let index = SYMMETRIES.iter().position(|s| *s == self).unwrap_or(0);
+        SYMMETRIES[(index + 1) % SYMMETRIES.len()]
+    }
+
+    /// Rotations need square region.
+    fn needs_square(self) -> bool {
+        self == Symmetry::C4 || self == Symmetry::D8
+    }
+
+    /// Every cell which must have the same state as `x`, `y` in `size` region.
+    fn orbit(self, x: i32, y: i32, width: i32, height: i32) -> Vec<(i32, i32)> {
+        let (mx, my) = (width - 1 - x, height - 1 - y);
+        let mut cells = match self {
+            Symmetry::C1 => vec![(x, y)],
+            Symmetry::C2 => vec![(x, y), (mx, my)],
+            Symmetry::C4 => vec![(x, y), (my, x), (mx, my), (y, mx)],
+            Symmetry::D2 => vec![(x, y), (mx, y)],
+            Symmetry::D4 => vec![(x, y), (mx, y), (x, my), (mx, my)],
+            Symmetry::D8 => vec![
+                (x, y),
+                (mx, y),
+                (x, my),
+                (mx, my),
+                (y, x),
+                (my, x),
+                (y, mx),
+                (my, mx),
+            ],
+        };
+        cells.sort();
+        cells.dedup();
+        cells
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for Symmetry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SYMMETRIES
+            .iter()
+            .find(|symmetry| symmetry.to_string().eq_ignore_ascii_case(s))
+            .cloned()
+            .ok_or_else(|| {
+                format!(
+                    "Unknown symmetry {:?}, expected C1, C2, C4, D2, D4 or D8",
+                    s
+                )
+            })
+    }
+}
+
+/// Random pattern fully described by its settings, the same seed gives the same soup.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Soup {
+    pub seed: u64,
+    pub density: f64,
+    pub width: i32,
+    pub height: i32,
+    pub symmetry: Symmetry,
+}
+
+impl Soup {
+    pub fn new(seed: u64, density: f64, width: i32, height: i32, symmetry: Symmetry) -> Self {
+        Self {
+            seed,
+            density,
+            width,
+            height,
+            symmetry,
+        }
+    }
+
+    /// Seeds short enough to be typed back.
+    pub fn random_seed() -> u64 {
+        rand::thread_rng().gen_range(0, 1_000_000_000)
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    /// Region filled by soup, rotational symmetries shrink it to a square.
+    pub fn size(&self) -> (i32, i32) {
+        if self.symmetry.needs_square() {
+            let side = self.width.min(self.height);
+            (side, side)
+        } else {
+            (self.width, self.height)
+        }
+    }
+
+    pub fn generate(&self) -> Pattern {
+        let mut rng = seeded_rng(self.seed);
+        let (width, height) = self.size();
+        let mut cells = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let orbit = self.symmetry.orbit(x, y, width, height);
+                // Only the smallest cell of each orbit draws, the rest copies it
+                if orbit[0] != (x, y) {
+                    continue;
+                }
+                if rng.gen_bool(self.density) {
+                    cells.extend(orbit);
+                }
+            }
+        }
+        Pattern::new(cells)
+    }
+
+    /// Clears `map` and places soup at its centre.
+    pub fn fill(&self, map: &mut Map) {
+        map.clear();
+        let (width, height) = self.size();
+        let pattern = self.generate();
+        pattern.place_on(
+            map,
+            (map.width() - width) / 2,
+            (map.height() - height) / 2,
+        );
+    }
+}
+
+impl fmt::Display for Soup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "seed {} ({}x{} {} density {})",
+            self.seed, self.width, self.height, self.symmetry, self.density
+        )
+    }
+}
+
+/// Generator fully determined by `seed`, stable across platforms and runs.
+pub fn seeded_rng(seed: u64) -> XorShiftRng {
+    let mut bytes = [0u8; 16];
+    let mut state = seed;
+    for chunk in bytes.chunks_mut(8) {
+        state = splitmix64(state);
+        chunk.copy_from_slice(&state.to_le_bytes());
+    }
+    XorShiftRng::from_seed(bytes)
+}
+
+fn splitmix64(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::soup::*;
+
+    #[test]
+    fn it_repeats_soup_for_seed() {
+        let soup = Soup::new(42, 0.5, 16, 16, Symmetry::C1);
+        assert_eq!(soup.generate(), soup.generate());
+        assert_ne!(soup.generate(), soup.with_seed(43).generate());
+        assert!(soup.generate().population() > 0);
+    }
+
+    #[test]
+    fn it_enforces_symmetry() {
+        for symmetry in SYMMETRIES.iter() {
+            let soup = Soup::new(7, 0.4, 12, 9, *symmetry);
+            let pattern = soup.generate();
+            let (width, height) = soup.size();
+            for (x, y) in pattern.cells() {
+                for (ox, oy) in symmetry.orbit(*x, *y, width, height) {
+                    assert!(pattern.contains(ox, oy), "{} at {},{}", symmetry, ox, oy);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_parses_symmetry() {
+        assert_eq!("d4".parse::<Symmetry>(), Ok(Symmetry::D4));
+        assert!("D3".parse::<Symmetry>().is_err());
+    }
+}
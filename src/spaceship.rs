@@ -0,0 +1,575 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::census::{classify, Classification};
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+
+pub const DEFAULT_SHIP_WIDTH: usize = 6;
+pub const MAX_SHIP_WIDTH: usize = 16;
+pub const DEFAULT_MAX_LENGTH: usize = 40;
+pub const DEFAULT_MAX_QUEUE: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShipSpeed {
+    C2,
+    C3,
+    C4,
+    C4Diagonal,
+}
+
+impl ShipSpeed {
+    pub fn period(self) -> usize {
+        match self {
+            ShipSpeed::C2 => 2,
+            ShipSpeed::C3 => 3,
+            ShipSpeed::C4 | ShipSpeed::C4Diagonal => 4,
+        }
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        self == ShipSpeed::C4Diagonal
+    }
+}
+
+impl fmt::Display for ShipSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShipSpeed::C4Diagonal => write!(f, "c/4 diagonal"),
+            speed => write!(f, "c/{} orthogonal", speed.period()),
+        }
+    }
+}
+
+impl FromStr for ShipSpeed {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "c/2" => Ok(ShipSpeed::C2),
+            "c/3" => Ok(ShipSpeed::C3),
+            "c/4" => Ok(ShipSpeed::C4),
+            "c/4d" | "c/4-diagonal" => Ok(ShipSpeed::C4Diagonal),
+            _ => Err(format!(
+                "Unknown speed {:?}, expected c/2, c/3, c/4 or c/4d",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShipSymmetry {
+    Asymmetric,
+    /// Mirrored left to right, odd or even by width.
+    Mirror,
+    /// Mirrored left to right after half of period, like c/2 spaceships of B3/S23.
+    Glide,
+}
+
+impl fmt::Display for ShipSymmetry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShipSymmetry::Asymmetric => write!(f, "asymmetric"),
+            ShipSymmetry::Mirror => write!(f, "mirror"),
+            ShipSymmetry::Glide => write!(f, "glide"),
+        }
+    }
+}
+
+impl FromStr for ShipSymmetry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "asymmetric" => Ok(ShipSymmetry::Asymmetric),
+            "mirror" => Ok(ShipSymmetry::Mirror),
+            "glide" => Ok(ShipSymmetry::Glide),
+            _ => Err(format!(
+                "Unknown symmetry {:?}, expected asymmetric, mirror or glide",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    /// Rows of the longest partial ship in queue.
+    pub depth: usize,
+    pub queue: usize,
+    pub expanded: u64,
+    pub found: usize,
+    /// Partial ships given up to keep queue within its limit.
+    pub dropped: u64,
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "depth {}, queue {}, expanded {}, found {}",
+            self.depth, self.queue, self.expanded, self.found
+        )?;
+        if self.dropped > 0 {
+            write!(f, ", dropped {}", self.dropped)?;
+        }
+        Ok(())
+    }
+}
+
+/// Partial ship, `rows` are the last `2 * period` rows which decide every extension.
+#[derive(Debug, Clone)]
+struct Node {
+    parent: Option<usize>,
+    row: u32,
+    rows: Vec<u32>,
+    index: usize,
+    started: bool,
+}
+
+/// Row extension search for spaceships in the style of gfind.
+///
+/// Rows of all phases are interleaved into one sequence, row `n` is row `n / period` of
+/// phase `n % period`. Ship moving one row per period makes row `n - period + 1` the
+/// successor of rows `n - 2 * period`, `n - period` and `n`, so every new row is limited by
+/// rows already placed. Diagonal ships are searched in a frame moving one column sideways
+/// per period. Rows are bit masks where column `x` is bit `x + 2`.
+///
+/// Partial ships ending with the same rows have the same completions, so they are searched
+/// once and only the first ship of such family is reported.
+pub struct ShipSearch {
+    rule: Rule,
+    transition: [bool; 512],
+    speed: ShipSpeed,
+    width: usize,
+    symmetry: ShipSymmetry,
+    max_length: usize,
+    max_queue: usize,
+    arena: Vec<Node>,
+    seen: HashSet<(Vec<u32>, usize)>,
+    found: Vec<Pattern>,
+    expanded: u64,
+    dropped: u64,
+}
+
+impl ShipSearch {
+    pub fn new(rule: &Rule, speed: ShipSpeed, width: usize) -> Self {
+        Self {
+            rule: *rule,
+            transition: rule.transition_table(),
+            speed,
+            width,
+            symmetry: ShipSymmetry::Asymmetric,
+            max_length: DEFAULT_MAX_LENGTH,
+            max_queue: DEFAULT_MAX_QUEUE,
+            arena: Vec::new(),
+            seen: HashSet::new(),
+            found: Vec::new(),
+            expanded: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Symmetric ships only, reflections are tied to orthogonal movement so they are ignored
+    /// for diagonal ships.
+    pub fn with_symmetry(mut self, symmetry: ShipSymmetry) -> Self {
+        if !self.speed.is_diagonal() {
+            self.symmetry = symmetry;
+        }
+        self
+    }
+
+    /// Longest ship searched, in rows of single phase.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    pub fn with_max_queue(mut self, max_queue: usize) -> Self {
+        self.max_queue = max_queue.max(1);
+        self
+    }
+
+    /// Partial ships dropped so far, search isn't exhaustive when there are any.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    fn period(&self) -> usize {
+        self.speed.period()
+    }
+
+    fn max_index(&self) -> usize {
+        (self.max_length + 2) * self.period()
+    }
+
+    /// Searches until `limit` ships are found or every ship up to maximal length was tried.
+    /// Reports progress after every breadth first level.
+    pub fn run<F: FnMut(&Progress)>(&mut self, limit: usize, mut progress: F) -> Vec<Pattern> {
+        let period = self.period();
+        self.arena = vec![Node {
+            parent: None,
+            row: 0,
+            rows: vec![0; 2 * period],
+            index: 2 * period,
+            started: false,
+        }];
+        self.seen.clear();
+        self.seen.insert((vec![0; 2 * period], 0));
+        self.found.clear();
+        self.expanded = 0;
+        self.dropped = 0;
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        queue.push_back(0);
+        let mut lookahead = period;
+        while !queue.is_empty() && self.found.len() < limit {
+            let depth = self.arena[queue[0]].index;
+            while let Some(&id) = queue.front() {
+                if self.arena[id].index != depth || self.found.len() >= limit {
+                    break;
+                }
+                queue.pop_front();
+                for child in self.expand(id) {
+                    queue.push_back(child);
+                }
+            }
+            if queue.len() > self.max_queue {
+                lookahead = self.deepen(&mut queue, lookahead, limit);
+            }
+            progress(&Progress {
+                depth: (depth + 1) / period,
+                queue: queue.len(),
+                expanded: self.expanded,
+                found: self.found.len(),
+                dropped: self.dropped,
+            });
+        }
+        self.found.clone()
+    }
+
+    /// Adds children of node to arena, completed ships are recorded instead.
+    fn expand(&mut self, id: usize) -> Vec<usize> {
+        let node = self.arena[id].clone();
+        if node.index >= self.max_index() {
+            return Vec::new();
+        }
+        self.expanded += 1;
+        let mut children = Vec::new();
+        for row in self.extensions(&node.rows, node.index) {
+            let started = node.started || row != 0;
+            let mut rows = node.rows[1..].to_vec();
+            rows.push(row);
+            // Leading empty rows are kept as other phases may start before phase 0
+            if started && rows.iter().all(|row| *row == 0) {
+                let mut path = self.path(id);
+                path.push(row);
+                self.record(&path);
+                continue;
+            }
+            if !self.seen.insert((rows.clone(), (node.index + 1) % self.period())) {
+                continue;
+            }
+            self.arena.push(Node {
+                parent: Some(id),
+                row,
+                rows,
+                index: node.index + 1,
+                started,
+            });
+            children.push(self.arena.len() - 1);
+        }
+        children
+    }
+
+    /// Drops queued nodes without descendants `lookahead` rows deeper, going deeper each time
+    /// until queue fits. Once lookahead reaches maximal length, the last nodes over the limit
+    /// are dropped. Arena is compacted to ancestors of what remains in queue.
+    fn deepen(&mut self, queue: &mut VecDeque<usize>, mut lookahead: usize, limit: usize) -> usize {
+        while queue.len() > self.max_queue / 2 && self.found.len() < limit {
+            let mut kept = VecDeque::new();
+            for &id in queue.iter() {
+                let node = self.arena[id].clone();
+                let mut path = self.path(id);
+                if self.lookahead(&node.rows, node.index, &mut path, lookahead, limit) {
+                    kept.push_back(id);
+                }
+            }
+            *queue = kept;
+            if lookahead * 2 >= self.max_index() {
+                break;
+            }
+            lookahead *= 2;
+        }
+        if queue.len() > self.max_queue {
+            self.dropped += (queue.len() - self.max_queue) as u64;
+            queue.truncate(self.max_queue);
+        }
+        self.compact(queue);
+        lookahead
+    }
+
+    /// Depth first search for any extension `depth` rows long, ships met on the way are
+    /// recorded.
+    fn lookahead(
+        &mut self,
+        rows: &[u32],
+        index: usize,
+        path: &mut Vec<u32>,
+        depth: usize,
+        limit: usize,
+    ) -> bool {
+        if depth == 0 {
+            return true;
+        }
+        if index >= self.max_index() || self.found.len() >= limit {
+            return false;
+        }
+        self.expanded += 1;
+        let mut extended = false;
+        for row in self.extensions(rows, index) {
+            let mut next = rows[1..].to_vec();
+            next.push(row);
+            path.push(row);
+            if next.iter().all(|row| *row == 0) && path.iter().any(|row| *row != 0) {
+                self.record(path);
+            } else if self.lookahead(&next, index + 1, path, depth - 1, limit) {
+                extended = true;
+            }
+            path.pop();
+            if extended {
+                break;
+            }
+        }
+        extended
+    }
+
+    fn compact(&mut self, queue: &mut VecDeque<usize>) {
+        let mut keep = vec![false; self.arena.len()];
+        for &id in queue.iter() {
+            let mut current = Some(id);
+            while let Some(id) = current {
+                if keep[id] {
+                    break;
+                }
+                keep[id] = true;
+                current = self.arena[id].parent;
+            }
+        }
+        let mut remap = vec![0; self.arena.len()];
+        let mut arena = Vec::new();
+        for (id, node) in self.arena.drain(..).enumerate() {
+            if keep[id] {
+                remap[id] = arena.len();
+                arena.push(Node {
+                    parent: node.parent.map(|parent| remap[parent]),
+                    ..node
+                });
+            }
+        }
+        self.arena = arena;
+        for id in queue.iter_mut() {
+            *id = remap[*id];
+        }
+        // States of dropped nodes can't be reached again from what is left
+        let period = self.period();
+        self.seen = self
+            .arena
+            .iter()
+            .map(|node| (node.rows.clone(), node.index % period))
+            .collect();
+    }
+
+    /// Rows placed from the root to node.
+    fn path(&self, id: usize) -> Vec<u32> {
+        let mut rows = Vec::new();
+        let mut current = Some(id);
+        while let Some(id) = current {
+            let node = &self.arena[id];
+            if node.parent.is_some() {
+                rows.push(node.row);
+            }
+            current = node.parent;
+        }
+        rows.reverse();
+        rows
+    }
+
+    /// Verifies candidate with regular stepper before keeping it.
+    fn record(&mut self, path: &[u32]) {
+        let period = self.period();
+        let mut rows = vec![0; 2 * period];
+        rows.extend_from_slice(path);
+        let mut cells = Vec::new();
+        for (y, row) in rows.iter().step_by(period).enumerate() {
+            for x in 0..self.width {
+                if row & (1 << (x + 2)) != 0 {
+                    cells.push((x as i32, y as i32));
+                }
+            }
+        }
+        let ship = Pattern::new(cells).normalized().0;
+        let (classification, key) = classify(&ship, &self.rule);
+        // Glide symmetric ships repeat after two periods
+        let verified = match classification {
+            Classification::Spaceship {
+                period: ship_period,
+                dx,
+                dy,
+            } => {
+                ship_period == dx as u64 * period as u64
+                    && dy == if self.speed.is_diagonal() { dx } else { 0 }
+            }
+            _ => false,
+        };
+        if verified && !self.found.contains(&key) {
+            self.found.push(key);
+        }
+    }
+
+    /// Every row which can follow `rows` ending just before row `index`.
+    fn extensions(&self, rows: &[u32], index: usize) -> Vec<u32> {
+        let period = self.period();
+        // Frame follows the ship when generation wraps to next period
+        let frame = if index % period != period - 1 {
+            Frame::Fixed
+        } else if self.speed.is_diagonal() {
+            Frame::Shifted
+        } else if self.symmetry == ShipSymmetry::Glide {
+            Frame::Mirrored
+        } else {
+            Frame::Fixed
+        };
+        let known = Known {
+            above: rows[0],
+            current: rows[period],
+            successor: rows[period + 1],
+            frame,
+        };
+        let mut extensions = Vec::new();
+        self.extend_columns(&known, 0, 0, &mut extensions);
+        extensions
+    }
+
+    /// Chooses columns left to right, successor of column `x - 1` is known once column `x`
+    /// is chosen.
+    fn extend_columns(&self, known: &Known, x: usize, row: u32, extensions: &mut Vec<u32>) {
+        if x == self.width {
+            if self.matches(known, row, x as i32 - 1) && self.matches(known, row, x as i32) {
+                extensions.push(row);
+            }
+            return;
+        }
+        let values: &[u32] = if self.symmetry == ShipSymmetry::Mirror && x >= self.width.div_ceil(2)
+        {
+            if row & (1 << (self.width - 1 - x + 2)) != 0 {
+                &[1]
+            } else {
+                &[0]
+            }
+        } else {
+            &[0, 1]
+        };
+        for value in values {
+            let row = row | (value << (x + 2));
+            if self.matches(known, row, x as i32 - 1) {
+                self.extend_columns(known, x + 1, row, extensions);
+            }
+        }
+    }
+
+    /// Whether cell in `column` of new row's predecessor evolves into the known successor.
+    fn matches(&self, known: &Known, below: u32, column: i32) -> bool {
+        let shift = column + 1;
+        let index = ((known.above >> shift) & 7)
+            | (((known.current >> shift) & 7) << 3)
+            | (((below >> shift) & 7) << 6);
+        let target = match known.frame {
+            Frame::Fixed => column,
+            Frame::Shifted => column + 1,
+            Frame::Mirrored => self.width as i32 - 1 - column,
+        };
+        let expected = (0..self.width as i32).contains(&target)
+            && known.successor & (1 << (target + 2)) != 0;
+        self.transition[index as usize] == expected
+    }
+}
+
+/// How successor row lies relative to rows it evolves from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frame {
+    Fixed,
+    /// Moved one column, diagonal ships.
+    Shifted,
+    /// Reflected left to right, glide symmetric ships.
+    Mirrored,
+}
+
+/// Rows deciding new row, see `ShipSearch`.
+struct Known {
+    above: u32,
+    current: u32,
+    successor: u32,
+    frame: Frame,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spaceship::*;
+
+    #[test]
+    fn it_finds_lightweight_spaceship() {
+        let ships = ShipSearch::new(&Rule::conway(), ShipSpeed::C2, 5)
+            .with_symmetry(ShipSymmetry::Glide)
+            .with_max_length(10)
+            .run(1, |_| {});
+        assert_eq!(ships.len(), 1);
+        assert_eq!(
+            ships[0].canonical(),
+            classify(&Pattern::from_rows(".O..O/O..../O...O/OOOO."), &Rule::conway()).1
+        );
+    }
+
+    #[test]
+    fn it_finds_glider_diagonally() {
+        let ships = ShipSearch::new(&Rule::conway(), ShipSpeed::C4Diagonal, 4)
+            .with_max_length(6)
+            .run(1, |_| {});
+        assert_eq!(
+            ships,
+            vec![classify(&Pattern::from_rows(".O./..O/OOO"), &Rule::conway()).1]
+        );
+    }
+
+    #[test]
+    fn it_finds_nothing_without_room() {
+        let mut search = ShipSearch::new(&Rule::conway(), ShipSpeed::C3, 4)
+            .with_symmetry(ShipSymmetry::Mirror)
+            .with_max_length(8)
+            .with_max_queue(16);
+        assert!(search.run(1, |_| {}).is_empty());
+    }
+
+    #[test]
+    fn it_keeps_queue_within_limit() {
+        let mut search = ShipSearch::new(&Rule::conway(), ShipSpeed::C2, 4)
+            .with_max_length(8)
+            .with_max_queue(4);
+        search.arena = vec![Node {
+            parent: None,
+            row: 0,
+            rows: vec![0; 4],
+            index: 4,
+            started: false,
+        }];
+        search.seen.insert((vec![1; 4], 0));
+        // Empty rows extend as far as any lookahead goes, so deepening can't prune them
+        let mut queue: VecDeque<usize> = vec![0; 10].into();
+        search.deepen(&mut queue, 2, 1);
+        assert_eq!(queue.len(), 4);
+        assert_eq!(search.dropped(), 6);
+        assert_eq!(search.arena.len(), 1);
+        assert_eq!(search.seen.len(), 1);
+    }
+}
@@ -0,0 +1,129 @@
+use std::fmt;
+
+use crate::app::TICKS_PER_SECOND;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Speed {
+    /// Fixed number of generations per second, may be fractional.
+    PerSecond(f64),
+    /// Fixed number of generations every tick.
+    PerFrame(u32),
+    /// As many generations as fit in a single tick.
+    Max,
+}
+
+pub const SPEEDS: [Speed; 14] = [
+    Speed::PerSecond(0.5),
+    Speed::PerSecond(1.0),
+    Speed::PerSecond(2.0),
+    Speed::PerSecond(4.0),
+    Speed::PerSecond(8.0),
+    Speed::PerSecond(15.0),
+    Speed::PerSecond(30.0),
+    Speed::PerSecond(60.0),
+    Speed::PerFrame(2),
+    Speed::PerFrame(4),
+    Speed::PerFrame(8),
+    Speed::PerFrame(16),
+    Speed::PerFrame(64),
+    Speed::Max,
+];
+
+/// Index in `SPEEDS` of 2 generations per second.
+pub const DEFAULT_SPEED: usize = 2;
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Speed::PerSecond(n) => write!(f, "{} gen/s", n),
+            Speed::PerFrame(n) => write!(f, "{} gen/frame", n),
+            Speed::Max => write!(f, "max"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SpeedControl {
+    index: usize,
+    credit: f64,
+}
+
+impl SpeedControl {
+    pub fn new() -> Self {
+        Self {
+            index: DEFAULT_SPEED,
+            credit: 0.0,
+        }
+    }
+
+    pub fn speed(&self) -> Speed {
+        SPEEDS[self.index]
+    }
+
+    /// Switches to `speed` if it is one of `SPEEDS`.
+    pub fn set(&mut self, speed: Speed) {
+        if let Some(index) = SPEEDS.iter().position(|known| *known == speed) {
+            self.index = index;
+        }
+        self.credit = 0.0;
+    }
+
+    pub fn faster(&mut self) {
+        if self.index + 1 < SPEEDS.len() {
+            self.index += 1;
+        }
+        self.credit = 0.0;
+    }
+
+    pub fn slower(&mut self) {
+        if self.index > 0 {
+            self.index -= 1;
+        }
+        self.credit = 0.0;
+    }
+
+    /// Number of generations that should be computed in the current tick.
+    /// `Speed::Max` returns `u32::MAX`, caller is expected to stop on its own time budget.
+    pub fn generations_for_tick(&mut self) -> u32 {
+        match self.speed() {
+            Speed::PerSecond(n) => {
+                self.credit += n / f64::from(TICKS_PER_SECOND);
+                // Small epsilon so rounding errors don't delay a generation by a whole tick.
+                let whole = (self.credit + 1e-9).floor();
+                self.credit -= whole;
+                whole as u32
+            }
+            Speed::PerFrame(n) => n,
+            Speed::Max => u32::MAX,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::TICKS_PER_SECOND;
+    use crate::speed::*;
+
+    #[test]
+    fn it_keeps_default_pace() {
+        let mut control = SpeedControl::new();
+        let total: u32 = (0..TICKS_PER_SECOND)
+            .map(|_| control.generations_for_tick())
+            .sum();
+        assert_eq!(total, 2);
+    }
+
+    #[test]
+    fn it_clamps_speed_steps() {
+        let mut control = SpeedControl::new();
+        for _ in 0..SPEEDS.len() {
+            control.slower();
+        }
+        assert_eq!(control.speed(), Speed::PerSecond(0.5));
+        for _ in 0..SPEEDS.len() {
+            control.faster();
+        }
+        assert_eq!(control.speed(), Speed::Max);
+        assert_eq!(control.generations_for_tick(), u32::MAX);
+    }
+}
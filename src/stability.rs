@@ -0,0 +1,344 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use crate::map::{Map, Topology};
+
+/// Generations remembered by detector, longer periods are not recognized.
+pub const MAX_HISTORY: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stability {
+    Evolving,
+    /// No alive cells since given generation.
+    Empty { since: u64 },
+    /// Board does not change since given generation.
+    Still { since: u64 },
+    /// Board repeats every `period` generations, first time at `since`.
+    Periodic { period: u64, since: u64 },
+    /// Board repeats shifted by `dx`, `dy` every `period` generations.
+    Moving {
+        period: u64,
+        since: u64,
+        dx: i32,
+        dy: i32,
+    },
+}
+
+impl Stability {
+    pub fn is_settled(&self) -> bool {
+        *self != Stability::Evolving
+    }
+
+    /// Generation board settled at.
+    pub fn since(&self) -> Option<u64> {
+        match self {
+            Stability::Evolving => None,
+            Stability::Empty { since }
+            | Stability::Still { since }
+            | Stability::Periodic { since, .. }
+            | Stability::Moving { since, .. } => Some(*since),
+        }
+    }
+}
+
+impl fmt::Display for Stability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stability::Evolving => write!(f, "evolving"),
+            Stability::Empty { since } => write!(f, "died out at {}", since),
+            Stability::Still { since } => write!(f, "still since {}", since),
+            Stability::Periodic { period, since } => {
+                write!(f, "period {} since {}", period, since)
+            }
+            Stability::Moving {
+                period,
+                since,
+                dx,
+                dy,
+            } => write!(f, "moving ({}, {})/{} since {}", dx, dy, period, since),
+        }
+    }
+}
+
+/// What simulation does once board settles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OnStable {
+    Continue,
+    Pause,
+    Reseed,
+}
+
+impl OnStable {
+    pub fn next(self) -> Self {
+        match self {
+            OnStable::Continue => OnStable::Pause,
+            OnStable::Pause => OnStable::Reseed,
+            OnStable::Reseed => OnStable::Continue,
+        }
+    }
+}
+
+impl fmt::Display for OnStable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OnStable::Continue => write!(f, "continue"),
+            OnStable::Pause => write!(f, "pause"),
+            OnStable::Reseed => write!(f, "reseed"),
+        }
+    }
+}
+
+impl FromStr for OnStable {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "continue" => Ok(OnStable::Continue),
+            "pause" => Ok(OnStable::Pause),
+            "reseed" => Ok(OnStable::Reseed),
+            _ => Err(format!(
+                "Unknown action {:?}, expected continue, pause or reseed",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Seen {
+    generation: u64,
+    origin: (i32, i32),
+}
+
+/// Remembers hashes of previous generations to find the first repeated one.
+#[derive(Debug, Clone)]
+pub struct CycleDetector {
+    exact: HashMap<u64, u64>,
+    shifted: HashMap<u64, Seen>,
+    order: VecDeque<(u64, u64)>,
+    stability: Stability,
+}
+
+impl CycleDetector {
+    pub fn new() -> Self {
+        Self {
+            exact: HashMap::new(),
+            shifted: HashMap::new(),
+            order: VecDeque::new(),
+            stability: Stability::Evolving,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.exact.clear();
+        self.shifted.clear();
+        self.order.clear();
+        self.stability = Stability::Evolving;
+    }
+
+    pub fn stability(&self) -> Stability {
+        self.stability
+    }
+
+    /// Nothing was observed since creation or last reset.
+    pub fn is_fresh(&self) -> bool {
+        self.order.is_empty() && !self.stability.is_settled()
+    }
+
+    /// Records `map` as given generation, returns stability once it's detected.
+    pub fn observe(&mut self, map: &Map, generation: u64) -> Stability {
+        if self.stability.is_settled() {
+            return self.stability;
+        }
+        let cells = map.alive_cells();
+        if cells.is_empty() {
+            self.stability = Stability::Empty { since: generation };
+            return self.stability;
+        }
+
+        let exact = hash_cells(&cells);
+        if let Some(first) = self.exact.get(&exact) {
+            let period = generation - first;
+            self.stability = if period == 1 {
+                Stability::Still { since: *first }
+            } else {
+                Stability::Periodic {
+                    period,
+                    since: *first,
+                }
+            };
+            return self.stability;
+        }
+
+        let origin = canonical_origin(map, &cells);
+        let shifted = hash_cells(&relative_cells(map, &cells, origin));
+        if let Some(seen) = self.shifted.get(&shifted) {
+            let (dx, dy) = displacement(map, seen.origin, origin);
+            self.stability = Stability::Moving {
+                period: generation - seen.generation,
+                since: seen.generation,
+                dx,
+                dy,
+            };
+            return self.stability;
+        }
+
+        self.exact.insert(exact, generation);
+        self.shifted.insert(
+            shifted,
+            Seen {
+                generation,
+                origin,
+            },
+        );
+        self.order.push_back((exact, shifted));
+        if self.order.len() > MAX_HISTORY {
+            if let Some((exact, shifted)) = self.order.pop_front() {
+                self.exact.remove(&exact);
+                self.shifted.remove(&shifted);
+            }
+        }
+        self.stability
+    }
+}
+
+fn hash_cells(cells: &[(i32, i32)]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    cells.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn relative_cells(map: &Map, cells: &[(i32, i32)], origin: (i32, i32)) -> Vec<(i32, i32)> {
+    let mut relative: Vec<(i32, i32)> = cells
+        .iter()
+        .map(|(x, y)| {
+            (
+                (x - origin.0).rem_euclid(map.width()),
+                (y - origin.1).rem_euclid(map.height()),
+            )
+        })
+        .collect();
+    relative.sort_by_key(|(x, y)| (*y, *x));
+    relative
+}
+
+/// Corner of the pattern which doesn't depend on its position.
+/// On torus pattern starts right after the widest empty band of rows and columns.
+fn canonical_origin(map: &Map, cells: &[(i32, i32)]) -> (i32, i32) {
+    let mut columns = vec![false; map.width() as usize];
+    let mut rows = vec![false; map.height() as usize];
+    for (x, y) in cells {
+        columns[*x as usize] = true;
+        rows[*y as usize] = true;
+    }
+    match map.topology() {
+        Topology::Bounded => (first_occupied(&columns), first_occupied(&rows)),
+        Topology::Torus => (after_widest_gap(&columns), after_widest_gap(&rows)),
+    }
+}
+
+fn first_occupied(occupied: &[bool]) -> i32 {
+    occupied.iter().position(|o| *o).unwrap_or(0) as i32
+}
+
+fn after_widest_gap(occupied: &[bool]) -> i32 {
+    let len = occupied.len();
+    let indices: Vec<usize> = (0..len).filter(|i| occupied[*i]).collect();
+    let mut best = (0, indices[0]);
+    for (k, current) in indices.iter().enumerate() {
+        let next = indices[(k + 1) % indices.len()];
+        let gap = (next + len - current - 1) % len;
+        if gap > best.0 {
+            best = (gap, next);
+        }
+    }
+    best.1 as i32
+}
+
+/// Shift from `from` to `to`, on torus the shortest one.
+fn displacement(map: &Map, from: (i32, i32), to: (i32, i32)) -> (i32, i32) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    match map.topology() {
+        Topology::Bounded => (dx, dy),
+        Topology::Torus => (wrap_shift(dx, map.width()), wrap_shift(dy, map.height())),
+    }
+}
+
+fn wrap_shift(d: i32, size: i32) -> i32 {
+    let d = d.rem_euclid(size);
+    if d > size / 2 {
+        d - size
+    } else {
+        d
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::*;
+    use crate::rule::Rule;
+    use crate::stability::*;
+
+    fn detect(mut map: Map, generations: u64) -> Stability {
+        let mut detector = CycleDetector::new();
+        for generation in 0..generations {
+            if detector.observe(&map, generation).is_settled() {
+                break;
+            }
+            map = map.next_generation(&Rule::conway());
+        }
+        detector.stability()
+    }
+
+    #[test]
+    fn it_detects_empty_board() {
+        let mut map = Map::new();
+        map.set_alive(5, 5);
+        assert_eq!(detect(map, 10), Stability::Empty { since: 1 });
+    }
+
+    #[test]
+    fn it_detects_still_life() {
+        let mut map = Map::new();
+        for (x, y) in &[(5, 5), (6, 5), (5, 6), (6, 6)] {
+            map.set_alive(*x, *y);
+        }
+        assert_eq!(detect(map, 10), Stability::Still { since: 0 });
+    }
+
+    #[test]
+    fn it_detects_oscillator() {
+        let mut map = Map::new();
+        for (x, y) in &[(4, 5), (5, 5), (6, 5)] {
+            map.set_alive(*x, *y);
+        }
+        assert_eq!(
+            detect(map, 10),
+            Stability::Periodic {
+                period: 2,
+                since: 0
+            }
+        );
+    }
+
+    #[test]
+    fn it_detects_glider_crossing_torus_edge() {
+        let mut map = Map::new();
+        map.set_topology(Topology::Torus);
+        for (x, y) in &[(18, 17), (19, 18), (17, 19), (18, 19), (19, 19)] {
+            map.set_alive(*x, *y);
+        }
+        assert_eq!(
+            detect(map, 100),
+            Stability::Moving {
+                period: 4,
+                since: 0,
+                dx: 1,
+                dy: 1
+            }
+        );
+    }
+}
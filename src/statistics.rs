@@ -0,0 +1,211 @@
+use std::fs;
+use std::path::Path;
+
+use crate::cell_state::CellState;
+use crate::map::Map;
+
+/// Generations kept for live graph, full series stays in simulation.
+pub const RECENT_GENERATIONS: usize = 180;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub min_x: i32,
+    pub min_y: i32,
+    pub max_x: i32,
+    pub max_y: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerationStats {
+    pub generation: u64,
+    pub population: usize,
+    pub births: usize,
+    pub deaths: usize,
+    pub bounding_box: Option<BoundingBox>,
+    pub density: f64,
+}
+
+impl GenerationStats {
+    /// Compares `current` board with the one it was computed from.
+    pub fn measure(previous: &Map, current: &Map, generation: u64) -> Self {
+        let mut births = 0;
+        let mut deaths = 0;
+        for y in 0..current.height() {
+            for x in 0..current.width() {
+                match (previous.get_at(x, y), current.get_at(x, y)) {
+                    (CellState::Dead, CellState::Alive) => births += 1,
+                    (CellState::Alive, CellState::Dead) => deaths += 1,
+                    _ => {}
+                }
+            }
+        }
+        let population = current.population();
+        Self {
+            generation,
+            population,
+            births,
+            deaths,
+            bounding_box: bounding_box(current),
+            density: population as f64 / f64::from(current.width() * current.height()),
+        }
+    }
+}
+
+pub fn bounding_box(map: &Map) -> Option<BoundingBox> {
+    map.alive_cells()
+        .iter()
+        .fold(None, |bounds: Option<BoundingBox>, (x, y)| {
+            Some(match bounds {
+                None => BoundingBox {
+                    min_x: *x,
+                    min_y: *y,
+                    max_x: *x,
+                    max_y: *y,
+                },
+                Some(b) => BoundingBox {
+                    min_x: b.min_x.min(*x),
+                    min_y: b.min_y.min(*y),
+                    max_x: b.max_x.max(*x),
+                    max_y: b.max_y.max(*y),
+                },
+            })
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    /// Guesses format from file extension, CSV unless it ends with `.json`.
+    pub fn from_path(path: &str) -> Self {
+        if path.to_lowercase().ends_with(".json") {
+            ExportFormat::Json
+        } else {
+            ExportFormat::Csv
+        }
+    }
+}
+
+/// Series of statistics for every computed generation.
+#[derive(Debug, Clone, Default)]
+pub struct Statistics {
+    series: Vec<GenerationStats>,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Self { series: Vec::new() }
+    }
+
+    pub fn push(&mut self, stats: GenerationStats) {
+        self.series.push(stats);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.series.is_empty()
+    }
+
+    pub fn recent(&self, count: usize) -> Vec<GenerationStats> {
+        let start = self.series.len().saturating_sub(count);
+        self.series[start..].to_vec()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv =
+            String::from("generation,population,births,deaths,min_x,min_y,max_x,max_y,density\n");
+        for stats in &self.series {
+            let bounds = match stats.bounding_box {
+                Some(b) => format!("{},{},{},{}", b.min_x, b.min_y, b.max_x, b.max_y),
+                None => ",,,".to_string(),
+            };
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                stats.generation, stats.population, stats.births, stats.deaths, bounds, stats.density
+            ));
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        let rows: Vec<String> = self
+            .series
+            .iter()
+            .map(|stats| {
+                let bounds = match stats.bounding_box {
+                    Some(b) => format!(
+                        "{{\"min_x\":{},\"min_y\":{},\"max_x\":{},\"max_y\":{}}}",
+                        b.min_x, b.min_y, b.max_x, b.max_y
+                    ),
+                    None => "null".to_string(),
+                };
+                format!(
+                    "  {{\"generation\":{},\"population\":{},\"births\":{},\"deaths\":{},\"bounding_box\":{},\"density\":{}}}",
+                    stats.generation, stats.population, stats.births, stats.deaths, bounds, stats.density
+                )
+            })
+            .collect();
+        format!("[\n{}\n]\n", rows.join(",\n"))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P, format: ExportFormat) -> Result<(), String> {
+        let content = match format {
+            ExportFormat::Csv => self.to_csv(),
+            ExportFormat::Json => self.to_json(),
+        };
+        fs::write(path.as_ref(), content)
+            .map_err(|e| format!("Can't write {}: {}", path.as_ref().display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::Map;
+    use crate::rule::Rule;
+    use crate::statistics::*;
+
+    fn blinker_statistics() -> Statistics {
+        let mut map = Map::new();
+        map.set_alive(4, 5);
+        map.set_alive(5, 5);
+        map.set_alive(6, 5);
+        let next = map.next_generation(&Rule::conway());
+        let mut statistics = Statistics::new();
+        statistics.push(GenerationStats::measure(&map, &next, 1));
+        statistics
+    }
+
+    #[test]
+    fn it_counts_births_and_deaths() {
+        let statistics = blinker_statistics();
+        let stats = &statistics.recent(1)[0];
+        assert_eq!(stats.population, 3);
+        assert_eq!(stats.births, 2);
+        assert_eq!(stats.deaths, 2);
+        assert_eq!(
+            stats.bounding_box,
+            Some(BoundingBox {
+                min_x: 5,
+                min_y: 4,
+                max_x: 5,
+                max_y: 6
+            })
+        );
+        assert_eq!(stats.density, 3.0 / 400.0);
+    }
+
+    #[test]
+    fn it_exports_series() {
+        let statistics = blinker_statistics();
+        assert_eq!(
+            statistics.to_csv(),
+            "generation,population,births,deaths,min_x,min_y,max_x,max_y,density\n1,3,2,2,5,4,5,6,0.0075\n"
+        );
+        assert_eq!(
+            statistics.to_json(),
+            "[\n  {\"generation\":1,\"population\":3,\"births\":2,\"deaths\":2,\"bounding_box\":{\"min_x\":5,\"min_y\":4,\"max_x\":5,\"max_y\":6},\"density\":0.0075}\n]\n"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use sdl2::pixels::Color;
+
+use crate::cell_state::CellState;
+use crate::map::Map;
+use crate::rule::Rule;
+use crate::screenshot::Poster;
+use crate::statistics::BoundingBox;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellShape {
+    /// Squares, runs of alive cells in a row are merged into one rectangle.
+    Square,
+    Circle,
+}
+
+impl fmt::Display for CellShape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CellShape::Square => write!(f, "square"),
+            CellShape::Circle => write!(f, "circle"),
+        }
+    }
+}
+
+impl FromStr for CellShape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "square" => Ok(CellShape::Square),
+            "circle" => Ok(CellShape::Circle),
+            _ => Err(format!(
+                "Unknown cell shape {:?}, expected square or circle",
+                s
+            )),
+        }
+    }
+}
+
+/// Vector image of board area laid out like `Poster`, one pixel of poster is one SVG unit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Svg {
+    pub poster: Poster,
+    pub shape: CellShape,
+}
+
+impl Svg {
+    pub fn new(poster: Poster) -> Self {
+        Self {
+            poster,
+            shape: CellShape::Square,
+        }
+    }
+
+    pub fn with_shape(mut self, shape: CellShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Image size of `area`, grid adds closing line on the right and bottom.
+    pub fn size(&self, area: &BoundingBox) -> (u32, u32) {
+        let border = self.poster.has_grid() as u32;
+        let scale = self.poster.scale;
+        (
+            (area.max_x - area.min_x + 1) as u32 * scale + border,
+            (area.max_y - area.min_y + 1) as u32 * scale + border,
+        )
+    }
+
+    /// Alive cells of `area` of `map`, with rule and generation noted in a comment.
+    pub fn to_text(&self, map: &Map, area: &BoundingBox, rule: &Rule, generation: u64) -> String {
+        let (width, height) = self.size(area);
+        let theme = self.poster.theme;
+        let mut cells = 0;
+        let mut shapes = String::new();
+        for y in area.min_y..=area.max_y {
+            let mut x = area.min_x;
+            while x <= area.max_x {
+                let length = (x..=area.max_x)
+                    .take_while(|x| map.get_at(*x, y) == CellState::Alive)
+                    .count() as i32;
+                if length == 0 {
+                    x += 1;
+                    continue;
+                }
+                cells += length;
+                let first = self.poster.cell_rect(x - area.min_x, y - area.min_y);
+                match self.shape {
+                    CellShape::Square => {
+                        let last = self
+                            .poster
+                            .cell_rect(x + length - 1 - area.min_x, y - area.min_y);
+                        shapes.push_str(&format!(
+                            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+                            first.left(),
+                            first.top(),
+                            last.right() - first.left(),
+                            first.height()
+                        ));
+                    }
+                    CellShape::Circle => {
+                        let radius = first.width() as f64 / 2.0;
+                        for index in 0..length {
+                            let left = first.left() + index * self.poster.scale as i32;
+                            shapes.push_str(&format!(
+                                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\"/>\n",
+                                left as f64 + radius,
+                                first.top() as f64 + radius,
+                                radius
+                            ));
+                        }
+                    }
+                }
+                x += length;
+            }
+        }
+
+        let mut text = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        text.push_str(&format!(
+            "<!-- game_of_life rule {}, generation {}, {} cells of {}x{} area at {},{} -->\n",
+            rule,
+            generation,
+            cells,
+            area.max_x - area.min_x + 1,
+            area.max_y - area.min_y + 1,
+            area.min_x,
+            area.min_y
+        ));
+        text.push_str(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+            width, height
+        ));
+        // Dead cells cover the whole background, grid lines are drawn over them
+        if !self.poster.transparent {
+            text.push_str(&format!(
+                "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                width,
+                height,
+                hex(theme.cell(CellState::Dead))
+            ));
+        }
+        let crisp = match self.shape {
+            CellShape::Square => " shape-rendering=\"crispEdges\"",
+            CellShape::Circle => "",
+        };
+        text.push_str(&format!(
+            "<g fill=\"{}\"{}>\n",
+            hex(theme.cell(CellState::Alive)),
+            crisp
+        ));
+        text.push_str(&shapes);
+        text.push_str("</g>\n");
+        if self.poster.has_grid() {
+            let scale = self.poster.scale;
+            let mut path = String::new();
+            for x in (0..width).step_by(scale as usize) {
+                path.push_str(&format!("M{}.5 0V{}", x, height));
+            }
+            for y in (0..height).step_by(scale as usize) {
+                path.push_str(&format!("M0 {}.5H{}", y, width));
+            }
+            text.push_str(&format!(
+                "<path d=\"{}\" stroke=\"{}\" shape-rendering=\"crispEdges\"/>\n",
+                path,
+                hex(theme.grid())
+            ));
+        }
+        text.push_str("</svg>\n");
+        text
+    }
+
+    pub fn save<P: AsRef<Path>>(
+        &self,
+        map: &Map,
+        area: &BoundingBox,
+        rule: &Rule,
+        generation: u64,
+        path: P,
+    ) -> Result<(), String> {
+        fs::write(path.as_ref(), self.to_text(map, area, rule, generation))
+            .map_err(|e| format!("Can't write {}: {}", path.as_ref().display(), e))
+    }
+}
+
+/// Every cell of `map`.
+pub fn board_area(map: &Map) -> BoundingBox {
+    BoundingBox {
+        min_x: 0,
+        min_y: 0,
+        max_x: map.width() - 1,
+        max_y: map.height() - 1,
+    }
+}
+
+fn hex(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pattern::Pattern;
+    use crate::screenshot::Theme;
+    use crate::svg::*;
+
+    #[test]
+    fn it_merges_runs_of_cells() {
+        let mut map = Map::new();
+        Pattern::from_rows("OOO./...O").place_on(&mut map, 5, 5);
+        let rule = Rule::conway();
+        let svg = Svg::new(Poster::new(4));
+        let text = svg.to_text(&map, &board_area(&map), &rule, 12);
+        assert!(text.contains(
+            "<!-- game_of_life rule B3/S23, generation 12, 4 cells of 20x20 area at 0,0 -->"
+        ));
+        assert!(text.contains("width=\"80\" height=\"80\" viewBox=\"0 0 80 80\""));
+        assert!(text.contains("<rect x=\"20\" y=\"20\" width=\"12\" height=\"4\"/>"));
+        assert!(text.contains("<rect x=\"32\" y=\"24\" width=\"4\" height=\"4\"/>"));
+        assert!(text.contains("fill=\"#dcdcdc\""));
+        assert!(!text.contains("<path"));
+        // Selection starts at its corner, grid lines around each of its 4x2 cells
+        let area = BoundingBox {
+            min_x: 5,
+            min_y: 5,
+            max_x: 8,
+            max_y: 6,
+        };
+        let poster = Poster::new(4)
+            .with_grid(true)
+            .with_theme(Theme::Print)
+            .with_transparent(true);
+        let svg = Svg::new(poster).with_shape(CellShape::Circle);
+        let text = svg.to_text(&map, &area, &rule, 12);
+        assert_eq!(svg.size(&area), (17, 9));
+        assert_eq!(text.matches("<circle").count(), 4);
+        assert!(text.contains("<circle cx=\"2.5\" cy=\"2.5\" r=\"1.5\"/>"));
+        assert!(text.contains(
+            "d=\"M0.5 0V9M4.5 0V9M8.5 0V9M12.5 0V9M16.5 0V9M0 0.5H17M0 4.5H17M0 8.5H17\""
+        ));
+        assert!(!text.contains("#ffffff"));
+    }
+}
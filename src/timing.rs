@@ -0,0 +1,123 @@
+use std::time::{Duration, Instant};
+
+/// Updates allowed in a single frame before remaining time is dropped,
+/// prevents spiral of death when updates are slower than real time.
+pub const MAX_UPDATES_PER_FRAME: u32 = 5;
+/// Longest frame taken into account, e.g. after window was dragged.
+const MAX_FRAME_TIME: Duration = Duration::from_millis(250);
+const MEASURE_WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameStats {
+    pub fps: f64,
+    pub generations_per_second: f64,
+}
+
+/// Fixed timestep accumulator and frame rate meter.
+#[derive(Debug, Clone)]
+pub struct FrameClock {
+    timestep: Duration,
+    last_frame: Instant,
+    accumulator: Duration,
+    window_start: Instant,
+    window_frames: u32,
+    window_generation: u64,
+    stats: FrameStats,
+}
+
+impl FrameClock {
+    pub fn new(timestep: Duration) -> Self {
+        let now = Instant::now();
+        Self {
+            timestep,
+            last_frame: now,
+            accumulator: Duration::new(0, 0),
+            window_start: now,
+            window_frames: 0,
+            window_generation: 0,
+            stats: FrameStats::default(),
+        }
+    }
+
+    /// Measures time since previous frame and returns number of fixed updates to run.
+    pub fn begin_frame(&mut self) -> u32 {
+        let now = Instant::now();
+        let delta = now - self.last_frame;
+        self.last_frame = now;
+        self.advance(delta)
+    }
+
+    fn advance(&mut self, delta: Duration) -> u32 {
+        self.accumulator += delta.min(MAX_FRAME_TIME);
+        let mut updates = 0;
+        while self.accumulator >= self.timestep {
+            self.accumulator -= self.timestep;
+            updates += 1;
+            if updates == MAX_UPDATES_PER_FRAME {
+                self.accumulator = Duration::new(0, 0);
+                break;
+            }
+        }
+        updates
+    }
+
+    /// Counts rendered frame, statistics are refreshed once per measure window.
+    pub fn end_frame(&mut self, generation: u64) {
+        self.window_frames += 1;
+        let elapsed = self.window_start.elapsed();
+        if elapsed < MEASURE_WINDOW {
+            return;
+        }
+        let seconds = elapsed.as_secs_f64();
+        self.stats = FrameStats {
+            fps: f64::from(self.window_frames) / seconds,
+            generations_per_second: generation.saturating_sub(self.window_generation) as f64
+                / seconds,
+        };
+        self.window_start = Instant::now();
+        self.window_frames = 0;
+        self.window_generation = generation;
+    }
+
+    /// Time left until next fixed update is due.
+    pub fn until_next_update(&self) -> Duration {
+        let since_last = self.last_frame.elapsed() + self.accumulator;
+        self.timestep
+            .checked_sub(since_last)
+            .unwrap_or_else(|| Duration::new(0, 0))
+    }
+
+    /// Forgets time spent waiting for events, nothing was simulated in the meantime.
+    pub fn skip_idle(&mut self) {
+        self.last_frame = Instant::now();
+        self.accumulator = Duration::new(0, 0);
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::timing::*;
+
+    #[test]
+    fn it_accumulates_partial_steps() {
+        let mut clock = FrameClock::new(Duration::from_millis(10));
+        assert_eq!(clock.advance(Duration::from_millis(4)), 0);
+        assert_eq!(clock.advance(Duration::from_millis(4)), 0);
+        assert_eq!(clock.advance(Duration::from_millis(4)), 1);
+        assert_eq!(clock.advance(Duration::from_millis(28)), 3);
+    }
+
+    #[test]
+    fn it_limits_updates_per_frame() {
+        let mut clock = FrameClock::new(Duration::from_millis(10));
+        assert_eq!(
+            clock.advance(Duration::from_millis(200)),
+            MAX_UPDATES_PER_FRAME
+        );
+        assert_eq!(clock.advance(Duration::from_millis(5)), 0);
+    }
+}